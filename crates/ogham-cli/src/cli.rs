@@ -1,6 +1,7 @@
 //! CLI argument definitions using clap derive.
 
-use clap::{Args, Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use ogham_compiler::pkg::{LockMode, VendorMode};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -25,6 +26,9 @@ pub enum Commands {
     /// Detect breaking changes against a reference
     Breaking(BreakingArgs),
 
+    /// List all schema changes between two references
+    Changelog(ChangelogArgs),
+
     /// Dump compiled IR as JSON (debug)
     Dump(DumpArgs),
 
@@ -32,14 +36,32 @@ pub enum Commands {
     Get(GetArgs),
 
     /// Install all dependencies for the current project
-    Install,
+    Install(InstallArgs),
+
+    /// Update dependency versions and commits
+    Update(UpdateArgs),
 
-    /// Update dependency versions
-    Update,
+    /// Add missing and remove unused requires in ogham.mod.yaml
+    Tidy,
 
     /// Copy dependencies into vendor/
     Vendor,
 
+    /// Inspect the resolved dependency graph
+    Deps(DepsArgs),
+
+    /// Inspect and prune the module cache in OGHAM_HOME
+    Cache(CacheArgs),
+
+    /// Build and install generator plugins
+    Plugin(PluginArgs),
+
+    /// Validate the module and publish it to a proxy or a directory
+    Publish(PublishArgs),
+
+    /// Validate the module and build its release archive without publishing
+    Pack(PackArgs),
+
 }
 
 // ── Command args ───────────────────────────────────────────────────────
@@ -49,6 +71,83 @@ pub struct CheckArgs {
     /// Project root directory
     #[arg(short, long, default_value = ".")]
     pub dir: PathBuf,
+
+    #[command(flatten)]
+    pub lock: LockArgs,
+
+    #[command(flatten)]
+    pub vendor: ModArgs,
+}
+
+/// How dependency resolution treats ogham.lock.yaml.
+#[derive(Args)]
+pub struct LockArgs {
+    /// Fail if ogham.mod.yaml and ogham.lock.yaml disagree
+    #[arg(long)]
+    pub locked: bool,
+
+    /// Like --locked, and never access the network or run git fetches
+    #[arg(long)]
+    pub frozen: bool,
+}
+
+impl LockArgs {
+    pub fn mode(&self) -> LockMode {
+        if self.frozen {
+            LockMode::Frozen
+        } else if self.locked {
+            LockMode::Locked
+        } else {
+            LockMode::Auto
+        }
+    }
+}
+
+/// Where compilation takes dependencies from.
+#[derive(Args)]
+pub struct ModArgs {
+    /// `vendor` to require vendor/, `mod` to ignore it (default: vendor/ when present)
+    #[arg(long = "mod", value_enum)]
+    pub source: Option<ModSource>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ModSource {
+    /// Dependencies from vendor/, checked against ogham.lock.yaml
+    Vendor,
+    /// Dependencies from the module cache
+    Mod,
+}
+
+impl ModArgs {
+    pub fn mode(&self) -> VendorMode {
+        match self.source {
+            None => VendorMode::Auto,
+            Some(ModSource::Vendor) => VendorMode::Vendor,
+            Some(ModSource::Mod) => VendorMode::Off,
+        }
+    }
+}
+
+#[derive(Args)]
+pub struct InstallArgs {
+    #[command(flatten)]
+    pub lock: LockArgs,
+}
+
+#[derive(Args)]
+pub struct UpdateArgs {
+    /// Modules to update (default: all dependencies)
+    pub modules: Vec<String>,
+
+    /// Print what would change without writing ogham.lock.yaml
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Compare old and new versions of updated dependencies and report
+    /// breaking changes to the types this module uses
+    #[arg(long)]
+    pub check_breaking: bool,
 }
 
 #[derive(Args)]
@@ -64,6 +163,37 @@ pub struct GenerateArgs {
     /// Skip breaking change check even if configured in ogham.mod.yaml
     #[arg(long)]
     pub skip_breaking: bool,
+
+    /// Report errors from plugins without failing; their files are still written
+    #[arg(long)]
+    pub keep_going: bool,
+
+    /// Overwrite existing files that were not generated by ogham
+    #[arg(long)]
+    pub force: bool,
+
+    /// Compare plugin output with the files on disk instead of writing it;
+    /// fails if anything is out of date
+    #[arg(long)]
+    pub check: bool,
+
+    /// Like --check, and print a unified diff of what would change
+    #[arg(long)]
+    pub diff: bool,
+
+    /// Always run plugins instead of replaying cached output
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Number of plugins to run at the same time (default: number of CPUs)
+    #[arg(short, long, default_value_t = default_jobs())]
+    pub jobs: usize,
+
+    #[command(flatten)]
+    pub lock: LockArgs,
+
+    #[command(flatten)]
+    pub vendor: ModArgs,
 }
 
 #[derive(Args)]
@@ -79,6 +209,47 @@ pub struct BreakingArgs {
     /// Nothing blocks; everything is logged
     #[arg(long, conflicts_with = "allow")]
     pub force: bool,
+
+    /// Output format (structured formats are written to stdout)
+    #[arg(long, value_enum, default_value_t = BreakingFormat::Text)]
+    pub format: BreakingFormat,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum BreakingFormat {
+    /// `error[B013]: ...` lines on stderr
+    Text,
+    /// JSON report with source locations on both sides
+    Json,
+    /// SARIF 2.1.0 for code scanning tools
+    Sarif,
+    /// GitHub Actions workflow commands (`::error file=...`)
+    Github,
+}
+
+#[derive(Args)]
+pub struct ChangelogArgs {
+    /// Old reference (git:v1.0.0, path, or module@version)
+    #[arg(long)]
+    pub from: String,
+
+    /// New reference (default: schemas in the current directory)
+    #[arg(long)]
+    pub to: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = ChangelogFormat::Markdown)]
+    pub format: ChangelogFormat,
+
+    /// Output file (default: stdout)
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ChangelogFormat {
+    Markdown,
+    Json,
 }
 
 #[derive(Args)]
@@ -92,9 +263,187 @@ pub struct DumpArgs {
     pub output: Option<PathBuf>,
 }
 
+#[derive(Args)]
+pub struct PublishArgs {
+    /// Module root directory
+    #[arg(short, long, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Proxy to upload to (default: first proxy in OGHAM_PROXY)
+    #[arg(long, conflicts_with = "out")]
+    pub proxy: Option<String>,
+
+    /// Write the archive to this directory instead of uploading
+    #[arg(short, long)]
+    pub out: Option<PathBuf>,
+
+    /// Skip the breaking change check against the previous version
+    #[arg(long)]
+    pub skip_breaking: bool,
+}
+
+#[derive(Args)]
+pub struct PackArgs {
+    /// Module root directory
+    #[arg(short, long, default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Output directory, laid out like proxy storage
+    #[arg(short, long, default_value = "dist")]
+    pub out: PathBuf,
+
+    /// Skip the breaking change check against the previous version
+    #[arg(long)]
+    pub skip_breaking: bool,
+}
+
+#[derive(Args)]
+pub struct DepsArgs {
+    #[command(subcommand)]
+    pub command: DepsCommand,
+}
+
+#[derive(Subcommand)]
+pub enum DepsCommand {
+    /// Print every module with its selected version and requirements
+    Graph(DepsGraphArgs),
+
+    /// Show every path by which a module is required
+    Why(DepsWhyArgs),
+}
+
+#[derive(Args)]
+pub struct DepsGraphArgs {
+    /// Output format (written to stdout)
+    #[arg(long, value_enum, default_value_t = GraphFormat::Text)]
+    pub format: GraphFormat,
+
+    #[command(flatten)]
+    pub lock: LockArgs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphFormat {
+    /// Indented tree
+    Text,
+    /// Modules and edges as JSON
+    Json,
+    /// Graphviz DOT
+    Dot,
+}
+
+#[derive(Args)]
+pub struct DepsWhyArgs {
+    /// Module path to explain
+    pub module: String,
+
+    #[command(flatten)]
+    pub lock: LockArgs,
+}
+
+#[derive(Args)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub command: CacheCommand,
+}
+
+#[derive(Subcommand)]
+pub enum CacheCommand {
+    /// Print every cached module version and git checkout
+    List,
+
+    /// Remove entries no project has used for a while
+    Prune(CachePruneArgs),
+
+    /// Remove every entry of a module, or the whole cache
+    Clean(CacheCleanArgs),
+
+    /// Re-hash every entry and compare with the hash taken when it was fetched
+    Verify,
+}
+
+#[derive(Args)]
+pub struct CachePruneArgs {
+    /// Remove entries unused for this long (e.g. 30d, 12h, 2w)
+    #[arg(long, value_parser = parse_duration)]
+    pub older_than: std::time::Duration,
+}
+
+#[derive(Args)]
+pub struct CacheCleanArgs {
+    /// Module path (default: everything)
+    pub module: Option<String>,
+}
+
+fn default_jobs() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// `<number><unit>` with unit `s`, `m`, `h`, `d` or `w`.
+pub fn parse_duration(s: &str) -> Result<std::time::Duration, String> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("invalid duration '{}': expected e.g. 30d", s))?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => return Err(format!("invalid duration '{}': unit must be s, m, h, d or w", s)),
+    };
+    let seconds = number
+        .checked_mul(seconds)
+        .ok_or_else(|| format!("invalid duration '{}': too large", s))?;
+    Ok(std::time::Duration::from_secs(seconds))
+}
+
+#[derive(Args)]
+pub struct PluginArgs {
+    #[command(subcommand)]
+    pub command: PluginCommand,
+}
+
+#[derive(Subcommand)]
+pub enum PluginCommand {
+    /// Fetch a plugin module, run its `plugin.build` and install the binary
+    Install(PluginInstallArgs),
+}
+
+#[derive(Args)]
+pub struct PluginInstallArgs {
+    /// Plugin module (e.g., github.com/org/go or github.com/org/go@v1.2.0);
+    /// without a version, the one the project requires
+    pub module: String,
+}
+
 #[derive(Args)]
 pub struct GetArgs {
     /// Dependency to add (e.g., github.com/org/database or github.com/org/database@2.1.0)
     pub dependency: String,
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("30s"), Ok(Duration::from_secs(30)));
+        assert_eq!(parse_duration("5m"), Ok(Duration::from_secs(5 * 60)));
+        assert_eq!(parse_duration("2h"), Ok(Duration::from_secs(2 * 60 * 60)));
+        assert_eq!(parse_duration("30d"), Ok(Duration::from_secs(30 * 24 * 60 * 60)));
+        assert_eq!(parse_duration("1w"), Ok(Duration::from_secs(7 * 24 * 60 * 60)));
+
+        assert!(parse_duration("3y").unwrap_err().contains("unit must be s, m, h, d or w"));
+        assert!(parse_duration("d").unwrap_err().contains("expected e.g. 30d"));
+        assert!(parse_duration("").unwrap_err().contains("expected e.g. 30d"));
+        assert_eq!(
+            parse_duration("40000000000000w").unwrap_err(),
+            "invalid duration '40000000000000w': too large"
+        );
+    }
+}
@@ -1,85 +1,87 @@
 //! `ogham breaking` — detect breaking changes against a reference.
 
-use crate::cli::BreakingArgs;
-use crate::cmd::generate::compile_project;
-use ogham_compiler::breaking::{self, Level};
+use crate::cli::{BreakingArgs, BreakingFormat};
+use crate::cmd::generate::{compile_project, compile_with_deps, for_each_member, open_workspace};
+use ogham_compiler::breaking::{self, Level, Renames, SourceLine, SourceLocations, Violation};
 use ogham_compiler::lower;
-use ogham_compiler::pipeline::{CompileOptions, SourceFile};
-use std::path::Path;
+use ogham_compiler::pipeline::{CompileOptions, CompileResult, SourceFile};
+use ogham_compiler::pkg::{self, LockMode};
+use ogham_proto::oghamproto::ir;
+use std::path::{Component, Path};
 use std::process::Command;
 
 pub fn run(args: BreakingArgs) -> Result<(), String> {
-    // Compile current schemas
     let dir = Path::new(".");
-    let (new_module, _, _) = compile_project(dir)?;
+    let text = matches!(args.format, BreakingFormat::Text);
 
-    // Load and compile old schemas
-    let old_sources = load_reference(&args.against, dir)?;
-    let old_result = ogham_compiler::pipeline::compile(&old_sources, &CompileOptions::default());
-
-    if old_result.diagnostics.has_errors() {
-        return Err(format!(
-            "failed to compile reference '{}': {} error(s)",
-            args.against,
-            old_result.diagnostics.errors().count()
-        ));
-    }
-
-    let old_package = old_sources
-        .first()
-        .and_then(|s| {
-            let parse = ogham_compiler::parser::parse(&s.content);
-            let root = ogham_compiler::ast::Root::cast(parse.syntax())?;
-            use ogham_compiler::ast::AstNode;
-            root.package_decl()
-                .and_then(|p| p.name())
-                .map(|t| t.text().to_string())
-        })
-        .unwrap_or_else(|| "default".to_string());
-
-    let old_module = lower::inflate(
-        &old_result.interner,
-        &old_result.arenas,
-        &old_result.symbols,
-        &old_package,
-        None,
-    );
+    // Compile current schemas — every member in a workspace — and compare
+    // them with the old ones
+    let mut reports = Vec::new();
+    let members = match open_workspace(dir)? {
+        Some(ws) => {
+            member_reference(&args.against, ".")?;
+            for_each_member(&ws, LockMode::Auto, |member, deps| {
+                let member_dir = Path::new(&member.path);
+                let against = member_reference(&args.against, &member.path)?;
+                let report = check(member_dir, &against, compile_with_deps(member_dir, deps, LockMode::Auto)?)?;
+                if text {
+                    print_text(&report.violations);
+                }
+                reports.push(report);
+                Ok(())
+            })
+        }
+        None => {
+            let report = check(dir, &args.against, compile_project(dir)?)?;
+            if text {
+                print_text(&report.violations);
+            }
+            reports.push(report);
+            Ok(())
+        }
+    };
 
-    // Compare
-    let violations = breaking::compare(&old_module, &new_module);
+    let violations: Vec<&Violation> = reports.iter().flat_map(|r| &r.violations).collect();
+    let errors = violations.iter().filter(|v| v.level == Level::Error).count();
+    let warnings = violations.iter().filter(|v| v.level == Level::Warning).count();
+    let infos = violations.iter().filter(|v| v.level == Level::Info).count();
 
-    if violations.is_empty() {
-        eprintln!("no breaking changes detected");
-        return Ok(());
-    }
+    if text {
+        if violations.is_empty() {
+            eprintln!("no breaking changes detected");
+            return members;
+        }
 
-    // Render violations
-    let mut errors = 0;
-    let mut warnings = 0;
-    let mut infos = 0;
+        eprintln!();
+        eprintln!(
+            "{} error(s), {} warning(s), {} info(s)",
+            errors, warnings, infos
+        );
+    } else {
+        // Locate each violation on both sides
+        let located: Vec<Located> = reports
+            .iter()
+            .flat_map(|r| {
+                r.violations.iter().map(|v| Located {
+                    violation: v,
+                    old: r.old.find(&r.renames.old_context(&v.context)),
+                    new: r.new.find(&v.context),
+                })
+            })
+            .collect();
 
-    for v in &violations {
-        match v.level {
-            Level::Error => {
-                errors += 1;
-                eprintln!("error[{}]: {}", v.code, v.message);
-            }
-            Level::Warning => {
-                warnings += 1;
-                eprintln!("warning[{}]: {}", v.code, v.message);
-            }
-            Level::Info => {
-                infos += 1;
-                eprintln!("info[{}]: {}", v.code, v.message);
+        match args.format {
+            BreakingFormat::Json => println!("{}", to_pretty(&to_json(&located, errors, warnings, infos))?),
+            BreakingFormat::Sarif => println!("{}", to_pretty(&to_sarif(&located))?),
+            BreakingFormat::Github => {
+                for l in &located {
+                    println!("{}", to_github(l));
+                }
             }
+            BreakingFormat::Text => unreachable!(),
         }
     }
-
-    eprintln!();
-    eprintln!(
-        "{} error(s), {} warning(s), {} info(s)",
-        errors, warnings, infos
-    );
+    members?;
 
     // Determine exit code based on flags
     if args.force {
@@ -105,14 +107,118 @@ pub fn run(args: BreakingArgs) -> Result<(), String> {
     }
 }
 
+/// Violations of one project against its reference, with declaration
+/// positions on both sides.
+struct Report {
+    violations: Vec<Violation>,
+    /// Old names of renamed declarations, for locating them in `old`
+    renames: Renames,
+    old: SourceLocations,
+    new: SourceLocations,
+}
+
+fn check(
+    dir: &Path,
+    against: &str,
+    (new_module, new_result, _): (ir::Module, CompileResult, String),
+) -> Result<Report, String> {
+    // Load and compile old schemas
+    let (old_module, old_result, old_sources) = compile_reference(against, dir)?;
+
+    // Compare, honouring `@tightens` declared by annotation libraries on either side
+    let mut rules = breaking::AnnotationRules::std();
+    rules.extend_from_hir(&old_result.interner, &old_result.arenas);
+    rules.extend_from_hir(&new_result.interner, &new_result.arenas);
+    let violations = breaking::compare_with_rules(&old_module, &new_module, &rules);
+
+    Ok(Report {
+        violations,
+        renames: breaking::renames(&old_module, &new_module),
+        old: SourceLocations::from_sources(&old_sources),
+        new: SourceLocations::from_sources(&load_from_dir(dir)?),
+    })
+}
+
+fn print_text(violations: &[Violation]) {
+    for v in violations {
+        eprintln!("{}[{}]: {}", v.level.as_str(), v.code, v.message);
+    }
+}
+
+/// The reference for one workspace member: git refs are looked up in the
+/// member's directory, and a path names an old copy of the whole workspace.
+fn member_reference(against: &str, member_path: &str) -> Result<String, String> {
+    if against.starts_with("git:") {
+        Ok(against.to_string())
+    } else if is_path_reference(against) {
+        Ok(Path::new(against).join(member_path).to_string_lossy().to_string())
+    } else {
+        Err(format!(
+            "'{}' names a single module; run `ogham breaking` in the member's directory to compare against it",
+            against
+        ))
+    }
+}
+
+/// Whether a reference names a directory, such as `./old`, `../sibling` or
+/// `/abs/dir`, rather than `module@version`.
+fn is_path_reference(against: &str) -> bool {
+    let path = Path::new(against);
+    path.is_absolute()
+        || matches!(path.components().next(), Some(Component::CurDir | Component::ParentDir))
+        || (!against.contains('@') && path.components().count() > 1)
+}
+
+/// Load a reference (`git:<ref>`, path, or `module@version`), compile it and
+/// inflate it to IR. The sources are returned for locating declarations.
+pub fn compile_reference(
+    against: &str,
+    dir: &Path,
+) -> Result<(ir::Module, CompileResult, Vec<SourceFile>), String> {
+    let old_sources = load_reference(against, dir)?;
+    let old_result = ogham_compiler::pipeline::compile(&old_sources, &CompileOptions::default());
+
+    if old_result.diagnostics.has_errors() {
+        return Err(format!(
+            "failed to compile reference '{}': {} error(s)",
+            against,
+            old_result.diagnostics.errors().count()
+        ));
+    }
+
+    let old_package = old_sources
+        .first()
+        .and_then(|s| {
+            let parse = ogham_compiler::parser::parse(&s.content);
+            let root = ogham_compiler::ast::Root::cast(parse.syntax())?;
+            use ogham_compiler::ast::AstNode;
+            root.package_decl()
+                .and_then(|p| p.name())
+                .map(|t| t.text().to_string())
+        })
+        .unwrap_or_else(|| "default".to_string());
+
+    let old_module = lower::inflate(
+        &old_result.interner,
+        &old_result.arenas,
+        &old_result.symbols,
+        &old_package,
+        None,
+    );
+
+    Ok((old_module, old_result, old_sources))
+}
+
 pub fn load_reference(against: &str, project_dir: &Path) -> Result<Vec<SourceFile>, String> {
     if let Some(git_ref) = against.strip_prefix("git:") {
         load_from_git(git_ref, project_dir)
-    } else if against.starts_with("./") || against.starts_with('/') {
+    } else if is_path_reference(against) {
         load_from_dir(Path::new(against))
+    } else if against.contains('@') {
+        load_from_dir(&pkg::fetch_module(against)?)
     } else {
         Err(format!(
-            "unsupported reference format: '{}'. Use git:<ref> or ./path/",
+            "unsupported reference format: '{}'. Use git:<ref>, ./path/ or module@version",
             against
         ))
     }
@@ -127,9 +233,10 @@ fn load_from_git(git_ref: &str, project_dir: &Path) -> Result<Vec<SourceFile>, S
         ""
     };
 
-    // List .ogham files at the ref
+    // List .ogham files at the ref, relative to the project directory
     let output = Command::new("git")
         .args(["ls-tree", "-r", "--name-only", git_ref])
+        .current_dir(project_dir)
         .output()
         .map_err(|e| format!("git error: {}", e))?;
 
@@ -151,13 +258,14 @@ fn load_from_git(git_ref: &str, project_dir: &Path) -> Result<Vec<SourceFile>, S
     let mut sources = Vec::new();
     for file_path in ogham_files {
         let output = Command::new("git")
-            .args(["show", &format!("{}:{}", git_ref, file_path)])
+            .args(["show", &format!("{}:./{}", git_ref, file_path)])
+            .current_dir(project_dir)
             .output()
             .map_err(|e| format!("git show error: {}", e))?;
 
         if output.status.success() {
             sources.push(SourceFile {
-                name: file_path.to_string(),
+                name: project_dir.join(file_path).to_string_lossy().to_string(),
                 content: String::from_utf8_lossy(&output.stdout).to_string(),
             });
         }
@@ -176,3 +284,131 @@ fn load_from_dir(dir: &Path) -> Result<Vec<SourceFile>, String> {
     let search_dir = if schemas_dir.is_dir() { &schemas_dir } else { dir };
     crate::cmd::generate::discover_ogham_files(search_dir)
 }
+
+// ── Structured output ──────────────────────────────────────────────────
+
+/// A violation with the declaration's position in the old and new schemas.
+struct Located<'a> {
+    violation: &'a Violation,
+    old: Option<&'a SourceLine>,
+    new: Option<&'a SourceLine>,
+}
+
+fn to_pretty(value: &serde_json::Value) -> Result<String, String> {
+    serde_json::to_string_pretty(value).map_err(|e| format!("failed to serialize report: {}", e))
+}
+
+/// Paths relative to the project root, as tools expect them.
+fn display_path(file: &str) -> &str {
+    file.strip_prefix("./").unwrap_or(file)
+}
+
+fn location_json(line: Option<&SourceLine>) -> serde_json::Value {
+    match line {
+        Some(l) => serde_json::json!({ "file": display_path(&l.file), "line": l.line }),
+        None => serde_json::Value::Null,
+    }
+}
+
+fn to_json(located: &[Located], errors: usize, warnings: usize, infos: usize) -> serde_json::Value {
+    let violations: Vec<serde_json::Value> = located
+        .iter()
+        .map(|l| {
+            serde_json::json!({
+                "level": l.violation.level.as_str(),
+                "code": l.violation.code,
+                "message": l.violation.message,
+                "context": l.violation.context,
+                "old": location_json(l.old),
+                "new": location_json(l.new),
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "violations": violations,
+        "summary": { "errors": errors, "warnings": warnings, "infos": infos },
+    })
+}
+
+fn sarif_location(line: &SourceLine) -> serde_json::Value {
+    serde_json::json!({
+        "physicalLocation": {
+            "artifactLocation": { "uri": display_path(&line.file) },
+            "region": { "startLine": line.line },
+        }
+    })
+}
+
+/// SARIF 2.1.0. The new schema is the primary location; the old one is
+/// attached as a related location (for removals it is the only one).
+fn to_sarif(located: &[Located]) -> serde_json::Value {
+    let mut rules: Vec<&str> = located.iter().map(|l| l.violation.code).collect();
+    rules.sort_unstable();
+    rules.dedup();
+
+    let results: Vec<serde_json::Value> = located
+        .iter()
+        .map(|l| {
+            let level = match l.violation.level {
+                Level::Error => "error",
+                Level::Warning => "warning",
+                Level::Info => "note",
+            };
+            let mut result = serde_json::json!({
+                "ruleId": l.violation.code,
+                "level": level,
+                "message": { "text": l.violation.message },
+                "locations": l.new.or(l.old).map(sarif_location).into_iter().collect::<Vec<_>>(),
+            });
+            if let (Some(_), Some(old)) = (l.new, l.old) {
+                let mut related = sarif_location(old);
+                related["message"] = serde_json::json!({ "text": "previous declaration" });
+                result["relatedLocations"] = serde_json::json!([related]);
+            }
+            result
+        })
+        .collect();
+
+    serde_json::json!({
+        "version": "2.1.0",
+        "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "ogham-breaking",
+                    "informationUri": "https://github.com/oghamlang/ogham",
+                    "rules": rules.iter().map(|code| serde_json::json!({ "id": code })).collect::<Vec<_>>(),
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// GitHub Actions workflow command, annotated at the new declaration when
+/// it still exists.
+fn to_github(l: &Located) -> String {
+    let command = match l.violation.level {
+        Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Info => "notice",
+    };
+    let mut params = Vec::new();
+    if let Some(line) = l.new.or(l.old) {
+        params.push(format!("file={}", display_path(&line.file)));
+        params.push(format!("line={}", line.line));
+    }
+    params.push(format!("title={}", l.violation.code));
+    format!(
+        "::{} {}::{}",
+        command,
+        params.join(","),
+        github_escape(&l.violation.message)
+    )
+}
+
+/// Escape data for a workflow command message.
+fn github_escape(s: &str) -> String {
+    s.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
@@ -0,0 +1,113 @@
+//! `ogham cache` — inspect and prune the module cache in OGHAM_HOME.
+
+use crate::cli::{CacheArgs, CacheCommand};
+use ogham_compiler::cache::{self, Entry, EntryKind, Verified};
+use ogham_compiler::pkg;
+use std::time::SystemTime;
+
+pub fn run(args: CacheArgs) -> Result<(), String> {
+    let home = pkg::ogham_home();
+    match args.command {
+        CacheCommand::List => {
+            let entries = cache::list(&home)?;
+            for e in &entries {
+                println!(
+                    "{:<48} {:<14} {:>9}  {}",
+                    e.module,
+                    version(e),
+                    size(e.size),
+                    age(e.last_used)
+                );
+            }
+            let total: u64 = entries.iter().map(|e| e.size).sum();
+            eprintln!("{} entry(ies), {} in {}", entries.len(), size(total), home.display());
+            Ok(())
+        }
+        CacheCommand::Prune(args) => {
+            report_removed(&cache::prune(&home, args.older_than)?);
+            Ok(())
+        }
+        CacheCommand::Clean(args) => {
+            let removed = cache::clean(&home, args.module.as_deref())?;
+            if let (Some(module), true) = (&args.module, removed.is_empty()) {
+                return Err(format!("{} is not in the cache", module));
+            }
+            report_removed(&removed);
+            Ok(())
+        }
+        CacheCommand::Verify => {
+            let results = cache::verify(&home)?;
+            let mut corrupt = 0;
+            for (e, verified) in &results {
+                match verified {
+                    Verified::Ok => {}
+                    Verified::Unrecorded => {
+                        eprintln!("  {} {}: no recorded hash, not verified", e.module, version(e));
+                    }
+                    Verified::Mismatch { recorded, actual } => {
+                        corrupt += 1;
+                        eprintln!(
+                            "  {} {}: modified since it was fetched\n    recorded: {}\n    actual:   {}",
+                            e.module,
+                            version(e),
+                            recorded,
+                            actual
+                        );
+                    }
+                }
+            }
+            if corrupt > 0 {
+                return Err(format!(
+                    "{} of {} entry(ies) modified; remove them with `ogham cache clean <module>`",
+                    corrupt,
+                    results.len()
+                ));
+            }
+            eprintln!("{} entry(ies) verified", results.len());
+            Ok(())
+        }
+    }
+}
+
+fn report_removed(removed: &[Entry]) {
+    for e in removed {
+        eprintln!("  removed {} {} ({})", e.module, version(e), size(e.size));
+    }
+    let total: u64 = removed.iter().map(|e| e.size).sum();
+    eprintln!("{} entry(ies) removed, {} freed", removed.len(), size(total));
+}
+
+/// Version, `git <short commit>` for a checkout, or `gen <short key>` for a
+/// plugin response.
+fn version(e: &Entry) -> String {
+    match e.kind {
+        EntryKind::Module => e.version.clone(),
+        EntryKind::Git => format!("git {}", &e.version[..e.version.len().min(12)]),
+        EntryKind::Generated => format!("gen {}", &e.version[..e.version.len().min(12)]),
+    }
+}
+
+fn size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn age(time: SystemTime) -> String {
+    let secs = SystemTime::now().duration_since(time).map(|d| d.as_secs()).unwrap_or(0);
+    match secs {
+        0..=59 => "just now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
@@ -0,0 +1,88 @@
+//! `ogham changelog` — list all schema changes between two references.
+
+use crate::cli::{ChangelogArgs, ChangelogFormat};
+use crate::cmd::breaking::compile_reference;
+use crate::cmd::generate::compile_project;
+use ogham_compiler::breaking::AnnotationRules;
+use ogham_compiler::changelog::{self, Changelog};
+use std::path::Path;
+
+pub fn run(args: ChangelogArgs) -> Result<(), String> {
+    let dir = Path::new(".");
+
+    let (old_module, old_result, _) = compile_reference(&args.from, dir)?;
+    let (new_module, new_result) = match &args.to {
+        Some(to) => {
+            let (module, result, _) = compile_reference(to, dir)?;
+            (module, result)
+        }
+        None => {
+            let (module, result, _) = compile_project(dir)?;
+            (module, result)
+        }
+    };
+    let to_label = args.to.as_deref().unwrap_or("working tree");
+
+    let mut rules = AnnotationRules::std();
+    rules.extend_from_hir(&old_result.interner, &old_result.arenas);
+    rules.extend_from_hir(&new_result.interner, &new_result.arenas);
+    let log = changelog::build(&old_module, &new_module, &rules);
+
+    let rendered = match args.format {
+        ChangelogFormat::Markdown => changelog::to_markdown(&log, &args.from, to_label),
+        ChangelogFormat::Json => serde_json::to_string_pretty(&to_json(&log, &args.from, to_label))
+            .map_err(|e| format!("failed to serialize changelog: {}", e))?,
+    };
+
+    if let Some(ref output) = args.output {
+        std::fs::write(output, &rendered)
+            .map_err(|e| format!("cannot write {}: {}", output.display(), e))?;
+        eprintln!(
+            "{} change(s) written to {}",
+            log.changes().count(),
+            output.display()
+        );
+    } else {
+        println!("{}", rendered.trim_end());
+    }
+
+    Ok(())
+}
+
+fn to_json(log: &Changelog, from: &str, to: &str) -> serde_json::Value {
+    let packages: Vec<serde_json::Value> = log
+        .packages
+        .iter()
+        .map(|p| {
+            let declarations: Vec<serde_json::Value> = p
+                .declarations
+                .iter()
+                .map(|d| {
+                    let changes: Vec<serde_json::Value> = d
+                        .changes
+                        .iter()
+                        .map(|c| {
+                            serde_json::json!({
+                                "kind": c.kind.as_str(),
+                                "level": c.violation.level.as_str(),
+                                "code": c.violation.code,
+                                "message": c.violation.message,
+                                "context": c.violation.context,
+                                "breaking": c.is_breaking(),
+                            })
+                        })
+                        .collect();
+                    serde_json::json!({ "name": d.name, "changes": changes })
+                })
+                .collect();
+            serde_json::json!({ "package": p.package, "declarations": declarations })
+        })
+        .collect();
+
+    serde_json::json!({
+        "from": from,
+        "to": to,
+        "breaking": log.changes().filter(|c| c.is_breaking()).count(),
+        "packages": packages,
+    })
+}
@@ -1,10 +1,22 @@
 //! `ogham check` — validate schemas, compile without running plugins.
 
 use crate::cli::CheckArgs;
-use crate::cmd::generate::compile_project;
+use crate::cmd::generate::{
+    compile_project_with, compile_with_deps, for_each_member, open_workspace, reject_vendor_in_workspace,
+};
 
 pub fn run(args: CheckArgs) -> Result<(), String> {
-    let (_module, _result, _) = compile_project(&args.dir)?;
+    if let Some(ws) = open_workspace(&args.dir)? {
+        reject_vendor_in_workspace(args.vendor.mode())?;
+        let mode = args.lock.mode();
+        for_each_member(&ws, mode, |member, deps| {
+            compile_with_deps(&member.dir, deps, mode).map(|_| ())
+        })?;
+        eprintln!("check passed ({} workspace member(s))", ws.members.len());
+        return Ok(());
+    }
+
+    let (_module, _result, _) = compile_project_with(&args.dir, args.lock.mode(), args.vendor.mode())?;
     eprintln!("check passed");
     Ok(())
 }
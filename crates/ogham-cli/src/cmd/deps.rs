@@ -0,0 +1,181 @@
+//! `ogham deps` — inspect the resolved dependency graph.
+
+use crate::cli::{DepsArgs, DepsCommand, DepsGraphArgs, DepsWhyArgs, GraphFormat};
+use crate::cmd::generate::open_workspace;
+use ogham_compiler::manifest;
+use ogham_compiler::pkg::{self, Edge, LockMode, Resolution};
+use ogham_compiler::workspace::Workspace;
+use std::collections::HashSet;
+use std::path::Path;
+
+pub fn run(args: DepsArgs) -> Result<(), String> {
+    match args.command {
+        DepsCommand::Graph(args) => graph(args),
+        DepsCommand::Why(args) => why(args),
+    }
+}
+
+/// Resolve the project — every member in a workspace root, or one member
+/// with the rest of its workspace — and return the root modules with the
+/// resolution.
+fn resolve(mode: LockMode) -> Result<(Vec<String>, Resolution), String> {
+    let dir = Path::new(".");
+    if let Some(ws) = open_workspace(dir)? {
+        let roots = ws.members.iter().map(|m| m.module().to_string()).collect();
+        return Ok((roots, pkg::resolve_workspace(&ws, mode)?));
+    }
+    if let Some(ws) = Workspace::find(dir)? {
+        if let Some(member) = ws.member_at(dir) {
+            return Ok((vec![member.module().to_string()], pkg::resolve_workspace(&ws, mode)?));
+        }
+    }
+    let mod_file = manifest::load_mod_file(dir)?;
+    Ok((vec![mod_file.module], pkg::resolve_graph(dir, mode)?))
+}
+
+fn graph(args: DepsGraphArgs) -> Result<(), String> {
+    let (roots, resolution) = resolve(args.lock.mode())?;
+    let edges = resolution.edges();
+
+    let rendered = match args.format {
+        GraphFormat::Text => to_tree(&roots, &resolution, &edges),
+        GraphFormat::Json => serde_json::to_string_pretty(&to_json(&roots, &resolution, &edges))
+            .map_err(|e| format!("failed to serialize graph: {}", e))?,
+        GraphFormat::Dot => to_dot(&roots, &resolution, &edges),
+    };
+    println!("{}", rendered.trim_end());
+    Ok(())
+}
+
+fn why(args: DepsWhyArgs) -> Result<(), String> {
+    let (roots, resolution) = resolve(args.lock.mode())?;
+    let Some(sel) = resolution.selections.iter().find(|s| s.module == args.module) else {
+        return Err(format!("{} is not in the dependency graph of {}", args.module, roots.join(", ")));
+    };
+
+    println!("{} {}", sel.module, sel.version);
+    println!("  selected: {}", sel.reason);
+
+    let paths = resolution.paths_to(&sel.module);
+    println!();
+    println!("{} path(s):", paths.len());
+    for path in &paths {
+        let mut line = path[0].from.clone();
+        for edge in path {
+            line.push_str(&format!(" → {} ({})", edge.to, edge.requirement));
+        }
+        println!("  {}", line);
+    }
+
+    // Requirements from versions MVS moved away from still count towards
+    // the minimum, but no path goes through them
+    let superseded: Vec<String> = sel
+        .requirements
+        .iter()
+        .filter(|r| {
+            r.by.split_once('@').is_some_and(|(module, version)| {
+                resolution.selections.iter().any(|s| s.module == module && s.version != version)
+            })
+        })
+        .map(|r| format!("{} requires {}", r.by, r.describe()))
+        .collect();
+    if !superseded.is_empty() {
+        println!();
+        println!("also required by versions that were not selected:");
+        for line in superseded {
+            println!("  {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn version_of<'a>(resolution: &'a Resolution, module: &str) -> &'a str {
+    resolution
+        .selections
+        .iter()
+        .find(|s| s.module == module)
+        .map(|s| s.version.as_str())
+        .unwrap_or_default()
+}
+
+/// Indented tree from each root. A module printed before is marked `(*)`
+/// and not expanded again.
+fn to_tree(roots: &[String], resolution: &Resolution, edges: &[Edge]) -> String {
+    fn walk(
+        module: &str,
+        prefix: &str,
+        resolution: &Resolution,
+        edges: &[Edge],
+        expanded: &mut HashSet<String>,
+        out: &mut String,
+    ) {
+        let children: Vec<&Edge> = edges.iter().filter(|e| e.from == module).collect();
+        for (i, edge) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let seen = !expanded.insert(edge.to.clone());
+            out.push_str(&format!(
+                "{}{} {} {} ({}){}\n",
+                prefix,
+                if last { "└──" } else { "├──" },
+                edge.to,
+                version_of(resolution, &edge.to),
+                edge.requirement,
+                if seen { " (*)" } else { "" }
+            ));
+            if !seen {
+                let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+                walk(&edge.to, &prefix, resolution, edges, expanded, out);
+            }
+        }
+    }
+
+    let mut out = String::new();
+    let mut expanded: HashSet<String> = roots.iter().cloned().collect();
+    for root in roots {
+        out.push_str(root);
+        out.push('\n');
+        walk(root, "", resolution, edges, &mut expanded, &mut out);
+    }
+    out
+}
+
+fn to_json(roots: &[String], resolution: &Resolution, edges: &[Edge]) -> serde_json::Value {
+    let modules: Vec<serde_json::Value> = resolution
+        .selections
+        .iter()
+        .map(|s| serde_json::json!({ "module": s.module, "version": s.version, "reason": s.reason }))
+        .collect();
+    let edges: Vec<serde_json::Value> = edges
+        .iter()
+        .map(|e| serde_json::json!({ "from": e.from, "to": e.to, "requirement": e.requirement }))
+        .collect();
+    serde_json::json!({ "roots": roots, "modules": modules, "edges": edges })
+}
+
+/// Graphviz DOT: roots as boxes, modules labelled with their version,
+/// edges with the requirement.
+fn to_dot(roots: &[String], resolution: &Resolution, edges: &[Edge]) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('"', "\\\""));
+
+    let mut out = String::from("digraph deps {\n  rankdir=LR;\n");
+    for root in roots {
+        out.push_str(&format!("  {} [shape=box];\n", quote(root)));
+    }
+    for sel in &resolution.selections {
+        out.push_str(&format!(
+            "  {} [label={}];\n",
+            quote(&sel.module),
+            quote(&format!("{}\\n{}", sel.module, sel.version))
+        ));
+    }
+    for edge in edges {
+        out.push_str(&format!(
+            "  {} -> {} [label={}];\n",
+            quote(&edge.from),
+            quote(&edge.to),
+            quote(&edge.requirement)
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
@@ -1,17 +1,90 @@
 //! `ogham generate` — compile .ogham files and run plugins.
 
-use crate::cli::GenerateArgs;
+use crate::cli::{parse_duration, GenerateArgs};
 use ogham_compiler::ast::AstNode;
+use ogham_compiler::cache;
+use ogham_compiler::diagnostics::{self, Diagnostics, Severity};
 use ogham_compiler::lower;
 use ogham_compiler::manifest;
+use ogham_compiler::output::{self, Difference};
 use ogham_compiler::pipeline::{self, CompileOptions, SourceFile};
-use std::io::Write;
-use std::path::Path;
+use ogham_compiler::plugin;
+use ogham_compiler::pkg::{self, LockMode, ResolveError, ResolvedDep, VendorMode};
+use ogham_compiler::workspace::{Member, Workspace};
+use ogham_proto::oghamproto::compiler::{CompileError, OghamCompileResponse, Severity as PluginSeverity};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Compile a project directory and return the IR module, compile result, and module path.
 pub fn compile_project(
     dir: &Path,
+) -> Result<(ogham_proto::oghamproto::ir::Module, pipeline::CompileResult, String), String> {
+    compile_project_with(dir, LockMode::Auto, VendorMode::Auto)
+}
+
+/// Whether a resolution error stops the command: always with
+/// `--locked`/`--frozen`, and for a checksum mismatch in any mode.
+fn resolution_fatal(err: &ResolveError, mode: LockMode) -> bool {
+    mode != LockMode::Auto || matches!(err, ResolveError::ChecksumMismatch(_))
+}
+
+/// Like `compile_project`; with `--locked`/`--frozen` dependency resolution
+/// errors are fatal instead of compiling without dependencies. Checksum
+/// mismatches are fatal in every mode.
+///
+/// A project that is a workspace member is resolved together with the
+/// rest of the workspace. Otherwise dependencies come from vendor/ when
+/// it is present (or required by `--mod=vendor`); a vendor/ that does not
+/// match ogham.lock.yaml is always fatal.
+pub fn compile_project_with(
+    dir: &Path,
+    mode: LockMode,
+    vendor: VendorMode,
+) -> Result<(ogham_proto::oghamproto::ir::Module, pipeline::CompileResult, String), String> {
+    compile_with_deps(dir, project_deps(dir, mode, vendor)?, mode)
+}
+
+/// Resolve the dependencies of a standalone project or of the workspace
+/// member at `dir`, or read them from `vendor/`.
+fn project_deps(
+    dir: &Path,
+    mode: LockMode,
+    vendor: VendorMode,
+) -> Result<Result<Vec<ResolvedDep>, ResolveError>, String> {
+    let workspace = Workspace::find(dir)?;
+    Ok(match workspace.as_ref().and_then(|ws| Some((ws, ws.member_at(dir)?))) {
+        Some((ws, member)) => {
+            reject_vendor_in_workspace(vendor)?;
+            pkg::resolve_workspace(ws, mode).map(|r| r.deps_of(member.module()))
+        }
+        None => match pkg::vendored_deps(dir, vendor)? {
+            Some(deps) => {
+                eprintln!("using vendor/ ({} module(s))", deps.len());
+                Ok(deps)
+            }
+            None => pkg::resolve_deps_with(dir, mode),
+        },
+    })
+}
+
+/// Workspaces resolve as one graph and are never vendored.
+pub fn reject_vendor_in_workspace(vendor: VendorMode) -> Result<(), String> {
+    if vendor == VendorMode::Vendor {
+        return Err("--mod=vendor is not supported in workspaces".into());
+    }
+    Ok(())
+}
+
+/// Compile a project against already resolved dependencies. A resolution
+/// error is fatal with `--locked`/`--frozen`; otherwise the project is
+/// compiled without dependencies.
+pub fn compile_with_deps(
+    dir: &Path,
+    deps: Result<Vec<ResolvedDep>, ResolveError>,
+    mode: LockMode,
 ) -> Result<(ogham_proto::oghamproto::ir::Module, pipeline::CompileResult, String), String> {
     let mod_file = manifest::load_mod_file(dir).ok();
     if let Some(ref m) = mod_file {
@@ -28,8 +101,13 @@ pub fn compile_project(
         return Err(format!("no .ogham files found in {}", search_dir.display()));
     }
 
-    // Resolve and add dependency sources
-    if let Ok(deps) = ogham_compiler::pkg::resolve_deps(dir) {
+    // Add dependency sources
+    let deps = match deps {
+        Ok(deps) => Some(deps),
+        Err(e) if resolution_fatal(&e, mode) => return Err(e.into()),
+        Err(_) => None,
+    };
+    if let Some(deps) = deps {
         if let Ok(dep_sources) = ogham_compiler::pkg::collect_dep_sources(&deps) {
             let dep_count = dep_sources.len();
             sources.extend(dep_sources);
@@ -82,29 +160,103 @@ pub fn compile_project(
     Ok((module, result, module_path))
 }
 
+/// The workspace whose ogham.work.yaml is in `dir`, if any — commands run
+/// over all of its members instead of a single project.
+pub fn open_workspace(dir: &Path) -> Result<Option<Workspace>, String> {
+    if !ogham_compiler::workspace::enabled() || !dir.join("ogham.work.yaml").is_file() {
+        return Ok(None);
+    }
+    Workspace::load(dir).map(Some)
+}
+
+/// Run `f` for every workspace member with the shared resolution, going on
+/// after a member fails. Fails if any member did.
+pub fn for_each_member(
+    ws: &Workspace,
+    mode: LockMode,
+    mut f: impl FnMut(&Member, Result<Vec<ResolvedDep>, ResolveError>) -> Result<(), String>,
+) -> Result<(), String> {
+    let resolution = pkg::resolve_workspace(ws, mode);
+    if let Err(e) = &resolution {
+        if resolution_fatal(e, mode) {
+            return Err(e.to_string());
+        }
+    }
+
+    let mut failed = Vec::new();
+    for member in &ws.members {
+        eprintln!("── {} ({})", member.module(), member.path);
+        let deps = match &resolution {
+            Ok(r) => Ok(r.deps_of(member.module())),
+            Err(e) => Err(e.clone()),
+        };
+        if let Err(e) = f(member, deps) {
+            eprintln!("error: {}: {}", member.module(), e);
+            failed.push(member.module());
+        }
+    }
+
+    if failed.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} of {} workspace member(s) failed: {}",
+        failed.len(),
+        ws.members.len(),
+        failed.join(", ")
+    ))
+}
+
 pub fn run(args: GenerateArgs) -> Result<(), String> {
-    let dir = &args.dir;
-    let (module, _result, module_path) = compile_project(dir)?;
-    let request_bytes = serialize_request(&module, &args, &module_path)?;
+    if let Some(ws) = open_workspace(&args.dir)? {
+        reject_vendor_in_workspace(args.vendor.mode())?;
+        return for_each_member(&ws, args.lock.mode(), |member, deps| {
+            let required = deps.clone().unwrap_or_default();
+            let compiled = compile_with_deps(&member.dir, deps, args.lock.mode())?;
+            generate(&member.dir, compiled, &required, &args)
+        });
+    }
+
+    let deps = project_deps(&args.dir, args.lock.mode(), args.vendor.mode())?;
+    let required = deps.clone().unwrap_or_default();
+    let compiled = compile_with_deps(&args.dir, deps, args.lock.mode())?;
+    generate(&args.dir, compiled, &required, &args)
+}
+
+/// Run the breaking check and the plugins of one compiled project.
+fn generate(
+    dir: &Path,
+    (module, result, module_path): (ogham_proto::oghamproto::ir::Module, pipeline::CompileResult, String),
+    deps: &[ResolvedDep],
+    args: &GenerateArgs,
+) -> Result<(), String> {
+    let request_bytes = serialize_request(&module, args, &module_path)?;
 
     eprintln!("compiled successfully ({} bytes IR)", request_bytes.len());
 
     // Breaking change check (if configured)
     if !args.skip_breaking {
-        run_breaking_check(dir, &module)?;
+        run_breaking_check(dir, &module, &result)?;
     }
 
     // Determine which plugins to run
-    let plugins = resolve_plugins(dir, &args)?;
+    let plugins = resolve_plugins(dir, args)?;
 
     if plugins.is_empty() {
         eprintln!("no plugins configured (use --plugin <name> or create ogham.gen.yaml)");
         return Ok(());
     }
 
-    // Run each plugin in order
+    // Prepare every invocation up front; building a missing plugin happens
+    // here, one at a time.
+    let default_timeout = plugin_timeout(dir)?;
+    let mut invocations = Vec::new();
     for plugin in &plugins {
         let out_dir = dir.join(&plugin.out);
+        let name = plugin.name.as_deref()
+            .or(plugin.path.as_deref())
+            .unwrap_or("unknown")
+            .to_string();
 
         let req = lower::build_request(
             module.clone(),
@@ -113,30 +265,352 @@ pub fn run(args: GenerateArgs) -> Result<(), String> {
             &out_dir.to_string_lossy(),
             &module_path,
         );
-        let request_with_out = {
-            use prost::Message;
-            let mut buf = Vec::new();
-            req.encode(&mut buf).map_err(|e| format!("encode error: {}", e))?;
-            buf
+        let transport = prepare_transport(plugin, &name, req, deps, !args.no_cache);
+        let timeout = match plugin.timeout.as_deref() {
+            Some(t) => parse_duration(t).map_err(|e| format!("plugin {}: timeout: {}", name, e))?,
+            None => default_timeout,
         };
+        invocations.push(Invocation { name, out_dir, timeout, transport });
+    }
 
-        let plugin_name = plugin.name.as_deref()
-            .or(plugin.path.as_deref())
-            .unwrap_or("unknown");
+    // Plugins run concurrently; what they print and their output is
+    // applied in the listed order.
+    let outcomes = run_invocations(&invocations, args.jobs);
+
+    let mut failures: Vec<(String, String)> = Vec::new();
+    let mut ignored = 0;
+    let mut outdated = 0;
+    for (invocation, outcome) in invocations.iter().zip(outcomes) {
+        let name = invocation.name.as_str();
+        for line in &outcome.log {
+            eprintln!("[{}] {}", short_name(name), line);
+        }
+        let response = match outcome.response {
+            Ok(r) => r,
+            Err(e) => {
+                failures.push((name.to_string(), e));
+                continue;
+            }
+        };
 
-        if let Some(ref grpc_addr) = plugin.grpc {
-            run_plugin_grpc(plugin_name, grpc_addr, req, &out_dir)?;
+        let errors = report_plugin_errors(name, &response.errors, &module, &module_path, &result);
+        if errors > 0 {
+            if !args.keep_going {
+                failures.push((name.to_string(), format!("reported {} error(s)", errors)));
+                continue;
+            }
+            ignored += errors;
+        }
+        let applied = if args.check || args.diff {
+            check_response_files(&response, &invocation.out_dir, name, args).map(|n| outdated += n)
         } else {
-            run_plugin(plugin_name, plugin.path.as_deref(), &request_with_out, &out_dir)?;
+            write_response_files(&response, &invocation.out_dir, name, args.force)
+        };
+        if let Err(e) = applied {
+            failures.push((name.to_string(), e));
         }
     }
 
+    if ignored > 0 {
+        eprintln!("warning: {} plugin error(s) ignored (--keep-going)", ignored);
+    }
+    if !failures.is_empty() {
+        eprintln!("error: {} of {} plugin(s) failed:", failures.len(), invocations.len());
+        for (name, e) in &failures {
+            eprintln!("  {}: {}", name, e);
+        }
+        return Err(format!("{} plugin(s) failed", failures.len()));
+    }
+    if outdated > 0 {
+        return Err(format!("generated code is out of date: {} file(s) differ", outdated));
+    }
     Ok(())
 }
 
+/// How long a plugin may run unless its entry sets `timeout:` — the
+/// `generate.timeout` of ogham.gen.yaml, by default five minutes.
+fn plugin_timeout(dir: &Path) -> Result<Duration, String> {
+    match manifest::load_gen_file(dir).ok().and_then(|g| g.generate.timeout) {
+        Some(t) => parse_duration(&t).map_err(|e| format!("generate.timeout: {}", e)),
+        None => Ok(DEFAULT_PLUGIN_TIMEOUT),
+    }
+}
+
+const DEFAULT_PLUGIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// One plugin run: where its output goes and how it is reached.
+struct Invocation {
+    name: String,
+    out_dir: std::path::PathBuf,
+    timeout: Duration,
+    /// Why the plugin cannot run, if it cannot.
+    transport: Result<Transport, String>,
+}
+
+enum Transport {
+    /// A binary speaking the request on stdin and the response on stdout.
+    /// `cache` is the binary's name and the key its response is cached under.
+    Stdio { bin: String, request: Vec<u8>, cache: Option<(String, String)> },
+    /// A WebAssembly module run in-process; `cache` as for `Stdio`.
+    Wasm { module: PathBuf, request: Vec<u8>, cache: Option<(String, String)> },
+    Grpc { addr: String, request: ogham_proto::oghamproto::compiler::OghamCompileRequest },
+}
+
+fn prepare_transport(
+    plugin: &ResolvedPlugin,
+    name: &str,
+    request: ogham_proto::oghamproto::compiler::OghamCompileRequest,
+    deps: &[ResolvedDep],
+    use_cache: bool,
+) -> Result<Transport, String> {
+    use prost::Message;
+    if let Some(ref addr) = plugin.grpc {
+        return Ok(Transport::Grpc { addr: addr.clone(), request });
+    }
+    if plugin.wasm {
+        let module = resolve_plugin_wasm(name, plugin, deps)?;
+        let cache = cache_entry(&request, Some(module.clone()), use_cache);
+        return Ok(Transport::Wasm { module, request: request.encode_to_vec(), cache });
+    }
+    let installed = match (&plugin.name, &plugin.path) {
+        (Some(name), None) => installed_plugin(name, deps)?,
+        _ => None,
+    };
+    let explicit_path = installed.as_deref().or(plugin.path.as_deref());
+    let bin = resolve_plugin_binary(name, explicit_path)?;
+    let cache = cache_entry(&request, locate_binary(&bin), use_cache);
+
+    let mut buf = Vec::new();
+    request.encode(&mut buf).map_err(|e| format!("encode error: {}", e))?;
+    Ok(Transport::Stdio { bin, request: buf, cache })
+}
+
+/// Name and key the response for `request` from the plugin in `file` is
+/// cached under. A plugin that cannot be found or read is run uncached.
+fn cache_entry(
+    request: &ogham_proto::oghamproto::compiler::OghamCompileRequest,
+    file: Option<PathBuf>,
+    use_cache: bool,
+) -> Option<(String, String)> {
+    let file = file.filter(|_| use_cache)?;
+    let key = cache::generation_key(request, &file).ok()?;
+    Some((file.file_name()?.to_string_lossy().to_string(), key))
+}
+
+/// File of a binary given as a path, or as a name looked up in `$PATH`.
+fn locate_binary(bin: &str) -> Option<PathBuf> {
+    let path = Path::new(bin);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let exe = format!("{}{}", bin, std::env::consts::EXE_SUFFIX);
+    std::env::split_paths(&std::env::var_os("PATH")?)
+        .flat_map(|dir| [dir.join(bin), dir.join(&exe)])
+        .find(|p| p.is_file())
+}
+
+/// Replay the response cached under `cache`, or run the plugin and cache
+/// its response unless it reports errors.
+fn run_cached(
+    name: &str,
+    cache: Option<&(String, String)>,
+    run: impl FnOnce() -> Result<OghamCompileResponse, String>,
+) -> Result<OghamCompileResponse, String> {
+    use prost::Message;
+    let Some((plugin, key)) = cache else { return run() };
+    let home = pkg::ogham_home();
+    if let Some(response) = cache::generated(&home, plugin, key)
+        .and_then(|bytes| OghamCompileResponse::decode(bytes.as_slice()).ok())
+    {
+        eprintln!("plugin {}: unchanged, replaying cached output", name);
+        return Ok(response);
+    }
+
+    let response = run()?;
+    let failed = response.errors.iter().any(|e| {
+        !matches!(PluginSeverity::try_from(e.severity), Ok(PluginSeverity::Warning | PluginSeverity::Info))
+    });
+    if !failed {
+        if let Err(e) = cache::store_generated(&home, plugin, key, &response.encode_to_vec()) {
+            eprintln!("warning: plugin {}: output not cached: {}", name, e);
+        }
+    }
+    Ok(response)
+}
+
+/// What one plugin run printed and returned.
+struct Outcome {
+    /// Lines the plugin wrote to stderr (and, for wasm, stdout).
+    log: Vec<String>,
+    response: Result<OghamCompileResponse, String>,
+}
+
+/// Run the invocations on up to `jobs` threads and return their outcomes
+/// in the same order.
+fn run_invocations(invocations: &[Invocation], jobs: usize) -> Vec<Outcome> {
+    let next = std::sync::atomic::AtomicUsize::new(0);
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::scope(|scope| {
+        for _ in 0..jobs.clamp(1, invocations.len().max(1)) {
+            let tx = tx.clone();
+            let next = &next;
+            scope.spawn(move || loop {
+                let i = next.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let Some(invocation) = invocations.get(i) else { break };
+                let mut log = Vec::new();
+                let response = match &invocation.transport {
+                    Err(e) => Err(e.clone()),
+                    Ok(Transport::Stdio { bin, request, cache }) => run_cached(&invocation.name, cache.as_ref(), || {
+                        run_plugin(bin, request.clone(), invocation.timeout, &mut log)
+                    }),
+                    Ok(Transport::Wasm { module, request, cache }) => run_cached(&invocation.name, cache.as_ref(), || {
+                        run_plugin_wasm(module, request, invocation.timeout, &mut log)
+                    }),
+                    Ok(Transport::Grpc { addr, request }) => {
+                        run_plugin_grpc(&invocation.name, addr, request.clone(), invocation.timeout)
+                    }
+                };
+                let _ = tx.send((i, Outcome { log, response }));
+            });
+        }
+    });
+    drop(tx);
+
+    let mut outcomes: Vec<_> = invocations
+        .iter()
+        .map(|_| Outcome { log: Vec::new(), response: Err("plugin did not run".to_string()) })
+        .collect();
+    for (i, outcome) in rx {
+        outcomes[i] = outcome;
+    }
+    outcomes
+}
+
+/// Compare plugin response files with out_dir without writing anything and
+/// return the number of files that differ. With `--diff`, print a unified
+/// diff of them to stdout.
+fn check_response_files(
+    response: &OghamCompileResponse,
+    out_dir: &Path,
+    plugin_name: &str,
+    args: &GenerateArgs,
+) -> Result<usize, String> {
+    let plan = output::plan(out_dir, plugin_name, &response.files, args.force)?;
+    let differences = plan.differences(out_dir)?;
+
+    for difference in &differences {
+        let path = out_dir.join(difference.name()).display().to_string();
+        let path = path.strip_prefix("./").unwrap_or(&path);
+        let (status, old, new): (_, &[u8], &[u8]) = match difference {
+            Difference::Added { new, .. } => ("missing", &[], new),
+            Difference::Changed { old, new, .. } => ("out of date", old, new),
+            Difference::Removed { old, .. } => ("stale", old, &[]),
+        };
+        eprintln!("  {}: {}", status, path);
+        if args.diff {
+            let (old, new) = (String::from_utf8_lossy(old), String::from_utf8_lossy(new));
+            let old_header = match difference {
+                Difference::Added { .. } => "/dev/null".to_string(),
+                _ => format!("a/{}", path),
+            };
+            let new_header = match difference {
+                Difference::Removed { .. } => "/dev/null".to_string(),
+                _ => format!("b/{}", path),
+            };
+            print!(
+                "{}",
+                similar::TextDiff::from_lines(old.as_ref(), new.as_ref())
+                    .unified_diff()
+                    .header(&old_header, &new_header)
+            );
+        }
+    }
+
+    if differences.is_empty() {
+        eprintln!("plugin {}: up to date", plugin_name);
+    } else {
+        eprintln!("plugin {}: {} file(s) differ", plugin_name, differences.len());
+    }
+    Ok(differences.len())
+}
+
+/// Render what a plugin reported at the declarations it names and return the
+/// number of errors. Diagnostics without a known declaration are printed
+/// without source context; an unset severity counts as an error.
+fn report_plugin_errors(
+    plugin: &str,
+    errors: &[CompileError],
+    module: &ogham_proto::oghamproto::ir::Module,
+    module_path: &str,
+    result: &pipeline::CompileResult,
+) -> usize {
+    // Plugins may name a type by its IR full name (`<import path>.User`), by
+    // `package.User` or by `User`.
+    let import_path = match module_path {
+        "" => module.package.clone(),
+        path => format!("{}/{}", path, module.package),
+    };
+    let candidates = |source_type: &str| {
+        let mut names = vec![source_type.to_string(), format!("{}.{}", import_path, source_type)];
+        if !module_path.is_empty() {
+            names.push(format!("{}/{}", module_path, source_type));
+        }
+        names
+    };
+
+    let mut diagnostics = Diagnostics::new();
+    for err in errors {
+        let severity = match PluginSeverity::try_from(err.severity) {
+            Ok(PluginSeverity::Warning) => Severity::Warning,
+            Ok(PluginSeverity::Info) => Severity::Info,
+            _ => Severity::Error,
+        };
+        let mut diag = diagnostics
+            .build(severity, err.message.clone())
+            .note(format!("reported by plugin {}", plugin));
+
+        let location = candidates(&err.source_type)
+            .iter()
+            .find_map(|name| result.locate(name, &err.source_field));
+        if let Some((file, span)) = location {
+            let label = match err.source_field.as_str() {
+                "" => err.source_type.clone(),
+                field => format!("{}.{}", err.source_type, field),
+            };
+            diag = diag.primary(&file, span, label);
+        }
+        diag.emit();
+    }
+
+    let mut sources: Vec<(String, String)> = Vec::new();
+    for diag in diagnostics.all() {
+        let file = diag.file();
+        if file.is_empty() || sources.iter().any(|(name, _)| name == file) {
+            continue;
+        }
+        if let Ok(content) = std::fs::read_to_string(file) {
+            sources.push((file.to_string(), content));
+        }
+    }
+    diagnostics::render_diagnostics(&diagnostics, &sources);
+    diagnostics.errors().count()
+}
+
+/// Binary of a `name` plugin the project requires, built from the plugin's
+/// `plugin.build` and installed on first use. `None` for plugins that are
+/// not required, which are looked up by name instead.
+fn installed_plugin(name: &str, deps: &[ResolvedDep]) -> Result<Option<String>, String> {
+    let Some(dep) = deps.iter().find(|d| d.module == name) else {
+        return Ok(None);
+    };
+    let installed = plugin::ensure_installed(&plugin::bin_dir(), dep)?;
+    Ok(installed.map(|p| p.to_string_lossy().to_string()))
+}
+
 fn run_breaking_check(
     dir: &Path,
     new_module: &ogham_proto::oghamproto::ir::Module,
+    new_result: &pipeline::CompileResult,
 ) -> Result<(), String> {
     let mod_file = match manifest::load_mod_file(dir) {
         Ok(m) => m,
@@ -190,7 +664,10 @@ fn run_breaking_check(
         None, // no module info needed for breaking change comparison
     );
 
-    let violations = ogham_compiler::breaking::compare(&old_module, new_module);
+    let mut rules = ogham_compiler::breaking::AnnotationRules::std();
+    rules.extend_from_hir(&old_result.interner, &old_result.arenas);
+    rules.extend_from_hir(&new_result.interner, &new_result.arenas);
+    let violations = ogham_compiler::breaking::compare_with_rules(&old_module, new_module, &rules);
 
     if violations.is_empty() {
         eprintln!("no breaking changes detected");
@@ -248,8 +725,10 @@ struct ResolvedPlugin {
     name: Option<String>,
     path: Option<String>,
     grpc: Option<String>,
+    wasm: bool,
     out: String,
     opts: std::collections::HashMap<String, String>,
+    timeout: Option<String>,
 }
 
 fn resolve_plugins(dir: &Path, args: &GenerateArgs) -> Result<Vec<ResolvedPlugin>, String> {
@@ -259,8 +738,10 @@ fn resolve_plugins(dir: &Path, args: &GenerateArgs) -> Result<Vec<ResolvedPlugin
             name: Some(plugin_name.clone()),
             path: None,
             grpc: None,
+            wasm: false,
             out: ".".to_string(),
             opts: Default::default(),
+            timeout: None,
         }]);
     }
 
@@ -271,8 +752,10 @@ fn resolve_plugins(dir: &Path, args: &GenerateArgs) -> Result<Vec<ResolvedPlugin
                 name: p.name,
                 path: p.path,
                 grpc: p.grpc,
+                wasm: p.wasm,
                 out: p.out,
                 opts: p.opts,
+                timeout: p.timeout,
             }).collect())
         }
         Err(_) => Ok(Vec::new()),
@@ -316,10 +799,32 @@ fn discover_recursive(dir: &Path, sources: &mut Vec<SourceFile>) -> Result<(), S
     Ok(())
 }
 
+/// Resolve a plugin's WebAssembly module: `path:` as-is, the module a
+/// required plugin ships or builds, or `ogham-gen-<short>.wasm` in
+/// `$OGHAM_BIN` (default: `$OGHAM_HOME/bin/`).
+fn resolve_plugin_wasm(name: &str, plugin: &ResolvedPlugin, deps: &[ResolvedDep]) -> Result<PathBuf, String> {
+    if let Some(path) = &plugin.path {
+        let p = PathBuf::from(path);
+        return if p.is_file() { Ok(p) } else { Err(format!("plugin module not found: {}", path)) };
+    }
+    if let Some(dep) = deps.iter().find(|d| plugin.name.as_deref() == Some(d.module.as_str())) {
+        if let Some(p) = plugin::ensure_installed_wasm(&plugin::bin_dir(), dep)? {
+            return Ok(p);
+        }
+    }
+    let candidate = plugin::bin_dir().join(format!("{}.wasm", plugin::binary_name(name)));
+    if candidate.is_file() {
+        Ok(candidate)
+    } else {
+        Err(format!("plugin module not found: {}", candidate.display()))
+    }
+}
+
 /// Resolve plugin binary path.
 ///
 /// Resolution order:
-/// 1. `path:` in gen.yaml → use as-is (relative or absolute)
+/// 1. `path:` in gen.yaml, or the versioned binary of a required plugin
+///    → use as-is (relative or absolute)
 /// 2. `name:` → derive binary name `ogham-gen-<short>`, then search:
 ///    a. `$OGHAM_BIN/` (explicit binary directory)
 ///    b. `$OGHAM_HOME/bin/` (default: `~/.ogham/bin/`)
@@ -373,50 +878,118 @@ fn dirs_fallback() -> Option<String> {
         .or_else(|| std::env::var("USERPROFILE").ok())
 }
 
-fn run_plugin(name: &str, explicit_path: Option<&str>, request_bytes: &[u8], out_dir: &Path) -> Result<(), String> {
-    let bin_name = resolve_plugin_binary(name, explicit_path)?;
-
+/// Run a stdio plugin, killing it after `timeout`. The lines it wrote to
+/// stderr are added to `log`, also when it fails or times out.
+fn run_plugin(bin_name: &str, request: Vec<u8>, timeout: Duration, log: &mut Vec<String>) -> Result<OghamCompileResponse, String> {
     eprintln!("running plugin: {}", bin_name);
 
-    let mut child = Command::new(&bin_name)
+    let mut child = Command::new(bin_name)
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
-        .stderr(Stdio::inherit())
+        .stderr(Stdio::piped())
         .spawn()
         .map_err(|e| format!("failed to start {}: {} (is it installed?)", bin_name, e))?;
 
-    if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(request_bytes)
-            .map_err(|e| format!("failed to write to plugin stdin: {}", e))?;
-    }
+    // Pipes are served by their own threads so that a plugin which stops
+    // reading or writing cannot block the timeout. They are not joined on
+    // timeout: a process the plugin started may keep a pipe open.
+    let stdin = child.stdin.take();
+    let writer = std::thread::spawn(move || match stdin {
+        Some(mut stdin) => stdin.write_all(&request),
+        None => Ok(()),
+    });
+    let stdout = child.stdout.take();
+    let reader = std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut stdout) = stdout {
+            let _ = stdout.read_to_end(&mut buf);
+        }
+        buf
+    });
+    // Stderr is collected into a shared buffer, so that what a plugin wrote
+    // before it timed out can still be shown.
+    let errors = Arc::new(Mutex::new(Vec::new()));
+    let stderr = child.stderr.take().map(|mut stderr| {
+        let errors = Arc::clone(&errors);
+        std::thread::spawn(move || {
+            let mut chunk = [0; 4096];
+            while let Ok(n @ 1..) = stderr.read(&mut chunk) {
+                errors.lock().unwrap_or_else(|e| e.into_inner()).extend_from_slice(&chunk[..n]);
+            }
+        })
+    });
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("failed to wait for plugin: {}", e))?;
+    let status = wait_with_timeout(&mut child, timeout);
+    if let (Ok(Some(_)), Some(stderr)) = (&status, stderr) {
+        let _ = stderr.join();
+    }
+    let errors = errors.lock().unwrap_or_else(|e| e.into_inner());
+    log.extend(String::from_utf8_lossy(&errors).lines().map(str::to_string));
+    let status = status
+        .map_err(|e| format!("failed to wait for plugin: {}", e))?
+        .ok_or_else(|| format!("timed out after {:?}", timeout))?;
+    let stdout = reader.join().unwrap_or_default();
+    if let Ok(Err(e)) = writer.join() {
+        if status.success() {
+            return Err(format!("failed to write to plugin stdin: {}", e));
+        }
+    }
 
-    if !output.status.success() {
-        return Err(format!("plugin {} exited with {}", bin_name, output.status));
+    use prost::Message;
+    let response = OghamCompileResponse::decode(stdout.as_slice());
+
+    if !status.success() {
+        // A plugin that gives up may still say why in its response.
+        let reasons: Vec<String> = response
+            .map(|r| r.errors.into_iter().map(|e| e.message).collect())
+            .unwrap_or_default();
+        if reasons.is_empty() {
+            return Err(format!("exited with {}", status));
+        }
+        return Err(format!("exited with {}: {}", status, reasons.join("; ")));
     }
 
+    response.map_err(|e| format!("failed to decode plugin response: {}", e))
+}
+
+/// Run a WebAssembly plugin in-process; the lines it printed are added to
+/// `log`.
+fn run_plugin_wasm(module: &Path, request: &[u8], timeout: Duration, log: &mut Vec<String>) -> Result<OghamCompileResponse, String> {
+    eprintln!("running plugin: {} (wasm)", module.display());
+    let response = crate::wasm::run(module, request, timeout, |line| log.push(line.to_string()))?;
+
     use prost::Message;
-    let response = ogham_proto::oghamproto::compiler::OghamCompileResponse::decode(output.stdout.as_slice())
-        .map_err(|e| format!("failed to decode plugin response: {}", e))?;
+    OghamCompileResponse::decode(response.as_slice()).map_err(|e| format!("failed to decode plugin response: {}", e))
+}
 
-    for err in &response.errors {
-        eprintln!("plugin {}: {}: {}", bin_name, err.severity, err.message);
+/// Wait for `child` to exit; kill it and return `None` once `timeout` passed.
+/// A timeout too far in the future to represent means no deadline.
+fn wait_with_timeout(child: &mut std::process::Child, timeout: Duration) -> std::io::Result<Option<std::process::ExitStatus>> {
+    let deadline = Instant::now().checked_add(timeout);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        std::thread::sleep(Duration::from_millis(20));
     }
+}
 
-    write_response_files(&response, out_dir, &bin_name)?;
-    Ok(())
+/// Last path segment of a plugin name, used to prefix its output.
+fn short_name(name: &str) -> &str {
+    name.rsplit(['/', '\\']).next().unwrap_or(name)
 }
 
 fn run_plugin_grpc(
     name: &str,
     addr: &str,
     request: ogham_proto::oghamproto::compiler::OghamCompileRequest,
-    out_dir: &Path,
-) -> Result<(), String> {
+    timeout: Duration,
+) -> Result<OghamCompileResponse, String> {
     use ogham_proto::oghamproto::compiler::ogham_plugin_api_client::OghamPluginApiClient;
 
     let addr = if addr.starts_with("http") {
@@ -430,56 +1003,47 @@ fn run_plugin_grpc(
     let rt = tokio::runtime::Runtime::new()
         .map_err(|e| format!("runtime error: {}", e))?;
 
-    let response = rt.block_on(async {
-        let mut client = OghamPluginApiClient::connect(addr.clone())
-            .await
-            .map_err(|e| format!("failed to connect to {}: {}", addr, e))?;
+    rt.block_on(async {
+        let call = async {
+            let mut client = OghamPluginApiClient::connect(addr.clone())
+                .await
+                .map_err(|e| format!("failed to connect to {}: {}", addr, e))?;
 
-        let resp = client
-            .compile(request)
-            .await
-            .map_err(|e| format!("gRPC error: {}", e))?;
-
-        Ok::<_, String>(resp.into_inner())
-    })?;
+            let resp = client
+                .compile(request)
+                .await
+                .map_err(|e| format!("gRPC error: {}", e))?;
 
-    for err in &response.errors {
-        eprintln!("plugin {}: {}: {}", name, err.severity, err.message);
-    }
-
-    write_response_files(&response, out_dir, name)?;
-    Ok(())
+            Ok::<_, String>(resp.into_inner())
+        };
+        tokio::time::timeout(timeout, call)
+            .await
+            .map_err(|_| format!("timed out after {:?}", timeout))?
+    })
 }
 
-/// Write plugin response files to out_dir. File names are relative paths.
+/// Write plugin response files to out_dir and delete the files the plugin
+/// no longer produces. File names are relative paths inside out_dir.
 fn write_response_files(
-    response: &ogham_proto::oghamproto::compiler::OghamCompileResponse,
+    response: &OghamCompileResponse,
     out_dir: &Path,
     plugin_name: &str,
+    force: bool,
 ) -> Result<(), String> {
-    for file in &response.files {
-        let path = out_dir.join(&file.name);
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)
-                .map_err(|e| format!("cannot create dir {}: {}", parent.display(), e))?;
-        }
-
-        if file.append {
-            let mut f = std::fs::OpenOptions::new()
-                .append(true)
-                .create(true)
-                .open(&path)
-                .map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
-            f.write_all(&file.content)
-                .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
-        } else {
-            std::fs::write(&path, &file.content)
-                .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
-        }
-
-        eprintln!("  wrote {}", path.display());
+    let plan = output::plan(out_dir, plugin_name, &response.files, force)?;
+    for name in plan.files.keys() {
+        eprintln!("  wrote {}", out_dir.join(name).display());
+    }
+    for name in &plan.stale {
+        eprintln!("  removed {}", out_dir.join(name).display());
     }
+    let (written, removed) = (plan.files.len(), plan.stale.len());
+    plan.apply(out_dir)?;
 
-    eprintln!("plugin {} generated {} file(s)", plugin_name, response.files.len());
+    if removed > 0 {
+        eprintln!("plugin {} generated {} file(s), removed {} stale", plugin_name, written, removed);
+    } else {
+        eprintln!("plugin {} generated {} file(s)", plugin_name, written);
+    }
     Ok(())
 }
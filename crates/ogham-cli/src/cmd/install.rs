@@ -1,7 +1,18 @@
-pub fn run() -> Result<(), String> {
+use crate::cli::InstallArgs;
+use crate::cmd::generate::open_workspace;
+use ogham_compiler::pkg::LockMode;
+
+pub fn run(args: InstallArgs) -> Result<(), String> {
     let dir = std::path::Path::new(".");
+    let mode = args.lock.mode();
     eprintln!("installing dependencies...");
-    let deps = ogham_compiler::pkg::install(dir)?;
+
+    // A workspace has one lock file for all members, next to ogham.work.yaml
+    let workspace = open_workspace(dir)?;
+    let deps = match &workspace {
+        Some(ws) => ogham_compiler::pkg::install_workspace(ws, mode)?,
+        None => ogham_compiler::pkg::install(dir, mode)?,
+    };
 
     // Integrity check
     let warnings = ogham_compiler::pkg::check_integrity(&deps);
@@ -9,8 +20,10 @@ pub fn run() -> Result<(), String> {
         eprintln!("warning: {}", w);
     }
 
-    // Write lock file
-    ogham_compiler::pkg::write_lock_file(dir, &deps)?;
+    // Write lock file — with --locked/--frozen it already matches
+    if mode == LockMode::Auto {
+        ogham_compiler::pkg::write_lock_file(dir, &deps)?;
+    }
 
     eprintln!("{} dependency(ies) resolved", deps.len());
     Ok(())
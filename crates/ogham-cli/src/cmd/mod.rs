@@ -2,7 +2,14 @@ pub mod generate;
 pub mod dump;
 pub mod check;
 pub mod breaking;
+pub mod changelog;
 pub mod get;
 pub mod install;
 pub mod update;
 pub mod vendor;
+pub mod publish;
+pub mod pack;
+pub mod tidy;
+pub mod deps;
+pub mod cache;
+pub mod plugin;
@@ -0,0 +1,15 @@
+//! `ogham pack` — dry run of `ogham publish`: validate and write the archive.
+
+use crate::cli::PackArgs;
+use crate::cmd::publish::{inside, prepare};
+use std::path::Path;
+
+pub fn run(args: PackArgs) -> Result<(), String> {
+    let exclude = inside(&args.dir, &args.out);
+    let exclude: Vec<&Path> = exclude.iter().map(|p| p.as_path()).collect();
+    let artifacts = prepare(&args.dir, args.skip_breaking, &exclude, None)?;
+
+    let dir = artifacts.write_to(&args.out)?;
+    eprintln!("wrote {} {} to {}", artifacts.module, artifacts.version, dir.display());
+    Ok(())
+}
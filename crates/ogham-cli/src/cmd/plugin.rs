@@ -0,0 +1,34 @@
+//! `ogham plugin` — build and install generator plugins.
+
+use crate::cli::{PluginArgs, PluginCommand, PluginInstallArgs};
+use ogham_compiler::pkg::{self, ResolvedDep};
+use ogham_compiler::plugin;
+use std::path::Path;
+
+pub fn run(args: PluginArgs) -> Result<(), String> {
+    match args.command {
+        PluginCommand::Install(args) => install(args),
+    }
+}
+
+fn install(args: PluginInstallArgs) -> Result<(), String> {
+    let dep = fetch(&args.module)?;
+    eprintln!("building plugin {} {}", dep.module, plugin::version_label(&dep));
+    for installed in plugin::install(&plugin::bin_dir(), &dep)? {
+        eprintln!("installed {}", installed.display());
+    }
+    Ok(())
+}
+
+/// The plugin module at the requested version, or at the version the
+/// project in the current directory requires.
+fn fetch(spec: &str) -> Result<ResolvedDep, String> {
+    let dir = Path::new(".");
+    if !spec.contains('@') && dir.join("ogham.mod.yaml").exists() {
+        let deps = pkg::resolve_deps(dir).map_err(|e| e.to_string())?;
+        if let Some(dep) = deps.into_iter().find(|d| d.module == spec) {
+            return Ok(dep);
+        }
+    }
+    pkg::fetch_dep(spec)
+}
@@ -0,0 +1,139 @@
+//! `ogham publish` — validate a module and publish its release archive.
+
+use crate::cli::PublishArgs;
+use crate::cmd::breaking::compile_reference;
+use crate::cmd::generate::compile_project;
+use ogham_compiler::breaking::{self, AnnotationRules, Level};
+use ogham_compiler::manifest;
+use ogham_compiler::pkg::{self, Artifacts};
+use ogham_compiler::proxy::{self, ProxyEntry};
+use std::path::{Path, PathBuf};
+
+pub fn run(args: PublishArgs) -> Result<(), String> {
+    let target = match (&args.out, &args.proxy) {
+        (Some(_), _) => None,
+        (None, Some(url)) => Some(url.trim_end_matches('/').to_string()),
+        (None, None) => Some(
+            proxy::chain_from_env()
+                .into_iter()
+                .find_map(|e| match e {
+                    ProxyEntry::Url(url) => Some(url),
+                    ProxyEntry::Direct => None,
+                })
+                .ok_or("no proxy to publish to: pass --proxy <url>, set OGHAM_PROXY, or use --out <dir>")?,
+        ),
+    };
+
+    let exclude: Vec<PathBuf> = args.out.iter().filter_map(|o| inside(&args.dir, o)).collect();
+    let exclude: Vec<&Path> = exclude.iter().map(PathBuf::as_path).collect();
+    let artifacts = prepare(&args.dir, args.skip_breaking, &exclude, target.as_deref())?;
+
+    match (&args.out, target) {
+        (Some(out), _) => {
+            let dir = artifacts.write_to(out)?;
+            eprintln!("wrote {} {} to {}", artifacts.module, artifacts.version, dir.display());
+        }
+        (None, Some(url)) => {
+            proxy::upload(
+                &url,
+                &artifacts.module,
+                &artifacts.version,
+                &artifacts.zip,
+                &artifacts.mod_file,
+                &artifacts.info,
+            )?;
+            eprintln!("published {} {} to {}", artifacts.module, artifacts.version, url);
+        }
+        (None, None) => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Everything `publish` checks before it uploads — shared with `ogham pack`.
+///
+/// The manifest must be publishable, dependencies must resolve, the module
+/// must compile, and it must have no ERROR or WARNING changes against the
+/// previous release with the same major version. The previous release is
+/// looked up on `target`, the proxy being published to, before the
+/// `OGHAM_PROXY` chain.
+pub fn prepare(
+    dir: &Path,
+    skip_breaking: bool,
+    exclude: &[&Path],
+    target: Option<&str>,
+) -> Result<Artifacts, String> {
+    let mod_file = manifest::load_mod_file(dir)?;
+    pkg::validate_publishable(&mod_file)?;
+    pkg::resolve_deps(dir)?;
+
+    let (new_module, new_result, _) = compile_project(dir)?;
+    let artifacts = pkg::pack(dir, exclude)?;
+
+    if skip_breaking {
+        eprintln!("skipping breaking change check");
+    } else {
+        let previous = pkg::previous_version(
+            &artifacts.module,
+            &artifacts.version,
+            &release_chain(target),
+        )?;
+        match previous {
+            None => eprintln!(
+                "no earlier release in this major version — skipping breaking change check"
+            ),
+            Some(previous) => {
+                eprintln!("checking breaking changes against {}", previous);
+                let reference = format!("{}@{}", artifacts.module, previous);
+                let (old_module, old_result, _) = compile_reference(&reference, dir)?;
+
+                let mut rules = AnnotationRules::std();
+                rules.extend_from_hir(&old_result.interner, &old_result.arenas);
+                rules.extend_from_hir(&new_result.interner, &new_result.arenas);
+                let blocking: Vec<_> = breaking::compare_with_rules(&old_module, &new_module, &rules)
+                    .into_iter()
+                    .filter(|v| v.level != Level::Info)
+                    .collect();
+                for v in &blocking {
+                    eprintln!("{}[{}]: {}", v.level.as_str(), v.code, v.message);
+                }
+                if !blocking.is_empty() {
+                    return Err(format!(
+                        "{} breaking change(s) against {}; bump the major version or use --skip-breaking",
+                        blocking.len(),
+                        previous
+                    ));
+                }
+            }
+        }
+    }
+
+    eprintln!(
+        "packed {} {} ({} bytes, {})",
+        artifacts.module,
+        artifacts.version,
+        artifacts.zip.len(),
+        artifacts.info.hash.as_deref().unwrap_or("")
+    );
+    Ok(artifacts)
+}
+
+/// Proxies to look up earlier releases on: the publish target first, then
+/// the rest of `OGHAM_PROXY`.
+fn release_chain(target: Option<&str>) -> Vec<ProxyEntry> {
+    let mut chain: Vec<ProxyEntry> = target.map(|url| ProxyEntry::Url(url.to_string())).into_iter().collect();
+    for entry in proxy::chain_from_env() {
+        if !chain.contains(&entry) {
+            chain.push(entry);
+        }
+    }
+    chain
+}
+
+/// `out` as a path under the module directory, if it is inside it — so an
+/// earlier archive isn't packed into the next one.
+pub fn inside(dir: &Path, out: &Path) -> Option<PathBuf> {
+    let dir_abs = dir.canonicalize().ok()?;
+    let out_abs = std::env::current_dir().ok()?.join(out);
+    let rel = out_abs.strip_prefix(&dir_abs).ok()?;
+    Some(dir.join(rel))
+}
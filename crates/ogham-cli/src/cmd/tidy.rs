@@ -0,0 +1,21 @@
+//! `ogham tidy` — sync `require` in ogham.mod.yaml with the imports.
+
+pub fn run() -> Result<(), String> {
+    let dir = std::path::Path::new(".");
+    eprintln!("tidying dependencies...");
+    let tidy = ogham_compiler::pkg::tidy(dir)?;
+
+    for (module, _) in &tidy.added {
+        eprintln!("  + {}", module);
+    }
+    for module in &tidy.removed {
+        eprintln!("  - {}", module);
+    }
+
+    if tidy.added.is_empty() && tidy.removed.is_empty() {
+        eprintln!("ogham.mod.yaml is already tidy");
+    } else {
+        eprintln!("{} added, {} removed", tidy.added.len(), tidy.removed.len());
+    }
+    Ok(())
+}
@@ -1,10 +1,102 @@
-pub fn run() -> Result<(), String> {
-    let dir = std::path::Path::new(".");
+use crate::cli::UpdateArgs;
+use crate::cmd::generate::compile_with_deps;
+use ogham_compiler::breaking::{self, Level};
+use ogham_compiler::pipeline::CompileResult;
+use ogham_compiler::pkg::{LockMode, ResolvedDep};
+use std::collections::HashSet;
+use std::path::Path;
+
+pub fn run(args: UpdateArgs) -> Result<(), String> {
+    let dir = Path::new(".");
+    // The locked versions, compiled against before the update
+    let old_deps = if args.check_breaking {
+        Some(ogham_compiler::pkg::resolve_deps(dir)?)
+    } else {
+        None
+    };
+
     eprintln!("updating dependencies...");
-    let deps = ogham_compiler::pkg::update_deps(dir)?;
-    for dep in &deps {
-        eprintln!("  {} v{}", dep.module, dep.version);
+    let update = ogham_compiler::pkg::update(dir, &args.modules)?;
+
+    for change in &update.changes {
+        eprintln!(
+            "  {} {} → {}",
+            change.module,
+            change.old.as_deref().unwrap_or("(new)"),
+            change.new.as_deref().unwrap_or("(removed)")
+        );
     }
-    eprintln!("{} dependency(ies) updated", deps.len());
+    if update.changes.is_empty() {
+        eprintln!("  everything is up to date");
+    }
+
+    if let Some(old_deps) = old_deps {
+        if !update.changes.is_empty() {
+            check_breaking(dir, old_deps, &update.deps)?;
+        }
+    }
+
+    if args.dry_run {
+        eprintln!("{} change(s); ogham.lock.yaml not written (--dry-run)", update.changes.len());
+        return Ok(());
+    }
+    ogham_compiler::pkg::write_lock_file(dir, &update.deps)?;
+    eprintln!("{} dependency(ies) updated", update.changes.len());
+    Ok(())
+}
+
+/// Compile the project against the old and the new dependencies and report
+/// breaking changes on the declarations it uses. Errors and warnings fail
+/// the update before the lock is written.
+fn check_breaking(dir: &Path, old_deps: Vec<ResolvedDep>, new_deps: &[ResolvedDep]) -> Result<(), String> {
+    eprintln!("checking updated dependencies for breaking changes...");
+    let (old_module, old_result, _) = compile_with_deps(dir, Ok(old_deps.clone()), LockMode::Auto)
+        .map_err(|e| format!("cannot compile against the locked dependencies: {}", e))?;
+    let (new_module, new_result, _) = compile_with_deps(dir, Ok(new_deps.to_vec()), LockMode::Auto)
+        .map_err(|e| format!("the project does not compile against the updated dependencies: {}", e))?;
+
+    let mut rules = breaking::AnnotationRules::std();
+    rules.extend_from_hir(&old_result.interner, &old_result.arenas);
+    rules.extend_from_hir(&new_result.interner, &new_result.arenas);
+    let violations = breaking::compare_with_rules(&old_module, &new_module, &rules);
+
+    let mut roots = own_declarations(&old_result, &old_deps);
+    roots.extend(own_declarations(&new_result, new_deps));
+    let violations = breaking::consumed_by(violations, &old_module, &new_module, &roots);
+
+    let mut blocking = 0;
+    for v in &violations {
+        if v.level != Level::Info {
+            blocking += 1;
+        }
+        eprintln!("{}[{}]: {}", v.level.as_str(), v.code, v.message);
+    }
+    if blocking > 0 {
+        return Err(format!(
+            "{} breaking change(s) in types this module uses; ogham.lock.yaml not written",
+            blocking
+        ));
+    }
+    eprintln!("no breaking changes in types this module uses");
     Ok(())
 }
+
+/// Full names of the types, enums and services declared in the project's
+/// own files rather than in a dependency.
+fn own_declarations(result: &CompileResult, deps: &[ResolvedDep]) -> HashSet<String> {
+    let own = |file: Option<ogham_compiler::hir::Sym>| {
+        file.map(|f| result.interner.resolve(f))
+            .is_some_and(|f| !deps.iter().any(|d| Path::new(f).starts_with(&d.path)))
+    };
+    let arenas = &result.arenas;
+    let symbols = &result.symbols;
+    symbols
+        .types
+        .iter()
+        .filter(|(_, &id)| own(arenas.types[id].loc.file))
+        .map(|(&name, _)| name)
+        .chain(symbols.enums.iter().filter(|(_, &id)| own(arenas.enums[id].loc.file)).map(|(&name, _)| name))
+        .chain(symbols.services.iter().filter(|(_, &id)| own(arenas.services[id].loc.file)).map(|(&name, _)| name))
+        .map(|name| result.interner.resolve(name).to_string())
+        .collect()
+}
@@ -2,6 +2,6 @@ pub fn run() -> Result<(), String> {
     let dir = std::path::Path::new(".");
     eprintln!("vendoring dependencies...");
     ogham_compiler::pkg::vendor(dir)?;
-    eprintln!("done");
+    eprintln!("wrote vendor/modules.yaml and ogham.lock.yaml");
     Ok(())
 }
@@ -2,6 +2,7 @@
 
 mod cli;
 mod cmd;
+mod wasm;
 
 use clap::Parser;
 
@@ -12,11 +13,18 @@ fn main() {
         cli::Commands::Generate(args) => cmd::generate::run(args),
         cli::Commands::Check(args) => cmd::check::run(args),
         cli::Commands::Breaking(args) => cmd::breaking::run(args),
+        cli::Commands::Changelog(args) => cmd::changelog::run(args),
         cli::Commands::Dump(args) => cmd::dump::run(args),
         cli::Commands::Get(args) => cmd::get::run(args),
-        cli::Commands::Install => cmd::install::run(),
-        cli::Commands::Update => cmd::update::run(),
+        cli::Commands::Install(args) => cmd::install::run(args),
+        cli::Commands::Update(args) => cmd::update::run(args),
+        cli::Commands::Tidy => cmd::tidy::run(),
         cli::Commands::Vendor => cmd::vendor::run(),
+        cli::Commands::Deps(args) => cmd::deps::run(args),
+        cli::Commands::Cache(args) => cmd::cache::run(args),
+        cli::Commands::Plugin(args) => cmd::plugin::run(args),
+        cli::Commands::Publish(args) => cmd::publish::run(args),
+        cli::Commands::Pack(args) => cmd::pack::run(args),
     };
 
     if let Err(e) = result {
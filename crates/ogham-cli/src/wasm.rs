@@ -0,0 +1,270 @@
+//! In-process execution of WebAssembly generator plugins.
+//!
+//! A wasm plugin is a `wasm32-wasip1` module exporting its `memory` and:
+//!
+//! - `alloc(len: i32) -> i32` — reserve `len` bytes for the request
+//! - `compile(ptr: i32, len: i32) -> i64` — read an encoded
+//!   `OghamCompileRequest` at `ptr` and return where the encoded
+//!   `OghamCompileResponse` is, as `ptr << 32 | len`
+//!
+//! An `_initialize` export, which reactor modules have, is called first. The
+//! plugin gets no preopened directories, environment or arguments; its stdout
+//! and stderr are captured and handed back line by line.
+
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use wasmtime::{Config, Engine, Linker, Module, ResourceLimiter, Store, Trap};
+use wasmtime_wasi::pipe::MemoryOutputPipe;
+use wasmtime_wasi::preview1::{self, WasiP1Ctx};
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder};
+
+/// Most linear memory a plugin may grow to.
+pub const MEMORY_LIMIT: usize = 1 << 30;
+
+/// Fuel a plugin may burn; roughly one unit per wasm instruction.
+pub const FUEL_LIMIT: u64 = 100_000_000_000;
+
+/// Most output a plugin may write to stdout and stderr, each.
+const OUTPUT_LIMIT: usize = 1 << 20;
+
+struct State {
+    wasi: WasiP1Ctx,
+}
+
+impl ResourceLimiter for State {
+    fn memory_growing(&mut self, _current: usize, desired: usize, _maximum: Option<usize>) -> wasmtime::Result<bool> {
+        if desired > MEMORY_LIMIT {
+            return Err(wasmtime::Error::msg(format!("exceeded the memory limit of {} MiB", MEMORY_LIMIT >> 20)));
+        }
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, _current: usize, _desired: usize, _maximum: Option<usize>) -> wasmtime::Result<bool> {
+        Ok(true)
+    }
+}
+
+/// Run the plugin in `module` on `request` and return its encoded response.
+/// Every line the plugin printed is passed to `log`, also when it fails.
+pub fn run(module: &Path, request: &[u8], timeout: Duration, log: impl FnMut(&str)) -> Result<Vec<u8>, String> {
+    run_with(module, request, timeout, FUEL_LIMIT, log)
+}
+
+/// Like [`run`], with `fuel` in place of [`FUEL_LIMIT`].
+fn run_with(
+    module: &Path,
+    request: &[u8],
+    timeout: Duration,
+    fuel: u64,
+    mut log: impl FnMut(&str),
+) -> Result<Vec<u8>, String> {
+    let mut config = Config::new();
+    config.consume_fuel(true).epoch_interruption(true);
+    let engine = Engine::new(&config).map_err(|e| format!("cannot start wasm runtime: {:#}", e))?;
+    let module = Module::from_file(&engine, module)
+        .map_err(|e| format!("cannot load {}: {:#}", module.display(), e))?;
+
+    let stdout = MemoryOutputPipe::new(OUTPUT_LIMIT);
+    let stderr = MemoryOutputPipe::new(OUTPUT_LIMIT);
+    let wasi = WasiCtxBuilder::new().stdout(stdout.clone()).stderr(stderr.clone()).build_p1();
+    let mut store = Store::new(&engine, State { wasi });
+    store.limiter(|state| state);
+    store.set_fuel(fuel).map_err(|e| e.to_string())?;
+    store.set_epoch_deadline(1);
+
+    // Interrupts the plugin once `timeout` passed; returning drops `_done`,
+    // which lets the watchdog go early.
+    let (_done, finished) = mpsc::channel::<()>();
+    let watchdog = engine.clone();
+    std::thread::spawn(move || {
+        if finished.recv_timeout(timeout) == Err(mpsc::RecvTimeoutError::Timeout) {
+            watchdog.increment_epoch();
+        }
+    });
+
+    let result = call(&mut store, &module, request).map_err(|e| describe(e, timeout, fuel));
+    for (pipe, out) in [("stdout", stdout.contents()), ("stderr", stderr.contents())] {
+        for line in String::from_utf8_lossy(&out).lines() {
+            log(line);
+        }
+        // Writes past the limit fail, which plugins rarely check.
+        if out.len() >= OUTPUT_LIMIT {
+            log(&format!("({} truncated at {} MiB)", pipe, OUTPUT_LIMIT >> 20));
+        }
+    }
+    result
+}
+
+fn call(store: &mut Store<State>, module: &Module, request: &[u8]) -> wasmtime::Result<Vec<u8>> {
+    let mut linker = Linker::new(module.engine());
+    preview1::add_to_linker_sync(&mut linker, |state: &mut State| &mut state.wasi)?;
+    let instance = linker.instantiate(&mut *store, module)?;
+
+    if let Ok(init) = instance.get_typed_func::<(), ()>(&mut *store, "_initialize") {
+        init.call(&mut *store, ())?;
+    }
+    let memory = instance
+        .get_memory(&mut *store, "memory")
+        .ok_or_else(|| wasmtime::Error::msg("module does not export `memory`"))?;
+    let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc")?;
+    let compile = instance.get_typed_func::<(i32, i32), i64>(&mut *store, "compile")?;
+
+    let len = i32::try_from(request.len()).map_err(|_| wasmtime::Error::msg("request too large"))?;
+    let ptr = alloc.call(&mut *store, len)?;
+    memory.write(&mut *store, ptr as u32 as usize, request)?;
+
+    let packed = compile.call(&mut *store, (ptr, len))? as u64;
+    let (ptr, len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+    // Checked before allocating: the length comes from the plugin.
+    if !ptr.checked_add(len).is_some_and(|end| end <= memory.data_size(&*store)) {
+        return Err(wasmtime::Error::msg("response out of bounds"));
+    }
+    let mut response = vec![0; len];
+    memory.read(&*store, ptr, &mut response)?;
+    Ok(response)
+}
+
+fn describe(e: wasmtime::Error, timeout: Duration, fuel: u64) -> String {
+    if let Some(exit) = e.downcast_ref::<I32Exit>() {
+        return format!("exited with status {} before returning a response", exit.0);
+    }
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::Interrupt) => format!("timed out after {:?}", timeout),
+        Some(Trap::OutOfFuel) => format!("ran out of fuel after {} units", fuel),
+        // The wasm backtrace in the error chain means nothing to users.
+        _ => e.root_cause().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// The WAT `body` as a plugin module file, with `memory`, a bump
+    /// `alloc` from offset 1024 and the WASI imports the fixtures use.
+    fn plugin(name: &str, body: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("ogham-wasm-{}-{}.wat", name, std::process::id()));
+        let wat = format!(
+            r#"(module
+                (import "wasi_snapshot_preview1" "fd_write" (func $fd_write (param i32 i32 i32 i32) (result i32)))
+                (import "wasi_snapshot_preview1" "proc_exit" (func $proc_exit (param i32)))
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 1024)
+                {}
+            )"#,
+            body
+        );
+        std::fs::write(&path, wat).unwrap();
+        path
+    }
+
+    fn run_plugin(name: &str, body: &str, timeout: Duration, fuel: u64) -> (Result<Vec<u8>, String>, Vec<String>) {
+        let module = plugin(name, body);
+        let mut log = Vec::new();
+        let result = run_with(&module, b"request", timeout, fuel, |line| log.push(line.to_string()));
+        let _ = std::fs::remove_file(module);
+        (result, log)
+    }
+
+    const SECOND: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn round_trip_returns_response_and_output() {
+        // Writes "hi\n" (an iovec at 0 pointing at 16) to stderr and echoes
+        // the request back.
+        let (result, log) = run_plugin(
+            "echo",
+            r#"(data (i32.const 16) "hi\n")
+               (func (export "compile") (param $ptr i32) (param $len i32) (result i64)
+                 (i32.store (i32.const 0) (i32.const 16))
+                 (i32.store (i32.const 4) (i32.const 3))
+                 (drop (call $fd_write (i32.const 2) (i32.const 0) (i32.const 1) (i32.const 8)))
+                 (i64.or
+                   (i64.shl (i64.extend_i32_u (local.get $ptr)) (i64.const 32))
+                   (i64.extend_i32_u (local.get $len))))"#,
+            10 * SECOND,
+            FUEL_LIMIT,
+        );
+        assert_eq!(result.unwrap(), b"request");
+        assert_eq!(log, ["hi"]);
+    }
+
+    #[test]
+    fn endless_plugin_times_out() {
+        let (result, _) = run_plugin(
+            "loop",
+            r#"(func (export "compile") (param i32 i32) (result i64) (loop $l (br $l)) (i64.const 0))"#,
+            Duration::from_millis(100),
+            FUEL_LIMIT,
+        );
+        assert_eq!(result.unwrap_err(), "timed out after 100ms");
+    }
+
+    #[test]
+    fn plugin_runs_out_of_fuel() {
+        let (result, _) = run_plugin(
+            "fuel",
+            r#"(func (export "compile") (param i32 i32) (result i64) (loop $l (br $l)) (i64.const 0))"#,
+            60 * SECOND,
+            10_000,
+        );
+        assert_eq!(result.unwrap_err(), "ran out of fuel after 10000 units");
+    }
+
+    #[test]
+    fn memory_is_capped() {
+        let (result, _) = run_plugin(
+            "grow",
+            r#"(func (export "compile") (param i32 i32) (result i64)
+                 (drop (memory.grow (i32.const 16384)))
+                 (i64.const 0))"#,
+            10 * SECOND,
+            FUEL_LIMIT,
+        );
+        assert_eq!(result.unwrap_err(), format!("exceeded the memory limit of {} MiB", MEMORY_LIMIT >> 20));
+    }
+
+    #[test]
+    fn output_is_capped() {
+        // Writes 2 MiB of zeroes to stdout, then returns an empty response.
+        let (result, log) = run_plugin(
+            "output",
+            r#"(func (export "compile") (param i32 i32) (result i64)
+                 (drop (memory.grow (i32.const 32)))
+                 (i32.store (i32.const 0) (i32.const 65536))
+                 (i32.store (i32.const 4) (i32.const 2097152))
+                 (drop (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8)))
+                 (i64.const 0))"#,
+            10 * SECOND,
+            FUEL_LIMIT,
+        );
+        assert_eq!(result.unwrap(), b"");
+        assert_eq!(log.last().unwrap(), "(stdout truncated at 1 MiB)");
+        assert_eq!(log[0].len(), OUTPUT_LIMIT);
+    }
+
+    #[test]
+    fn response_outside_memory_is_rejected() {
+        let (result, _) = run_plugin(
+            "bounds",
+            r#"(func (export "compile") (param i32 i32) (result i64) (i64.const 0xffff_ffff))"#,
+            10 * SECOND,
+            FUEL_LIMIT,
+        );
+        assert_eq!(result.unwrap_err(), "response out of bounds");
+    }
+
+    #[test]
+    fn exit_before_response_is_reported() {
+        let (result, _) = run_plugin(
+            "exit",
+            r#"(func (export "compile") (param i32 i32) (result i64) (call $proc_exit (i32.const 3)) (i64.const 0))"#,
+            10 * SECOND,
+            FUEL_LIMIT,
+        );
+        assert_eq!(result.unwrap_err(), "exited with status 3 before returning a response");
+    }
+}
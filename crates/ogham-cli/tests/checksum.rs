@@ -0,0 +1,52 @@
+//! A tampered dependency checkout must stop `ogham check` whatever the lock mode.
+//! Runs the binary so `OGHAM_HOME` can point at a temp dir for this
+//! process only.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+
+fn check(project: &Path, home: &Path, flags: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_ogham"))
+        .arg("check")
+        .arg("--dir")
+        .arg(project)
+        .args(flags)
+        .env("OGHAM_HOME", home)
+        .env("OGHAM_PROXY", "direct")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn tampered_checkout_fails_in_every_lock_mode() {
+    let root = std::env::temp_dir().join(format!("ogham-cli-tampered-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let home = root.join("home");
+    let project = root.join("proj");
+    let entry = home.join("git/checkouts/example.com/lib/abc123");
+    fs::create_dir_all(&project).unwrap();
+    fs::create_dir_all(&entry).unwrap();
+    fs::write(entry.join("ogham.mod.yaml"), "module: example.com/lib\n").unwrap();
+    fs::write(entry.join("lib.ogham"), "package lib;\n").unwrap();
+    fs::write(project.join("ogham.mod.yaml"), "module: example.com/proj\nrequire:\n  example.com/lib:\n    git: https://example.invalid/lib.git\n    tag: v1.0.0\n").unwrap();
+    fs::write(project.join("proj.ogham"), "package proj;\n").unwrap();
+    let lock = format!(
+        "locked:\n  example.com/lib:\n    version: \"v1.0.0\"\n    commit: \"abc123\"\n    hash: \"{}\"\n    source: git\n",
+        ogham_compiler::pkg::hash_dir(&entry).unwrap()
+    );
+    fs::write(project.join("ogham.lock.yaml"), lock).unwrap();
+
+    let out = check(&project, &home, &[]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    fs::write(entry.join("lib.ogham"), "package lib;\ntype Evil {}\n").unwrap();
+    for flags in [&[][..], &["--locked"], &["--frozen"]] {
+        let out = check(&project, &home, flags);
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        assert!(!out.status.success(), "tampered dependency compiled with {:?}", flags);
+        assert!(stderr.contains("checksum mismatch for example.com/lib"), "{}", stderr);
+    }
+
+    let _ = fs::remove_dir_all(&root);
+}
@@ -0,0 +1,174 @@
+//! `ogham generate` with stdio plugins: `--check`, timeouts, concurrency
+//! and failure reporting. The plugins are shell scripts replaying a canned
+//! response, so these tests only run on unix.
+#![cfg(unix)]
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+
+use ogham_proto::oghamproto::compiler::{GeneratedFile, OghamCompileResponse};
+use prost::Message;
+
+fn project(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("ogham-cli-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    fs::create_dir_all(root.join("proj")).unwrap();
+    fs::write(root.join("proj/ogham.mod.yaml"), "module: example.com/proj\nversion: 0.1.0\n").unwrap();
+    fs::write(root.join("proj/proj.ogham"), "package proj;\n").unwrap();
+    root
+}
+
+/// A plugin that reads its request, runs `script` and answers with `files`.
+/// The response is part of the script, so a plugin answering differently is
+/// a different plugin.
+fn plugin(root: &Path, name: &str, script: &str, files: &[(&str, &str, bool)]) -> PathBuf {
+    let response = OghamCompileResponse {
+        files: files
+            .iter()
+            .map(|(name, content, append)| GeneratedFile {
+                name: name.to_string(),
+                content: content.as_bytes().to_vec(),
+                append: *append,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    let escaped: String = response.encode_to_vec().iter().map(|b| format!("\\{:03o}", b)).collect();
+
+    let path = root.join(name);
+    fs::write(&path, format!("#!/bin/sh\ncat > /dev/null\n{}\nprintf '{}'\n", script, escaped)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+fn generate(root: &Path, gen_yaml: &str, flags: &[&str]) -> Output {
+    let project = root.join("proj");
+    fs::write(project.join("ogham.gen.yaml"), gen_yaml).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_ogham"))
+        .current_dir(&project)
+        .args(["generate", "--dir", "."])
+        .args(flags)
+        .env("OGHAM_HOME", root.join("home"))
+        .env("OGHAM_PROXY", "direct")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn plugin_past_its_timeout_is_killed() {
+    let root = project("timeout");
+    let slow = plugin(&root, "slow", "echo started >&2\nexec sleep 30", &[]);
+
+    let started = Instant::now();
+    let out = generate(
+        &root,
+        &format!("generate:\n  plugins:\n    - path: {}\n      timeout: 1s\n      out: gen/\n", slow.display()),
+        &[],
+    );
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(!out.status.success());
+    assert!(started.elapsed() < Duration::from_secs(20), "plugin was not killed: {}", stderr);
+    assert!(stderr.contains("timed out after 1s"), "{}", stderr);
+    assert!(stderr.contains("[slow] started"), "output before the timeout is lost: {}", stderr);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn oversized_timeouts_do_not_crash() {
+    let root = project("huge-timeout");
+    let quick = plugin(&root, "quick", "", &[("a.txt", "a\n", false)]);
+    let gen_yaml = |timeout: &str| {
+        format!("generate:\n  plugins:\n    - path: {}\n      timeout: {}\n      out: gen/\n", quick.display(), timeout)
+    };
+
+    let out = generate(&root, &gen_yaml("40000000000000w"), &[]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(!out.status.success());
+    assert!(stderr.contains("timeout: invalid duration '40000000000000w': too large"), "{}", stderr);
+
+    // Representable, but beyond any deadline a clock can hold
+    let out = generate(&root, &gen_yaml("30000000000000w"), &[]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(fs::read_to_string(root.join("proj/gen/a.txt")).unwrap(), "a\n");
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+#[test]
+fn concurrent_plugins_apply_in_manifest_order() {
+    let root = project("jobs");
+    // The first plugin finishes last, yet its file is written first and its
+    // output printed first.
+    let first = plugin(&root, "first", "sleep 1\necho one >&2", &[("shared.txt", "first\n", false)]);
+    let second = plugin(&root, "second", "echo two >&2", &[("shared.txt", "second\n", true)]);
+    let broken = plugin(&root, "broken", "echo giving up >&2\nexit 3", &[]);
+    let third = plugin(&root, "third", "", &[("shared.txt", "third\n", true)]);
+    let missing = root.join("missing");
+
+    let plugins: String = [&first, &second, &broken, &third, &missing]
+        .iter()
+        .map(|p| format!("    - path: {}\n      out: gen/\n", p.display()))
+        .collect();
+    let out = generate(&root, &format!("generate:\n  plugins:\n{}", plugins), &["--jobs", "2"]);
+    let stderr = String::from_utf8_lossy(&out.stderr);
+
+    assert!(!out.status.success());
+    assert_eq!(fs::read_to_string(root.join("proj/gen/shared.txt")).unwrap(), "first\nsecond\nthird\n");
+    let one = stderr.find("[first] one").expect(&stderr);
+    let two = stderr.find("[second] two").expect(&stderr);
+    let giving_up = stderr.find("[broken] giving up").expect(&stderr);
+    assert!(one < two && two < giving_up, "output out of manifest order: {}", stderr);
+
+    let summary = stderr.find("error: 2 of 5 plugin(s) failed:").expect(&stderr);
+    let failures: Vec<&str> = stderr[summary..].lines().skip(1).take(2).collect();
+    assert!(failures[0].contains("broken: exited with exit status: 3"), "{}", stderr);
+    assert!(failures[1].contains("missing: plugin binary not found"), "{}", stderr);
+
+    let _ = fs::remove_dir_all(&root);
+}
+
+/// Every file in `dir` with its content.
+fn snapshot(dir: &Path) -> BTreeMap<PathBuf, Vec<u8>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(snapshot(&path));
+        } else {
+            files.insert(path.clone(), fs::read(&path).unwrap());
+        }
+    }
+    files
+}
+
+#[test]
+fn check_reports_differences_without_writing() {
+    let root = project("check");
+    let gen_yaml = |plugin: &Path| format!("generate:\n  plugins:\n    - path: {}\n      out: gen/\n", plugin.display());
+    let files = [("a.txt", "one\n", false), ("b.txt", "two\n", false), ("c.txt", "three\n", false)];
+    let out = generate(&root, &gen_yaml(&plugin(&root, "gen", "", &files)), &[]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+
+    // a.txt is edited, b.txt deleted and c.txt no longer produced.
+    fs::write(root.join("proj/gen/a.txt"), "edited\n").unwrap();
+    fs::remove_file(root.join("proj/gen/b.txt")).unwrap();
+    let plugin = plugin(&root, "gen", "", &files[..2]);
+    let before = snapshot(&root.join("proj/gen"));
+
+    let out = generate(&root, &gen_yaml(&plugin), &["--check", "--diff"]);
+    let (stdout, stderr) = (String::from_utf8_lossy(&out.stdout), String::from_utf8_lossy(&out.stderr));
+    assert!(!out.status.success());
+    assert!(stderr.contains("generated code is out of date: 3 file(s) differ"), "{}", stderr);
+    assert!(stdout.contains("--- a/gen/a.txt\n+++ b/gen/a.txt\n"), "{}", stdout);
+    assert!(stdout.contains("-edited\n+one\n"), "{}", stdout);
+    assert!(stdout.contains("--- /dev/null\n+++ b/gen/b.txt\n"), "{}", stdout);
+    assert!(stdout.contains("--- a/gen/c.txt\n+++ /dev/null\n"), "{}", stdout);
+    assert_eq!(snapshot(&root.join("proj/gen")), before, "--check must not write");
+
+    let _ = fs::remove_dir_all(&root);
+}
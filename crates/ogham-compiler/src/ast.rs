@@ -630,6 +630,11 @@ impl AnnotationDecl {
     pub fn compositions(&self) -> Vec<AnnotationComposition> {
         children_of_type(&self.syntax)
     }
+
+    /// Annotations written before the definition, e.g. `@tightens`.
+    pub fn annotations(&self) -> Vec<AnnotationCall> {
+        preceding_annotations(&self.syntax)
+    }
 }
 
 ast_node!(AnnotationTargets, AnnotationTargets);
@@ -709,6 +714,10 @@ impl AnnotationField {
     pub fn inline_type(&self) -> Option<InlineAnnotationType> {
         first_child_of_type(&self.syntax)
     }
+
+    pub fn annotations(&self) -> Vec<AnnotationCall> {
+        preceding_annotations(&self.syntax)
+    }
 }
 
 ast_node!(AnnotationComposition, AnnotationComposition);
@@ -772,6 +781,27 @@ impl AnnotationCall {
         }
     }
 
+    /// For built-in annotations without a library (`@default(now)`), returns the name.
+    pub fn simple_name(&self) -> Option<String> {
+        if self.library_name().is_some() {
+            return None;
+        }
+        first_ident_token(&self.syntax).map(|t| t.text().to_string())
+    }
+
+    /// Whether this is a built-in annotation, written without a library.
+    pub fn is_builtin(&self) -> bool {
+        self.library_name().is_none()
+    }
+
+    /// Name token of a built-in annotation.
+    pub fn builtin_name(&self) -> Option<SyntaxToken> {
+        if !self.is_builtin() {
+            return None;
+        }
+        first_ident_token(&self.syntax)
+    }
+
     pub fn args(&self) -> Option<AnnotationArgs> {
         first_child_of_type(&self.syntax)
     }
@@ -4,9 +4,25 @@
 //! - ERROR: wire-breaking (field number changed, type changed, field removed)
 //! - WARNING: JSON/codegen-breaking (field renamed, optional changed, rpc renamed)
 //! - INFO: safe changes (new fields, new types, annotation changes)
+//!
+//! Removed declarations that reappear under a new name with the same structure
+//! (types, enums, services, rpcs) are reported once as a rename (B003) and then
+//! compared as if the name had not changed. Declarations nested in a renamed
+//! type move with it. A renamed service is an ERROR, like a removed one: its
+//! name is part of every rpc path.
+//!
+//! Validation annotations are compared parameter by parameter: a change that
+//! rejects previously valid data (raising `min`, adding `Required`) is a WARNING,
+//! a change that only accepts more data is INFO.
 
+use crate::ast::{self, AstNode};
+use crate::hir::{Arenas, Interner, Tightening};
+use crate::pipeline::{self, CompileOptions, SourceFile};
+use crate::stdlib;
+use crate::syntax_kind::SyntaxToken;
 use ogham_proto::oghamproto::ir;
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::OnceLock;
 
 // ── Violation types ────────────────────────────────────────────────────
 
@@ -17,6 +33,17 @@ pub enum Level {
     Info,
 }
 
+impl Level {
+    /// Lowercase name, as printed in `error[B001]: ...` and JSON output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Info => "info",
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Violation {
     pub level: Level,
@@ -25,24 +52,730 @@ pub struct Violation {
     pub context: String, // "User.email", "OrderStatus.Refunded", "UserAPI.GetUser"
 }
 
+// ── Annotation rules ───────────────────────────────────────────────────
+
+/// How a change to an annotation parameter affects the set of accepted values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamRule {
+    /// Raising the value rejects previously valid data (`min`).
+    TightensOnIncrease,
+    /// Lowering the value rejects previously valid data (`max`).
+    TightensOnDecrease,
+    /// Direction is unknown — any change may reject valid data (`pattern`).
+    TightensOnChange,
+}
+
+/// Validation annotations whose arguments are compared field by field.
+///
+/// Libraries declare them with `@tightens` in the annotation definitions,
+/// picked up by `extend_from_hir`; `std()` reads those of `std/validate`.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationRules {
+    /// (library, annotation) — adding one of these tightens validation.
+    constraints: HashSet<(String, String)>,
+    /// (library, annotation, parameter) → direction.
+    params: HashMap<(String, String, String), ParamRule>,
+}
+
+impl AnnotationRules {
+    /// Rules for `github.com/oghamlang/std/validate`, read from the
+    /// `@tightens` declarations in its embedded source. The source is
+    /// compiled once per process.
+    pub fn std() -> Self {
+        static STD: OnceLock<AnnotationRules> = OnceLock::new();
+        STD.get_or_init(|| {
+            let sources = stdlib::resolve_std_imports(&["github.com/oghamlang/std/validate".to_string()]);
+            let result = pipeline::compile(&sources, &CompileOptions::default());
+            debug_assert!(
+                !result.diagnostics.has_errors(),
+                "embedded std/validate does not compile: {:?}",
+                result.diagnostics.errors().map(|d| &d.message).collect::<Vec<_>>()
+            );
+            let mut rules = Self::default();
+            rules.extend_from_hir(&result.interner, &result.arenas);
+            rules
+        })
+        .clone()
+    }
+
+    /// Add rules declared with `@tightens` on annotation definitions and
+    /// `@tightens(...)` on their parameters.
+    pub fn extend_from_hir(&mut self, interner: &Interner, arenas: &Arenas) {
+        for (_, def) in arenas.annotation_defs.iter() {
+            let library = interner.resolve(def.library);
+            let name = interner.resolve(def.name);
+            if def.tightens {
+                self.add_constraint(library, name);
+            }
+            for param in &def.params {
+                let rule = match param.tightens {
+                    Some(Tightening::Increase) => ParamRule::TightensOnIncrease,
+                    Some(Tightening::Decrease) => ParamRule::TightensOnDecrease,
+                    Some(Tightening::Change) => ParamRule::TightensOnChange,
+                    None => continue,
+                };
+                self.add_constraint(library, name);
+                self.add_param(library, name, interner.resolve(param.name), rule);
+            }
+        }
+    }
+
+    pub fn add_constraint(&mut self, library: &str, name: &str) {
+        self.constraints.insert((library.to_string(), name.to_string()));
+    }
+
+    pub fn add_param(&mut self, library: &str, name: &str, param: &str, rule: ParamRule) {
+        self.params.insert((library.to_string(), name.to_string(), param.to_string()), rule);
+    }
+
+    fn is_constraint(&self, library: &str, name: &str) -> bool {
+        self.constraints.contains(&(library.to_string(), name.to_string()))
+    }
+
+    fn param_rule(&self, library: &str, name: &str, param: &str) -> Option<ParamRule> {
+        let key = (library.to_string(), name.to_string(), param.to_string());
+        if let Some(rule) = self.params.get(&key) {
+            return Some(*rule);
+        }
+        // Positional arguments (`validate::Pattern("^[a-z]+$")`) have no name —
+        // treat any change to them as potentially tightening.
+        if param.is_empty() && self.is_constraint(library, name) {
+            return Some(ParamRule::TightensOnChange);
+        }
+        None
+    }
+}
+
 // ── Public API ─────────────────────────────────────────────────────────
 
 /// Compare two modules and return all violations.
 pub fn compare(old: &ir::Module, new: &ir::Module) -> Vec<Violation> {
+    compare_with_rules(old, new, &AnnotationRules::std())
+}
+
+/// Compare two modules using the given validation annotation rules.
+pub fn compare_with_rules(old: &ir::Module, new: &ir::Module, rules: &AnnotationRules) -> Vec<Violation> {
     let mut violations = Vec::new();
 
-    compare_types(old, new, &mut violations);
-    compare_enums(old, new, &mut violations);
-    compare_services(old, new, &mut violations);
+    let old = detect_renames(old, new, &mut violations);
+    compare_types(&old, new, rules, &mut violations);
+    compare_enums(&old, new, &mut violations);
+    compare_services(&old, new, &mut violations);
 
     violations
 }
 
+/// Old names of the declarations and members renamed between two modules.
+#[derive(Debug, Clone, Default)]
+pub struct Renames {
+    /// New full name → old full name
+    names: HashMap<String, String>,
+}
+
+impl Renames {
+    /// Name `context`, as reported on a violation, had in the old module:
+    /// the old name of a renamed declaration or member, or the same member
+    /// under the old name of its renamed enclosing declaration.
+    pub fn old_context(&self, context: &str) -> String {
+        if let Some(old) = self.names.get(context) {
+            return old.clone();
+        }
+        let mut end = context.len();
+        while let Some(dot) = context[..end].rfind('.') {
+            if let Some(old) = self.names.get(&context[..dot]) {
+                return format!("{}{}", old, &context[dot..]);
+            }
+            end = dot;
+        }
+        context.to_string()
+    }
+}
+
+/// The renames [`compare_with_rules`] detects between `old` and `new`, for
+/// locating its violations in the old sources.
+pub fn renames(old: &ir::Module, new: &ir::Module) -> Renames {
+    fn members(old: &ir::Type, new: &ir::Type, names: &mut HashMap<String, String>) {
+        for field in &old.fields {
+            if let Some(to) = new.fields.iter().find(|f| f.number == field.number && f.name != field.name) {
+                names.insert(format!("{}.{}", new.full_name, to.name), format!("{}.{}", old.full_name, field.name));
+            }
+        }
+    }
+    fn values(old: &ir::Enum, new: &ir::Enum, names: &mut HashMap<String, String>) {
+        for value in &old.values {
+            if let Some(to) = new.values.iter().find(|v| v.number == value.number && v.name != value.name) {
+                names.insert(format!("{}.{}", new.full_name, to.name), format!("{}.{}", old.full_name, value.name));
+            }
+        }
+    }
+
+    // `renamed` is `old` with the new names applied in place, so the two
+    // line up declaration by declaration
+    let renamed = detect_renames(old, new, &mut Vec::new());
+    let (new_types, new_enums) = (all_types(new), all_enums(new));
+    let mut names = HashMap::new();
+    let mut pair = |from: &str, to: &str| {
+        if from != to {
+            names.insert(to.to_string(), from.to_string());
+        }
+    };
+
+    let mut types: Vec<(&ir::Type, &ir::Type)> = old.types.iter().zip(&renamed.types).collect();
+    let mut enums: Vec<(&ir::Enum, &ir::Enum)> = old.enums.iter().zip(&renamed.enums).collect();
+    let mut i = 0;
+    while let Some(&(o, r)) = types.get(i) {
+        types.extend(o.nested_types.iter().zip(&r.nested_types));
+        enums.extend(o.nested_enums.iter().zip(&r.nested_enums));
+        i += 1;
+    }
+    for (o, r) in &types {
+        pair(&o.full_name, &r.full_name);
+    }
+    for (o, r) in &enums {
+        pair(&o.full_name, &r.full_name);
+    }
+    for (o, r) in old.services.iter().zip(&renamed.services) {
+        pair(&o.full_name, &r.full_name);
+        for (old_rpc, rpc) in o.rpcs.iter().zip(&r.rpcs) {
+            pair(&format!("{}.{}", o.full_name, old_rpc.name), &format!("{}.{}", r.full_name, rpc.name));
+        }
+    }
+
+    for (o, r) in &types {
+        if let Some(n) = new_types.get(r.full_name.as_str()) {
+            members(o, n, &mut names);
+        }
+    }
+    for (o, r) in &enums {
+        if let Some(n) = new_enums.get(r.full_name.as_str()) {
+            values(o, n, &mut names);
+        }
+    }
+    Renames { names }
+}
+
+// ── Consumer scope ─────────────────────────────────────────────────────
+
+/// Keep the violations a consumer can observe: those on declarations its own
+/// declarations (`roots`, by full name) reach in `old` or `new`, directly or
+/// through other types. Members are attributed to their innermost enclosing
+/// declaration, so a change to an unused nested type of a used type is dropped.
+pub fn consumed_by(
+    violations: Vec<Violation>,
+    old: &ir::Module,
+    new: &ir::Module,
+    roots: &HashSet<String>,
+) -> Vec<Violation> {
+    let mut reached = reachable(old, roots);
+    reached.extend(reachable(new, roots));
+
+    let mut declared: HashSet<&str> = HashSet::new();
+    for module in [old, new] {
+        declared.extend(all_types(module).into_keys());
+        declared.extend(all_enums(module).into_keys());
+        declared.extend(module.services.iter().map(|s| s.full_name.as_str()));
+    }
+
+    violations
+        .into_iter()
+        .filter(|v| {
+            let mut key = v.context.as_str();
+            while !declared.contains(key) {
+                match key.rfind('.') {
+                    Some(dot) => key = &key[..dot],
+                    None => return false,
+                }
+            }
+            reached.contains(key)
+        })
+        .collect()
+}
+
+/// Full names of `roots` and every type and enum they reach through field,
+/// oneof and map types, and through the rpc parameters of root services.
+pub fn reachable(module: &ir::Module, roots: &HashSet<String>) -> HashSet<String> {
+    let types = all_types(module);
+    let mut reached = roots.clone();
+    let mut pending: Vec<&ir::Type> = types
+        .values()
+        .filter(|t| roots.contains(&t.full_name))
+        .copied()
+        .collect();
+    let mut refs: Vec<&ir::TypeReference> = module
+        .services
+        .iter()
+        .filter(|s| roots.contains(&s.full_name))
+        .flat_map(|s| &s.rpcs)
+        .flat_map(|rpc| [rpc.input.as_ref(), rpc.output.as_ref()])
+        .filter_map(|param| param?.r#type.as_ref())
+        .collect();
+
+    loop {
+        while let Some(tr) = refs.pop() {
+            match &tr.kind {
+                Some(ir::type_reference::Kind::MessageType(m)) => {
+                    if reached.insert(m.full_name.clone()) {
+                        pending.extend(types.get(m.full_name.as_str()));
+                    }
+                }
+                Some(ir::type_reference::Kind::EnumType(e)) => {
+                    reached.insert(e.full_name.clone());
+                }
+                Some(ir::type_reference::Kind::Map(m)) => {
+                    refs.extend(m.key.as_deref());
+                    refs.extend(m.value.as_deref());
+                }
+                Some(ir::type_reference::Kind::Scalar(_)) | None => {}
+            }
+        }
+        let Some(ty) = pending.pop() else { break };
+        refs.extend(ty.fields.iter().filter_map(|f| f.r#type.as_ref()));
+        refs.extend(ty.oneofs.iter().flat_map(|o| &o.fields).filter_map(|f| f.r#type.as_ref()));
+    }
+    reached
+}
+
+// ── Source locations ───────────────────────────────────────────────────
+
+/// Position of a declaration in a schema file. Lines are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceLine {
+    pub file: String,
+    pub line: usize,
+}
+
+/// Declaration positions keyed like `Violation::context` (`pkg.Type`,
+/// `pkg.Type.field`, `pkg.Enum.Value`, `pkg.Service.Rpc`).
+///
+/// The IR carries no source locations, so they are recovered by re-parsing.
+#[derive(Debug, Clone, Default)]
+pub struct SourceLocations {
+    lines: HashMap<String, SourceLine>,
+}
+
+impl SourceLocations {
+    pub fn from_sources(sources: &[SourceFile]) -> Self {
+        let mut locations = Self::default();
+        for source in sources {
+            let parse = crate::parser::parse(&source.content);
+            let Some(root) = ast::Root::cast(parse.syntax()) else {
+                continue;
+            };
+            let package = root
+                .package_decl()
+                .and_then(|p| p.name())
+                .map(|t| t.text().to_string())
+                .unwrap_or_else(|| "default".to_string());
+
+            let mut file = FileLocations {
+                source,
+                lines: &mut locations.lines,
+            };
+            for decl in root.type_decls() {
+                file.type_decl(&package, &decl);
+            }
+            for decl in root.enum_decls() {
+                file.enum_decl(&package, &decl);
+            }
+            for decl in root.service_decls() {
+                let Some(name) = decl.name() else { continue };
+                let full = format!("{}.{}", package, name.text());
+                file.insert(&full, &name);
+                for rpc in decl.rpcs() {
+                    if let Some(rpc_name) = rpc.name() {
+                        file.insert(&format!("{}.{}", full, rpc_name.text()), &rpc_name);
+                    }
+                }
+            }
+        }
+        locations
+    }
+
+    /// Location of `context`, or of its closest enclosing declaration —
+    /// fields injected from shapes are reported at their type.
+    pub fn find(&self, context: &str) -> Option<&SourceLine> {
+        let mut key = context;
+        loop {
+            if let Some(line) = self.lines.get(key) {
+                return Some(line);
+            }
+            key = &key[..key.rfind('.')?];
+        }
+    }
+}
+
+struct FileLocations<'a> {
+    source: &'a SourceFile,
+    lines: &'a mut HashMap<String, SourceLine>,
+}
+
+impl FileLocations<'_> {
+    fn insert(&mut self, key: &str, name: &SyntaxToken) {
+        let offset = usize::from(name.text_range().start());
+        let line = self.source.content[..offset].matches('\n').count() + 1;
+        self.lines.entry(key.to_string()).or_insert_with(|| SourceLine {
+            file: self.source.name.clone(),
+            line,
+        });
+    }
+
+    fn type_decl(&mut self, prefix: &str, decl: &ast::TypeDecl) {
+        let Some(name) = decl.name() else { return };
+        let full = format!("{}.{}", prefix, name.text());
+        self.insert(&full, &name);
+
+        let Some(body) = decl.body() else { return };
+        for field in body.fields() {
+            if let Some(field_name) = field.name() {
+                self.insert(&format!("{}.{}", full, field_name.text()), &field_name);
+            }
+        }
+        for oneof in body.oneofs() {
+            let Some(oneof_name) = oneof.name() else { continue };
+            let oneof_full = format!("{}.{}", full, oneof_name.text());
+            self.insert(&oneof_full, &oneof_name);
+            for field in oneof.fields() {
+                if let Some(field_name) = field.name() {
+                    self.insert(&format!("{}.{}", oneof_full, field_name.text()), &field_name);
+                    self.insert(&format!("{}.{}", full, field_name.text()), &field_name);
+                }
+            }
+        }
+        for nested in body.nested_types() {
+            if let Some(inner) = nested.type_decl() {
+                self.type_decl(&full, &inner);
+            }
+        }
+        for nested in body.nested_enums() {
+            if let Some(inner) = nested.enum_decl() {
+                self.enum_decl(&full, &inner);
+            }
+        }
+    }
+
+    fn enum_decl(&mut self, prefix: &str, decl: &ast::EnumDecl) {
+        let Some(name) = decl.name() else { return };
+        let full = format!("{}.{}", prefix, name.text());
+        self.insert(&full, &name);
+        for value in decl.values() {
+            if let Some(value_name) = value.name() {
+                self.insert(&format!("{}.{}", full, value_name.text()), &value_name);
+            }
+        }
+    }
+}
+
+// ── Rename detection ───────────────────────────────────────────────────
+
+/// Minimum share of matching members for a removed + added pair to count as a rename.
+const RENAME_THRESHOLD: f64 = 0.8;
+
+/// Find declarations that were renamed between `old` and `new`, report them
+/// as B003 and return a copy of `old` with the new names applied, so the
+/// remaining passes compare renamed declarations member by member.
+fn detect_renames(old: &ir::Module, new: &ir::Module, out: &mut Vec<Violation>) -> ir::Module {
+    let mut names: HashMap<String, String> = HashMap::new();
+
+    let (old_types, new_types) = (all_types(old), all_types(new));
+    let (old_enums, new_enums) = (all_enums(old), all_enums(new));
+    for (kind, mut pairs) in [
+        ("type", match_renamed(&old_types, &new_types, type_similarity)),
+        ("enum", match_renamed(&old_enums, &new_enums, enum_similarity)),
+    ] {
+        // Outer declarations first: a nested declaration moves with its
+        // parent and is not reported, whatever it matched on its own
+        pairs.sort_by(|a, b| a.0.len().cmp(&b.0.len()).then(a.0.cmp(&b.0)));
+        for (from, to) in pairs {
+            if names.contains_key(&from) {
+                continue;
+            }
+            out.push(rename_violation(Level::Warning, kind, &from, &to));
+            carry_nested(&from, &to, &old_types, &new_types, &mut names);
+            carry_nested(&from, &to, &old_enums, &new_enums, &mut names);
+            names.insert(from, to);
+        }
+    }
+
+    let mut renamed = old.clone();
+    apply_type_renames(&mut renamed, &names);
+
+    // Services are matched after type renames so rpc signatures line up.
+    let old_services: HashMap<&str, &ir::Service> =
+        renamed.services.iter().map(|s| (s.full_name.as_str(), s)).collect();
+    let new_services: HashMap<&str, &ir::Service> =
+        new.services.iter().map(|s| (s.full_name.as_str(), s)).collect();
+    let service_names: HashMap<String, String> =
+        match_renamed(&old_services, &new_services, service_similarity).into_iter().collect();
+    for svc in &mut renamed.services {
+        if let Some(to) = service_names.get(&svc.full_name) {
+            // The service name is part of every rpc path: clients calling
+            // the old name break, as if the service had been removed
+            out.push(rename_violation(Level::Error, "service", &svc.full_name, to));
+            svc.name = short_name(to).to_string();
+            svc.full_name = to.clone();
+        }
+    }
+
+    for svc in &mut renamed.services {
+        if let Some(new_svc) = new_services.get(svc.full_name.as_str()) {
+            rename_rpcs(svc, new_svc, out);
+        }
+    }
+
+    renamed
+}
+
+/// Map the declarations nested in a renamed `from` to the same names under
+/// `to`, where `new` has them. Empty nested declarations carry no evidence
+/// of their own and would otherwise be reported as removed and added.
+fn carry_nested<T>(
+    from: &str,
+    to: &str,
+    old: &HashMap<&str, &T>,
+    new: &HashMap<&str, &T>,
+    names: &mut HashMap<String, String>,
+) {
+    for name in old.keys() {
+        let Some(rest) = name.strip_prefix(from).filter(|rest| rest.starts_with('.')) else {
+            continue;
+        };
+        let target = format!("{}{}", to, rest);
+        if new.contains_key(target.as_str()) && !old.contains_key(target.as_str()) {
+            names.entry(name.to_string()).or_insert(target);
+        }
+    }
+}
+
+/// Pair removed declarations with added ones by structural similarity.
+/// Best matches win; every declaration takes part in at most one rename.
+fn match_renamed<T>(
+    old: &HashMap<&str, &T>,
+    new: &HashMap<&str, &T>,
+    similarity: fn(&T, &T) -> f64,
+) -> Vec<(String, String)> {
+    let mut candidates = Vec::new();
+    for (old_name, old_decl) in old {
+        if new.contains_key(old_name) {
+            continue;
+        }
+        for (new_name, new_decl) in new {
+            if old.contains_key(new_name) {
+                continue;
+            }
+            let score = similarity(*old_decl, *new_decl);
+            if score >= RENAME_THRESHOLD {
+                candidates.push((score, *old_name, *new_name));
+            }
+        }
+    }
+
+    // Name order breaks ties so the result does not depend on hash order.
+    candidates.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(b.1)).then(a.2.cmp(b.2)));
+
+    let mut used_old = HashSet::new();
+    let mut used_new = HashSet::new();
+    let mut pairs = Vec::new();
+    for (_, old_name, new_name) in candidates {
+        if used_old.contains(old_name) || used_new.contains(new_name) {
+            continue;
+        }
+        used_old.insert(old_name);
+        used_new.insert(new_name);
+        pairs.push((old_name.to_string(), new_name.to_string()));
+    }
+    pairs
+}
+
+/// Share of field numbers carrying the same shape in both types.
+fn type_similarity(old: &ir::Type, new: &ir::Type) -> f64 {
+    let old_fields = field_shapes(old);
+    let new_fields = field_shapes(new);
+    let matching = old_fields.iter().filter(|(num, shape)| new_fields.get(*num) == Some(*shape)).count();
+    share(matching, old_fields.len(), new_fields.len())
+}
+
+/// Share of values with the same number and name. The implicit
+/// `Unspecified = 0` is ignored — every enum has it.
+fn enum_similarity(old: &ir::Enum, new: &ir::Enum) -> f64 {
+    let values = |e: &ir::Enum| -> HashSet<(i32, String)> {
+        e.values.iter().filter(|v| v.number != 0).map(|v| (v.number, v.name.clone())).collect()
+    };
+    let old_values = values(old);
+    let new_values = values(new);
+    share(old_values.intersection(&new_values).count(), old_values.len(), new_values.len())
+}
+
+/// Share of rpcs with the same name and signature.
+fn service_similarity(old: &ir::Service, new: &ir::Service) -> f64 {
+    let rpcs = |s: &ir::Service| -> HashSet<(String, String, String)> {
+        s.rpcs
+            .iter()
+            .map(|r| (r.name.clone(), rpc_param_sig(r.input.as_ref()), rpc_param_sig(r.output.as_ref())))
+            .collect()
+    };
+    let old_rpcs = rpcs(old);
+    let new_rpcs = rpcs(new);
+    share(old_rpcs.intersection(&new_rpcs).count(), old_rpcs.len(), new_rpcs.len())
+}
+
+/// Empty declarations carry no evidence and never match.
+fn share(matching: usize, old_len: usize, new_len: usize) -> f64 {
+    let total = old_len.max(new_len);
+    if total == 0 {
+        return 0.0;
+    }
+    matching as f64 / total as f64
+}
+
+/// Field number → shape, covering regular and oneof fields. The shape
+/// ignores referenced type names, so renames of those don't hide a match.
+fn field_shapes(ty: &ir::Type) -> HashMap<u32, String> {
+    let mut shapes: HashMap<u32, String> = ty
+        .fields
+        .iter()
+        .map(|f| {
+            let prefix = if f.is_repeated { "[]" } else { "" };
+            (f.number, format!("{}{}", prefix, type_shape(f.r#type.as_ref())))
+        })
+        .collect();
+    for oneof in &ty.oneofs {
+        for f in &oneof.fields {
+            shapes.insert(f.number, type_shape(f.r#type.as_ref()));
+        }
+    }
+    shapes
+}
+
+fn type_shape(tr: Option<&ir::TypeReference>) -> String {
+    match tr.and_then(|t| t.kind.as_ref()) {
+        Some(ir::type_reference::Kind::Scalar(s)) => format!("scalar({})", s.scalar_kind),
+        Some(ir::type_reference::Kind::MessageType(_)) => "message".to_string(),
+        Some(ir::type_reference::Kind::EnumType(_)) => "enum".to_string(),
+        Some(ir::type_reference::Kind::Map(m)) => {
+            format!("map({},{})", type_shape(m.key.as_deref()), type_shape(m.value.as_deref()))
+        }
+        None => "none".to_string(),
+    }
+}
+
+/// Rpcs are renamed only when exactly one removed and one added rpc share
+/// the full signature — `(void) -> Empty` style methods are too common to guess.
+fn rename_rpcs(old_svc: &mut ir::Service, new_svc: &ir::Service, out: &mut Vec<Violation>) {
+    let sig = |r: &ir::Rpc| (rpc_param_sig(r.input.as_ref()), rpc_param_sig(r.output.as_ref()));
+    let old_names: HashSet<String> = old_svc.rpcs.iter().map(|r| r.name.clone()).collect();
+    let new_names: HashSet<&str> = new_svc.rpcs.iter().map(|r| r.name.as_str()).collect();
+
+    let removed: Vec<usize> = (0..old_svc.rpcs.len())
+        .filter(|&i| !new_names.contains(old_svc.rpcs[i].name.as_str()))
+        .collect();
+    let added: Vec<&ir::Rpc> = new_svc.rpcs.iter().filter(|r| !old_names.contains(&r.name)).collect();
+
+    for &i in &removed {
+        let old_sig = sig(&old_svc.rpcs[i]);
+        let mut matches = added.iter().filter(|&&r| sig(r) == old_sig);
+        let (Some(new_rpc), None) = (matches.next(), matches.next()) else {
+            continue;
+        };
+        let rivals = removed.iter().filter(|&&j| sig(&old_svc.rpcs[j]) == old_sig).count();
+        if rivals != 1 {
+            continue;
+        }
+        let old_rpc = &mut old_svc.rpcs[i];
+        out.push(Violation {
+            level: Level::Warning,
+            code: "B003",
+            message: format!("rpc renamed: {}: {} → {}", new_svc.full_name, old_rpc.name, new_rpc.name),
+            context: format!("{}.{}", new_svc.full_name, new_rpc.name),
+        });
+        old_rpc.name = new_rpc.name.clone();
+    }
+}
+
+fn rename_violation(level: Level, kind: &str, from: &str, to: &str) -> Violation {
+    Violation {
+        level,
+        code: "B003",
+        message: format!("{} renamed: {} → {}", kind, from, to),
+        context: to.to_string(),
+    }
+}
+
+/// Rename types and enums, and every reference to them, in place.
+fn apply_type_renames(module: &mut ir::Module, names: &HashMap<String, String>) {
+    fn rename(name: &mut String, full_name: &mut String, names: &HashMap<String, String>) {
+        if let Some(to) = names.get(full_name.as_str()) {
+            *name = short_name(to).to_string();
+            *full_name = to.clone();
+        }
+    }
+
+    fn rename_ref(tr: &mut ir::TypeReference, names: &HashMap<String, String>) {
+        match tr.kind.as_mut() {
+            Some(ir::type_reference::Kind::MessageType(m)) => rename(&mut m.name, &mut m.full_name, names),
+            Some(ir::type_reference::Kind::EnumType(e)) => rename(&mut e.name, &mut e.full_name, names),
+            Some(ir::type_reference::Kind::Map(m)) => {
+                if let Some(key) = m.key.as_deref_mut() {
+                    rename_ref(key, names);
+                }
+                if let Some(value) = m.value.as_deref_mut() {
+                    rename_ref(value, names);
+                }
+            }
+            Some(ir::type_reference::Kind::Scalar(_)) | None => {}
+        }
+    }
+
+    fn rename_type(ty: &mut ir::Type, names: &HashMap<String, String>) {
+        rename(&mut ty.name, &mut ty.full_name, names);
+        for field in &mut ty.fields {
+            if let Some(tr) = field.r#type.as_mut() {
+                rename_ref(tr, names);
+            }
+        }
+        for oneof in &mut ty.oneofs {
+            for field in &mut oneof.fields {
+                if let Some(tr) = field.r#type.as_mut() {
+                    rename_ref(tr, names);
+                }
+            }
+        }
+        for nested in &mut ty.nested_types {
+            rename_type(nested, names);
+        }
+        for nested in &mut ty.nested_enums {
+            rename(&mut nested.name, &mut nested.full_name, names);
+        }
+    }
+
+    if names.is_empty() {
+        return;
+    }
+    for ty in &mut module.types {
+        rename_type(ty, names);
+    }
+    for e in &mut module.enums {
+        rename(&mut e.name, &mut e.full_name, names);
+    }
+    for svc in &mut module.services {
+        for rpc in &mut svc.rpcs {
+            for param in [rpc.input.as_mut(), rpc.output.as_mut()].into_iter().flatten() {
+                if let Some(tr) = param.r#type.as_mut() {
+                    rename_ref(tr, names);
+                }
+            }
+        }
+    }
+}
+
+fn short_name(full_name: &str) -> &str {
+    full_name.rsplit('.').next().unwrap_or(full_name)
+}
+
 // ── Type comparison ────────────────────────────────────────────────────
 
-fn compare_types(old: &ir::Module, new: &ir::Module, out: &mut Vec<Violation>) {
-    let old_map: HashMap<&str, &ir::Type> = old.types.iter().map(|t| (t.full_name.as_str(), t)).collect();
-    let new_map: HashMap<&str, &ir::Type> = new.types.iter().map(|t| (t.full_name.as_str(), t)).collect();
+fn compare_types(old: &ir::Module, new: &ir::Module, rules: &AnnotationRules, out: &mut Vec<Violation>) {
+    let old_map = all_types(old);
+    let new_map = all_types(new);
 
     // Removed types
     for name in old_map.keys() {
@@ -71,8 +804,8 @@ fn compare_types(old: &ir::Module, new: &ir::Module, out: &mut Vec<Violation>) {
     // Changed types
     for (name, old_type) in &old_map {
         if let Some(new_type) = new_map.get(name) {
-            compare_type_fields(name, old_type, new_type, out);
-            compare_type_oneofs(name, old_type, new_type, out);
+            compare_type_fields(name, old_type, new_type, rules, out);
+            compare_type_oneofs(name, old_type, new_type, rules, out);
         }
     }
 }
@@ -81,17 +814,34 @@ fn compare_type_fields(
     type_name: &str,
     old_type: &ir::Type,
     new_type: &ir::Type,
+    rules: &AnnotationRules,
     out: &mut Vec<Violation>,
 ) {
     let old_by_number: HashMap<u32, &ir::Field> = old_type.fields.iter().map(|f| (f.number, f)).collect();
     let new_by_number: HashMap<u32, &ir::Field> = new_type.fields.iter().map(|f| (f.number, f)).collect();
     let old_by_name: HashMap<&str, &ir::Field> = old_type.fields.iter().map(|f| (f.name.as_str(), f)).collect();
     let new_by_name: HashMap<&str, &ir::Field> = new_type.fields.iter().map(|f| (f.name.as_str(), f)).collect();
+    let old_in_oneofs = oneof_fields_by_number(old_type);
+    let new_in_oneofs = oneof_fields_by_number(new_type);
 
     // Fields removed by number
     for (num, old_field) in &old_by_number {
         if !new_by_number.contains_key(num) {
-            // Check if it was just renamed (same number exists with different name)
+            if let Some((oneof, new_f)) = new_in_oneofs.get(num) {
+                let ctx = format!("{}.{}.{}", type_name, oneof, new_f.name);
+                out.push(Violation {
+                    level: Level::Warning,
+                    code: "B017",
+                    message: format!(
+                        "field moved into oneof: {}.{} (= {}) → oneof {}",
+                        type_name, old_field.name, num, oneof
+                    ),
+                    context: ctx.clone(),
+                });
+                compare_field_types(&ctx, *num, old_field.r#type.as_ref(), new_f.r#type.as_ref(), out);
+                compare_annotations(&ctx, &old_field.annotations, &new_f.annotations, rules, out);
+                continue;
+            }
             out.push(Violation {
                 level: Level::Error,
                 code: "B010",
@@ -101,9 +851,9 @@ fn compare_type_fields(
         }
     }
 
-    // Fields added
+    // Fields added (moves out of a oneof are reported by compare_type_oneofs)
     for (num, new_field) in &new_by_number {
-        if !old_by_number.contains_key(num) {
+        if !old_by_number.contains_key(num) && !old_in_oneofs.contains_key(num) {
             out.push(Violation {
                 level: Level::Info,
                 code: "B011",
@@ -132,19 +882,7 @@ fn compare_type_fields(
             }
 
             // Wire type changed
-            let old_wire = wire_type_of(old_field.r#type.as_ref());
-            let new_wire = wire_type_of(new_field.r#type.as_ref());
-            if old_wire != new_wire {
-                out.push(Violation {
-                    level: Level::Error,
-                    code: "B013",
-                    message: format!(
-                        "field type changed: {} (= {}): {} → {}",
-                        ctx, num, old_wire, new_wire
-                    ),
-                    context: ctx.clone(),
-                });
-            }
+            compare_field_types(&ctx, *num, old_field.r#type.as_ref(), new_field.r#type.as_ref(), out);
 
             // Repeated changed
             if old_field.is_repeated != new_field.is_repeated {
@@ -171,6 +909,8 @@ fn compare_type_fields(
                     context: ctx.clone(),
                 });
             }
+
+            compare_annotations(&ctx, &old_field.annotations, &new_field.annotations, rules, out);
         }
     }
 
@@ -196,13 +936,22 @@ fn compare_type_oneofs(
     type_name: &str,
     old_type: &ir::Type,
     new_type: &ir::Type,
+    rules: &AnnotationRules,
     out: &mut Vec<Violation>,
 ) {
     let old_map: HashMap<&str, &ir::OneofGroup> = old_type.oneofs.iter().map(|o| (o.name.as_str(), o)).collect();
     let new_map: HashMap<&str, &ir::OneofGroup> = new_type.oneofs.iter().map(|o| (o.name.as_str(), o)).collect();
+    let new_regular: HashMap<u32, &ir::Field> = new_type.fields.iter().map(|f| (f.number, f)).collect();
+    let new_in_oneofs = oneof_fields_by_number(new_type);
 
-    for name in old_map.keys() {
-        if !new_map.contains_key(name) {
+    for (name, old_oneof) in &old_map {
+        // A oneof whose fields all survive elsewhere was dissolved, not removed —
+        // each field is reported as moved below.
+        let dissolved = old_oneof
+            .fields
+            .iter()
+            .all(|f| new_regular.contains_key(&f.number) || new_in_oneofs.contains_key(&f.number));
+        if !new_map.contains_key(name) && !dissolved {
             out.push(Violation {
                 level: Level::Error,
                 code: "B020",
@@ -213,14 +962,50 @@ fn compare_type_oneofs(
     }
 
     for (name, old_oneof) in &old_map {
-        if let Some(new_oneof) = new_map.get(name) {
-            let old_fields: HashMap<u32, &ir::OneofField> =
-                old_oneof.fields.iter().map(|f| (f.number, f)).collect();
-            let new_fields: HashMap<u32, &ir::OneofField> =
-                new_oneof.fields.iter().map(|f| (f.number, f)).collect();
-
-            for (num, old_f) in &old_fields {
-                if !new_fields.contains_key(num) {
+        for old_f in &old_oneof.fields {
+            let num = old_f.number;
+
+            // Moved out of the oneof into a regular field
+            if let Some(new_field) = new_regular.get(&num) {
+                let ctx = format!("{}.{}", type_name, new_field.name);
+                out.push(Violation {
+                    level: Level::Warning,
+                    code: "B018",
+                    message: format!(
+                        "field moved out of oneof: {}.{}.{} (= {})",
+                        type_name, name, old_f.name, num
+                    ),
+                    context: ctx.clone(),
+                });
+                compare_field_types(&ctx, num, old_f.r#type.as_ref(), new_field.r#type.as_ref(), out);
+                compare_annotations(&ctx, &old_f.annotations, &new_field.annotations, rules, out);
+                continue;
+            }
+
+            match new_in_oneofs.get(&num) {
+                // Same oneof — compare in place
+                Some((new_oneof, new_f)) if new_oneof == name => {
+                    let ctx = format!("{}.{}.{}", type_name, name, new_f.name);
+                    compare_field_types(&ctx, num, old_f.r#type.as_ref(), new_f.r#type.as_ref(), out);
+                    compare_annotations(&ctx, &old_f.annotations, &new_f.annotations, rules, out);
+                }
+                // Moved to a different oneof
+                Some((new_oneof, new_f)) => {
+                    let ctx = format!("{}.{}.{}", type_name, new_oneof, new_f.name);
+                    out.push(Violation {
+                        level: Level::Warning,
+                        code: "B017",
+                        message: format!(
+                            "field moved into oneof: {}.{}.{} (= {}) → oneof {}",
+                            type_name, name, old_f.name, num, new_oneof
+                        ),
+                        context: ctx.clone(),
+                    });
+                    compare_field_types(&ctx, num, old_f.r#type.as_ref(), new_f.r#type.as_ref(), out);
+                    compare_annotations(&ctx, &old_f.annotations, &new_f.annotations, rules, out);
+                }
+                // Gone — reported here only if the oneof itself survived (B020 covers the rest)
+                None if new_map.contains_key(name) => {
                     out.push(Violation {
                         level: Level::Error,
                         code: "B021",
@@ -231,16 +1016,243 @@ fn compare_type_oneofs(
                         context: format!("{}.{}.{}", type_name, name, old_f.name),
                     });
                 }
+                None => {}
             }
         }
     }
 }
 
+/// Map every oneof member of a type to `(oneof name, field)` by field number.
+fn oneof_fields_by_number(ty: &ir::Type) -> HashMap<u32, (&str, &ir::OneofField)> {
+    ty.oneofs
+        .iter()
+        .flat_map(|o| o.fields.iter().map(move |f| (f.number, (o.name.as_str(), f))))
+        .collect()
+}
+
+/// Compare the wire types of two fields sharing a number.
+fn compare_field_types(
+    ctx: &str,
+    num: u32,
+    old: Option<&ir::TypeReference>,
+    new: Option<&ir::TypeReference>,
+    out: &mut Vec<Violation>,
+) {
+    let old_wire = wire_type_of(old);
+    let new_wire = wire_type_of(new);
+    if old_wire == new_wire {
+        return;
+    }
+
+    if let (Some(old_map), Some(new_map)) = (map_type_of(old), map_type_of(new)) {
+        let old_key = wire_type_of(old_map.key.as_deref());
+        let new_key = wire_type_of(new_map.key.as_deref());
+        if old_key != new_key {
+            out.push(Violation {
+                level: Level::Error,
+                code: "B019",
+                message: format!("map key type changed: {} (= {}): {} → {}", ctx, num, old_key, new_key),
+                context: ctx.to_string(),
+            });
+        }
+        let old_value = wire_type_of(old_map.value.as_deref());
+        let new_value = wire_type_of(new_map.value.as_deref());
+        if old_value != new_value {
+            out.push(Violation {
+                level: Level::Error,
+                code: "B013",
+                message: format!("map value type changed: {} (= {}): {} → {}", ctx, num, old_value, new_value),
+                context: ctx.to_string(),
+            });
+        }
+        return;
+    }
+
+    out.push(Violation {
+        level: Level::Error,
+        code: "B013",
+        message: format!("field type changed: {} (= {}): {} → {}", ctx, num, old_wire, new_wire),
+        context: ctx.to_string(),
+    });
+}
+
+// ── Annotation comparison ──────────────────────────────────────────────
+
+fn compare_annotations(
+    ctx: &str,
+    old_anns: &[ir::AnnotationCall],
+    new_anns: &[ir::AnnotationCall],
+    rules: &AnnotationRules,
+    out: &mut Vec<Violation>,
+) {
+    let old_map: HashMap<(&str, &str), &ir::AnnotationCall> =
+        old_anns.iter().map(|a| ((a.library.as_str(), a.name.as_str()), a)).collect();
+    let new_map: HashMap<(&str, &str), &ir::AnnotationCall> =
+        new_anns.iter().map(|a| ((a.library.as_str(), a.name.as_str()), a)).collect();
+
+    for (&(library, name), old_ann) in &old_map {
+        match new_map.get(&(library, name)) {
+            Some(new_ann) => compare_annotation_args(ctx, old_ann, new_ann, rules, out),
+            None if rules.is_constraint(library, name) => out.push(Violation {
+                level: Level::Info,
+                code: "B051",
+                message: format!("validation loosened: {} removed from {}", annotation_display(old_ann), ctx),
+                context: ctx.to_string(),
+            }),
+            None => out.push(Violation {
+                level: Level::Info,
+                code: "B052",
+                message: format!("annotation removed: {} from {}", annotation_display(old_ann), ctx),
+                context: ctx.to_string(),
+            }),
+        }
+    }
+
+    for (&(library, name), new_ann) in &new_map {
+        if old_map.contains_key(&(library, name)) {
+            continue;
+        }
+        if rules.is_constraint(library, name) {
+            out.push(Violation {
+                level: Level::Warning,
+                code: "B050",
+                message: format!("validation tightened: {} added to {}", annotation_display(new_ann), ctx),
+                context: ctx.to_string(),
+            });
+        } else {
+            out.push(Violation {
+                level: Level::Info,
+                code: "B052",
+                message: format!("annotation added: {} to {}", annotation_display(new_ann), ctx),
+                context: ctx.to_string(),
+            });
+        }
+    }
+}
+
+fn compare_annotation_args(
+    ctx: &str,
+    old_ann: &ir::AnnotationCall,
+    new_ann: &ir::AnnotationCall,
+    rules: &AnnotationRules,
+    out: &mut Vec<Violation>,
+) {
+    let old_args: HashMap<&str, &ir::AnnotationLiteral> = old_ann
+        .arguments
+        .iter()
+        .filter_map(|a| a.value.as_ref().map(|v| (a.name.as_str(), v)))
+        .collect();
+    let new_args: HashMap<&str, &ir::AnnotationLiteral> = new_ann
+        .arguments
+        .iter()
+        .filter_map(|a| a.value.as_ref().map(|v| (a.name.as_str(), v)))
+        .collect();
+
+    let params: BTreeSet<&str> = old_args.keys().chain(new_args.keys()).copied().collect();
+    for param in params {
+        let old_val = old_args.get(param).copied();
+        let new_val = new_args.get(param).copied();
+        if old_val == new_val {
+            continue;
+        }
+
+        let ann = if param.is_empty() {
+            annotation_display(new_ann)
+        } else {
+            format!("{}({})", annotation_display(new_ann), param)
+        };
+        let change = format!("{} → {}", literal_display(old_val), literal_display(new_val));
+
+        match rules.param_rule(&new_ann.library, &new_ann.name, param) {
+            Some(rule) if is_tightening(rule, old_val, new_val) => out.push(Violation {
+                level: Level::Warning,
+                code: "B050",
+                message: format!("validation tightened: {} on {}: {}", ann, ctx, change),
+                context: ctx.to_string(),
+            }),
+            Some(_) => out.push(Violation {
+                level: Level::Info,
+                code: "B051",
+                message: format!("validation loosened: {} on {}: {}", ann, ctx, change),
+                context: ctx.to_string(),
+            }),
+            None => out.push(Violation {
+                level: Level::Info,
+                code: "B052",
+                message: format!("annotation changed: {} on {}: {}", ann, ctx, change),
+                context: ctx.to_string(),
+            }),
+        }
+    }
+}
+
+/// Whether moving a parameter from `old` to `new` rejects previously valid data.
+/// An absent bound means "unbounded"; an absent flag means `false`.
+fn is_tightening(
+    rule: ParamRule,
+    old: Option<&ir::AnnotationLiteral>,
+    new: Option<&ir::AnnotationLiteral>,
+) -> bool {
+    use ir::annotation_literal::Value;
+
+    let is_flag = |l: Option<&ir::AnnotationLiteral>| {
+        matches!(l.and_then(|l| l.value.as_ref()), Some(Value::BoolValue(_)))
+    };
+    let unset = if is_flag(old) || is_flag(new) { Some(0.0) } else { None };
+    let old_num = old.and_then(literal_number).or(unset);
+    let new_num = new.and_then(literal_number).or(unset);
+
+    match rule {
+        ParamRule::TightensOnChange => new.is_some(),
+        ParamRule::TightensOnIncrease => match (old_num, new_num) {
+            (Some(o), Some(n)) => n > o,
+            (None, Some(_)) => true,
+            (_, None) => false,
+        },
+        ParamRule::TightensOnDecrease => match (old_num, new_num) {
+            (Some(o), Some(n)) => n < o,
+            (None, Some(_)) => true,
+            (_, None) => false,
+        },
+    }
+}
+
+fn literal_number(lit: &ir::AnnotationLiteral) -> Option<f64> {
+    use ir::annotation_literal::Value;
+    match lit.value.as_ref()? {
+        Value::IntValue(i) => Some(*i as f64),
+        Value::FloatValue(f) => Some(*f),
+        Value::BoolValue(b) => Some(if *b { 1.0 } else { 0.0 }),
+        _ => None,
+    }
+}
+
+fn literal_display(lit: Option<&ir::AnnotationLiteral>) -> String {
+    use ir::annotation_literal::Value;
+    match lit.and_then(|l| l.value.as_ref()) {
+        None => "unset".to_string(),
+        Some(Value::StringValue(s)) => format!("{:?}", s),
+        Some(Value::IntValue(i)) => i.to_string(),
+        Some(Value::FloatValue(f)) => f.to_string(),
+        Some(Value::BoolValue(b)) => b.to_string(),
+        Some(Value::StructValue(_)) => "{...}".to_string(),
+        Some(Value::ListValue(l)) => format!("[{} item(s)]", l.values.len()),
+    }
+}
+
+fn annotation_display(ann: &ir::AnnotationCall) -> String {
+    if ann.library.is_empty() {
+        format!("@{}", ann.name)
+    } else {
+        format!("@{}::{}", ann.library, ann.name)
+    }
+}
+
 // ── Enum comparison ────────────────────────────────────────────────────
 
 fn compare_enums(old: &ir::Module, new: &ir::Module, out: &mut Vec<Violation>) {
-    let old_map: HashMap<&str, &ir::Enum> = old.enums.iter().map(|e| (e.full_name.as_str(), e)).collect();
-    let new_map: HashMap<&str, &ir::Enum> = new.enums.iter().map(|e| (e.full_name.as_str(), e)).collect();
+    let old_map = all_enums(old);
+    let new_map = all_enums(new);
 
     for name in old_map.keys() {
         if !new_map.contains_key(name) {
@@ -332,6 +1344,17 @@ fn compare_services(old: &ir::Module, new: &ir::Module, out: &mut Vec<Violation>
         }
     }
 
+    for name in new_map.keys() {
+        if !old_map.contains_key(name) {
+            out.push(Violation {
+                level: Level::Info,
+                code: "B046",
+                message: format!("service added: {}", name),
+                context: name.to_string(),
+            });
+        }
+    }
+
     for (name, old_svc) in &old_map {
         if let Some(new_svc) = new_map.get(name) {
             let old_rpcs: HashMap<&str, &ir::Rpc> =
@@ -412,7 +1435,43 @@ fn compare_services(old: &ir::Module, new: &ir::Module, out: &mut Vec<Violation>
     }
 }
 
-// ── Helpers ────────────────────────────────────────────────────────────
+// ── Helpers ────────────────────────────────────────────────────────────
+
+/// All types of a module keyed by full name, including nested types
+/// (`Warehouse.Zone`) at any depth.
+fn all_types(module: &ir::Module) -> HashMap<&str, &ir::Type> {
+    fn walk<'a>(ty: &'a ir::Type, out: &mut HashMap<&'a str, &'a ir::Type>) {
+        out.entry(ty.full_name.as_str()).or_insert(ty);
+        for nested in &ty.nested_types {
+            walk(nested, out);
+        }
+    }
+
+    let mut out = HashMap::new();
+    for ty in &module.types {
+        walk(ty, &mut out);
+    }
+    out
+}
+
+/// All enums of a module keyed by full name, including enums nested in types.
+fn all_enums(module: &ir::Module) -> HashMap<&str, &ir::Enum> {
+    let mut out: HashMap<&str, &ir::Enum> =
+        module.enums.iter().map(|e| (e.full_name.as_str(), e)).collect();
+    for ty in all_types(module).into_values() {
+        for nested in &ty.nested_enums {
+            out.entry(nested.full_name.as_str()).or_insert(nested);
+        }
+    }
+    out
+}
+
+fn map_type_of(tr: Option<&ir::TypeReference>) -> Option<&ir::MapType> {
+    match tr?.kind.as_ref()? {
+        ir::type_reference::Kind::Map(m) => Some(m.as_ref()),
+        _ => None,
+    }
+}
 
 /// Simplified wire type string for comparison.
 fn wire_type_of(tr: Option<&ir::TypeReference>) -> String {
@@ -447,10 +1506,10 @@ fn rpc_param_sig(param: Option<&ir::RpcParam>) -> String {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
-    fn make_module(types: Vec<ir::Type>, enums: Vec<ir::Enum>, services: Vec<ir::Service>) -> ir::Module {
+    pub(crate) fn make_module(types: Vec<ir::Type>, enums: Vec<ir::Enum>, services: Vec<ir::Service>) -> ir::Module {
         ir::Module {
             package: "test".to_string(),
             types,
@@ -459,7 +1518,7 @@ mod tests {
         }
     }
 
-    fn make_field(name: &str, number: u32, scalar_kind: i32) -> ir::Field {
+    pub(crate) fn make_field(name: &str, number: u32, scalar_kind: i32) -> ir::Field {
         ir::Field {
             name: name.to_string(),
             number,
@@ -475,7 +1534,7 @@ mod tests {
         }
     }
 
-    fn make_type(name: &str, fields: Vec<ir::Field>) -> ir::Type {
+    pub(crate) fn make_type(name: &str, fields: Vec<ir::Field>) -> ir::Type {
         ir::Type {
             name: name.to_string(),
             full_name: format!("test.{}", name),
@@ -656,6 +1715,325 @@ mod tests {
         assert!(violations.iter().any(|v| v.level == Level::Error && v.code == "B032"));
     }
 
+    fn make_validate(name: &str, args: Vec<(&str, ir::annotation_literal::Value)>) -> ir::AnnotationCall {
+        ir::AnnotationCall {
+            library: "validate".to_string(),
+            name: name.to_string(),
+            arguments: args
+                .into_iter()
+                .map(|(n, v)| ir::AnnotationArgument {
+                    name: n.to_string(),
+                    value: Some(ir::AnnotationLiteral { value: Some(v) }),
+                })
+                .collect(),
+            definition: None,
+            location: None,
+        }
+    }
+
+    fn annotated_module(annotations: Vec<ir::AnnotationCall>) -> ir::Module {
+        let mut field = make_field("age", 1, 6);
+        field.annotations = annotations;
+        make_module(vec![make_type("User", vec![field])], Vec::new(), Vec::new())
+    }
+
+    #[test]
+    fn raised_range_min_is_warning() {
+        use ir::annotation_literal::Value;
+        let old = annotated_module(vec![make_validate("Range", vec![("min", Value::IntValue(1))])]);
+        let new = annotated_module(vec![make_validate("Range", vec![("min", Value::IntValue(5))])]);
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].level, Level::Warning);
+        assert_eq!(violations[0].code, "B050");
+    }
+
+    #[test]
+    fn lowered_range_min_is_info() {
+        use ir::annotation_literal::Value;
+        let old = annotated_module(vec![make_validate("Range", vec![("min", Value::IntValue(5))])]);
+        let new = annotated_module(vec![make_validate("Range", vec![("min", Value::IntValue(1))])]);
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].level, Level::Info);
+        assert_eq!(violations[0].code, "B051");
+    }
+
+    #[test]
+    fn lowered_length_max_is_warning() {
+        use ir::annotation_literal::Value;
+        let old = annotated_module(vec![make_validate("Length", vec![("max", Value::IntValue(255))])]);
+        let new = annotated_module(vec![make_validate("Length", vec![("max", Value::IntValue(100))])]);
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B050"));
+    }
+
+    #[test]
+    fn added_required_is_warning_removed_is_info() {
+        let plain = annotated_module(Vec::new());
+        let required = annotated_module(vec![make_validate("Required", Vec::new())]);
+
+        let violations = compare(&plain, &required);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B050"));
+
+        let violations = compare(&required, &plain);
+        assert!(violations.iter().all(|v| v.level == Level::Info));
+        assert!(violations.iter().any(|v| v.code == "B051"));
+    }
+
+    #[test]
+    fn changed_pattern_is_warning() {
+        use ir::annotation_literal::Value;
+        let old = annotated_module(vec![make_validate("Pattern", vec![("pattern", Value::StringValue("^[a-z]+$".into()))])]);
+        let new = annotated_module(vec![make_validate("Pattern", vec![("pattern", Value::StringValue("^[a-z]{3,}$".into()))])]);
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B050"));
+    }
+
+    #[test]
+    fn exclusive_flag_set_is_warning() {
+        use ir::annotation_literal::Value;
+        let old = annotated_module(vec![make_validate("Range", vec![("min", Value::IntValue(0))])]);
+        let new = annotated_module(vec![make_validate("Range", vec![
+            ("min", Value::IntValue(0)),
+            ("exclusive_min", Value::BoolValue(true)),
+        ])]);
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B050"));
+    }
+
+    #[test]
+    fn declared_library_rules_apply() {
+        use ir::annotation_literal::Value;
+        let ann = |max: i64| ir::AnnotationCall {
+            library: "limits".to_string(),
+            name: "Size".to_string(),
+            arguments: vec![ir::AnnotationArgument {
+                name: "max".to_string(),
+                value: Some(ir::AnnotationLiteral { value: Some(Value::IntValue(max)) }),
+            }],
+            definition: None,
+            location: None,
+        };
+        let old = annotated_module(vec![ann(10)]);
+        let new = annotated_module(vec![ann(5)]);
+
+        // Unknown library — only an informational annotation change
+        let violations = compare(&old, &new);
+        assert!(violations.iter().all(|v| v.level == Level::Info && v.code == "B052"));
+
+        let mut rules = AnnotationRules::std();
+        rules.add_param("limits", "Size", "max", ParamRule::TightensOnDecrease);
+        let violations = compare_with_rules(&old, &new, &rules);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B050"));
+    }
+
+    #[test]
+    fn rules_from_annotation_definition() {
+        use crate::pipeline::{compile, CompileOptions, SourceFile};
+        let result = compile(&[SourceFile {
+            name: "limits.ogham".to_string(),
+            content: "package limits;\n\
+                annotation Size for field(string) {\n    @tightens(decrease) uint32? max;\n    @tightens(change) string unit;\n}\n\
+                @tightens\nannotation Set for field {\n}\n\
+                annotation Doc for field {\n    string text;\n}\n"
+                .to_string(),
+        }], &CompileOptions::default());
+        assert!(!result.diagnostics.has_errors(), "errors: {:?}", result.diagnostics.all());
+
+        let mut rules = AnnotationRules::default();
+        rules.extend_from_hir(&result.interner, &result.arenas);
+        assert_eq!(rules.param_rule("limits", "Size", "max"), Some(ParamRule::TightensOnDecrease));
+        assert_eq!(rules.param_rule("limits", "Size", "unit"), Some(ParamRule::TightensOnChange));
+        assert!(rules.is_constraint("limits", "Size"));
+        assert!(rules.is_constraint("limits", "Set"));
+        assert!(!rules.is_constraint("limits", "Doc"));
+    }
+
+    #[test]
+    fn std_rules_come_from_validate_ogham() {
+        use ParamRule::*;
+
+        let rules = AnnotationRules::std();
+        let mut constraints: Vec<&str> = rules.constraints.iter().map(|(_, name)| name.as_str()).collect();
+        constraints.sort();
+        assert_eq!(constraints, ["Entries", "Items", "Length", "NotEmpty", "Pattern", "Range", "Required"]);
+        assert_eq!(rules.params.len(), 13);
+        for (name, param, rule) in [
+            ("Length", "min", TightensOnIncrease),
+            ("Length", "max", TightensOnDecrease),
+            ("Length", "exact", TightensOnChange),
+            ("Pattern", "pattern", TightensOnChange),
+            ("Range", "min", TightensOnIncrease),
+            ("Range", "max", TightensOnDecrease),
+            ("Range", "exclusive_min", TightensOnIncrease),
+            ("Range", "exclusive_max", TightensOnIncrease),
+            ("Items", "min", TightensOnIncrease),
+            ("Items", "max", TightensOnDecrease),
+            ("Items", "unique", TightensOnIncrease),
+            ("Entries", "min", TightensOnIncrease),
+            ("Entries", "max", TightensOnDecrease),
+        ] {
+            assert_eq!(rules.param_rule("validate", name, param), Some(rule), "{}.{}", name, param);
+        }
+    }
+
+    fn make_oneof(name: &str, fields: Vec<ir::Field>) -> ir::OneofGroup {
+        ir::OneofGroup {
+            name: name.to_string(),
+            fields: fields
+                .into_iter()
+                .map(|f| ir::OneofField {
+                    name: f.name,
+                    number: f.number,
+                    r#type: f.r#type,
+                    annotations: f.annotations,
+                    mapping: None,
+                    location: None,
+                })
+                .collect(),
+            annotations: Vec::new(),
+            location: None,
+        }
+    }
+
+    fn make_map_field(name: &str, number: u32, key_kind: i32, value_kind: i32) -> ir::Field {
+        let scalar = |scalar_kind| {
+            Box::new(ir::TypeReference {
+                kind: Some(ir::type_reference::Kind::Scalar(ir::ScalarType { scalar_kind })),
+            })
+        };
+        let mut field = make_field(name, number, 0);
+        field.r#type = Some(ir::TypeReference {
+            kind: Some(ir::type_reference::Kind::Map(Box::new(ir::MapType {
+                key: Some(scalar(key_kind)),
+                value: Some(scalar(value_kind)),
+            }))),
+        });
+        field
+    }
+
+    #[test]
+    fn nested_type_changes_detected() {
+        let mut old_zone = make_type("Zone", vec![make_field("code", 1, 2), make_field("floor", 2, 6)]);
+        old_zone.full_name = "test.Warehouse.Zone".to_string();
+        let mut new_zone = make_type("Zone", vec![make_field("code", 1, 2)]);
+        new_zone.full_name = "test.Warehouse.Zone".to_string();
+
+        let mut old_wh = make_type("Warehouse", vec![make_field("id", 1, 2)]);
+        old_wh.nested_types.push(old_zone);
+        let mut new_wh = make_type("Warehouse", vec![make_field("id", 1, 2)]);
+        new_wh.nested_types.push(new_zone);
+
+        let old = make_module(vec![old_wh], Vec::new(), Vec::new());
+        let new = make_module(vec![new_wh], Vec::new(), Vec::new());
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.code == "B010" && v.context == "test.Warehouse.Zone.floor"));
+    }
+
+    #[test]
+    fn nested_enum_changes_detected() {
+        let nested = |values| ir::Enum {
+            name: "Kind".to_string(),
+            full_name: "test.Warehouse.Kind".to_string(),
+            values,
+            annotations: Vec::new(),
+            location: None,
+            module: None,
+        };
+        let mut old_wh = make_type("Warehouse", Vec::new());
+        old_wh.nested_enums.push(nested(vec![make_enum_val("Unspecified", 0), make_enum_val("Cold", 1)]));
+        let mut new_wh = make_type("Warehouse", Vec::new());
+        new_wh.nested_enums.push(nested(vec![make_enum_val("Unspecified", 0)]));
+
+        let old = make_module(vec![old_wh], Vec::new(), Vec::new());
+        let new = make_module(vec![new_wh], Vec::new(), Vec::new());
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.level == Level::Error && v.code == "B032"));
+    }
+
+    #[test]
+    fn field_moved_into_oneof_is_warning() {
+        let old = make_module(
+            vec![make_type("Payment", vec![make_field("id", 1, 2), make_field("card", 2, 2)])],
+            Vec::new(),
+            Vec::new(),
+        );
+        let mut new_type = make_type("Payment", vec![make_field("id", 1, 2)]);
+        new_type.oneofs.push(make_oneof("method", vec![make_field("card", 2, 2)]));
+        let new = make_module(vec![new_type], Vec::new(), Vec::new());
+
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B017"));
+        assert!(!violations.iter().any(|v| v.code == "B010"), "{:?}", violations);
+    }
+
+    #[test]
+    fn moved_fields_still_compare_type_and_annotations() {
+        use ir::annotation_literal::Value;
+        let mut card = make_field("card", 2, 2);
+        card.annotations = vec![make_validate("Range", vec![("min", Value::IntValue(1))])];
+        let old = make_module(vec![make_type("Payment", vec![card])], Vec::new(), Vec::new());
+
+        let mut moved = make_field("card", 2, 6);
+        moved.annotations = vec![make_validate("Range", vec![("min", Value::IntValue(5))])];
+        let mut into = make_type("Payment", Vec::new());
+        into.oneofs.push(make_oneof("method", vec![moved]));
+        let violations = compare(&old, &make_module(vec![into.clone()], Vec::new(), Vec::new()));
+        for code in ["B017", "B013", "B050"] {
+            assert!(violations.iter().any(|v| v.code == code), "{} missing: {:?}", code, violations);
+        }
+
+        // And back out, and into another oneof
+        let back = make_module(vec![make_type("Payment", vec![make_field("card", 2, 2)])], Vec::new(), Vec::new());
+        let violations = compare(&make_module(vec![into.clone()], Vec::new(), Vec::new()), &back);
+        for code in ["B018", "B013", "B051"] {
+            assert!(violations.iter().any(|v| v.code == code), "{} missing: {:?}", code, violations);
+        }
+        let mut other = make_type("Payment", Vec::new());
+        other.oneofs.push(make_oneof("via", vec![make_field("card", 2, 2)]));
+        let violations = compare(
+            &make_module(vec![into], Vec::new(), Vec::new()),
+            &make_module(vec![other], Vec::new(), Vec::new()),
+        );
+        for code in ["B017", "B013", "B051"] {
+            assert!(violations.iter().any(|v| v.code == code), "{} missing: {:?}", code, violations);
+        }
+    }
+
+    #[test]
+    fn field_moved_out_of_oneof_is_warning() {
+        let mut old_type = make_type("Payment", vec![make_field("id", 1, 2)]);
+        old_type.oneofs.push(make_oneof("method", vec![make_field("card", 2, 2)]));
+        let old = make_module(vec![old_type], Vec::new(), Vec::new());
+        let new = make_module(
+            vec![make_type("Payment", vec![make_field("id", 1, 2), make_field("card", 2, 2)])],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B018"));
+        assert!(!violations.iter().any(|v| v.code == "B011" || v.code == "B020"), "{:?}", violations);
+    }
+
+    #[test]
+    fn map_key_change_is_error() {
+        let old = make_module(
+            vec![make_type("Stock", vec![make_map_field("counts", 1, 2, 6)])], // map<string, int32>
+            Vec::new(),
+            Vec::new(),
+        );
+        let new = make_module(
+            vec![make_type("Stock", vec![make_map_field("counts", 1, 7, 6)])], // map<int64, int32>
+            Vec::new(),
+            Vec::new(),
+        );
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].code, "B019");
+    }
+
     #[test]
     fn enum_value_renamed_is_warning() {
         let old = make_module(
@@ -685,4 +2063,334 @@ mod tests {
         let violations = compare(&old, &new);
         assert!(violations.iter().any(|v| v.level == Level::Warning && v.code == "B034"));
     }
+
+    fn make_status_enum(name: &str, values: Vec<ir::EnumValue>) -> ir::Enum {
+        ir::Enum {
+            name: name.to_string(),
+            full_name: format!("test.{}", name),
+            values,
+            annotations: Vec::new(),
+            location: None,
+            module: None,
+        }
+    }
+
+    fn message_field(name: &str, number: u32, type_name: &str) -> ir::Field {
+        let mut field = make_field(name, number, 0);
+        field.r#type = Some(ir::TypeReference {
+            kind: Some(ir::type_reference::Kind::MessageType(ir::MessageType {
+                name: type_name.to_string(),
+                full_name: format!("test.{}", type_name),
+                fields: Vec::new(),
+                oneofs: Vec::new(),
+                nested_enums: Vec::new(),
+                annotations: Vec::new(),
+            })),
+        });
+        field
+    }
+
+    fn make_rpc(name: &str, input: &str, output: &str) -> ir::Rpc {
+        let param = |type_name: &str| {
+            Some(ir::RpcParam {
+                is_void: false,
+                is_stream: false,
+                r#type: message_field("", 0, type_name).r#type,
+            })
+        };
+        ir::Rpc {
+            name: name.to_string(),
+            input: param(input),
+            output: param(output),
+            annotations: Vec::new(),
+            location: None,
+        }
+    }
+
+    fn make_service(name: &str, rpcs: Vec<ir::Rpc>) -> ir::Service {
+        ir::Service {
+            name: name.to_string(),
+            full_name: format!("test.{}", name),
+            rpcs,
+            annotations: Vec::new(),
+            location: None,
+            module: None,
+        }
+    }
+
+    #[test]
+    fn type_renamed_reported_once() {
+        let old = make_module(
+            vec![
+                make_type("User", vec![make_field("email", 1, 2), make_field("age", 2, 6)]),
+                make_type("Team", vec![message_field("owner", 1, "User")]),
+            ],
+            Vec::new(),
+            Vec::new(),
+        );
+        let new = make_module(
+            vec![
+                make_type("Account", vec![make_field("email", 1, 2), make_field("age", 2, 6)]),
+                make_type("Team", vec![message_field("owner", 1, "Account")]),
+            ],
+            Vec::new(),
+            Vec::new(),
+        );
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].code, "B003");
+        assert_eq!(violations[0].level, Level::Warning);
+        assert!(violations[0].message.contains("test.User → test.Account"));
+    }
+
+    #[test]
+    fn nested_declarations_move_with_renamed_type() {
+        let nested = |outer: &str| {
+            let address = message_field("address", 3, &format!("{}.Address", outer));
+            let mut ty = make_type(outer, vec![make_field("email", 1, 2), make_field("age", 2, 6), address]);
+            ty.nested_types = vec![
+                ir::Type { name: "Address".to_string(), ..make_type(&format!("{}.Address", outer), Vec::new()) },
+                ir::Type {
+                    name: "Phone".to_string(),
+                    ..make_type(&format!("{}.Phone", outer), vec![make_field("number", 1, 2)])
+                },
+            ];
+            ty
+        };
+        let old = make_module(vec![nested("User")], Vec::new(), Vec::new());
+        let new = make_module(vec![nested("Account")], Vec::new(), Vec::new());
+
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].code, "B003");
+        assert!(violations[0].message.contains("test.User → test.Account"));
+    }
+
+    #[test]
+    fn renamed_type_still_compared() {
+        let old = make_module(
+            vec![make_type(
+                "User",
+                vec![
+                    make_field("email", 1, 2),
+                    make_field("age", 2, 6),
+                    make_field("name", 3, 2),
+                    make_field("bio", 4, 2),
+                    make_field("city", 5, 2),
+                ],
+            )],
+            Vec::new(),
+            Vec::new(),
+        );
+        let new = make_module(
+            vec![make_type(
+                "Account",
+                vec![
+                    make_field("email", 1, 2),
+                    make_field("age", 2, 6),
+                    make_field("name", 3, 2),
+                    make_field("bio", 4, 2),
+                ],
+            )],
+            Vec::new(),
+            Vec::new(),
+        );
+        let violations = compare(&old, &new);
+        assert!(violations.iter().any(|v| v.code == "B003"));
+        assert!(violations.iter().any(|v| v.code == "B010" && v.context == "test.Account.city"));
+        assert!(!violations.iter().any(|v| v.code == "B001" || v.code == "B002"));
+    }
+
+    #[test]
+    fn dissimilar_types_not_renamed() {
+        let old = make_module(
+            vec![make_type("User", vec![make_field("email", 1, 2), make_field("age", 2, 6)])],
+            Vec::new(),
+            Vec::new(),
+        );
+        let new = make_module(
+            vec![make_type("Order", vec![make_field("total", 1, 6), make_field("note", 2, 2)])],
+            Vec::new(),
+            Vec::new(),
+        );
+        let violations = compare(&old, &new);
+        assert!(!violations.iter().any(|v| v.code == "B003"));
+        assert!(violations.iter().any(|v| v.code == "B001"));
+        assert!(violations.iter().any(|v| v.code == "B002"));
+    }
+
+    #[test]
+    fn enum_renamed_reported_once() {
+        let values = || {
+            vec![make_enum_val("Unspecified", 0), make_enum_val("Active", 1), make_enum_val("Banned", 2)]
+        };
+        let old = make_module(Vec::new(), vec![make_status_enum("Status", values())], Vec::new());
+        let new = make_module(Vec::new(), vec![make_status_enum("UserStatus", values())], Vec::new());
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].code, "B003");
+        assert_eq!(violations[0].context, "test.UserStatus");
+    }
+
+    #[test]
+    fn service_renamed_reported_once() {
+        let rpcs = || vec![make_rpc("Get", "GetRequest", "User"), make_rpc("List", "ListRequest", "Users")];
+        let old = make_module(Vec::new(), Vec::new(), vec![make_service("UserAPI", rpcs())]);
+        let new = make_module(Vec::new(), Vec::new(), vec![make_service("AccountAPI", rpcs())]);
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].code, "B003");
+        assert!(violations[0].message.starts_with("service renamed"));
+    }
+
+    #[test]
+    fn service_rename_is_error() {
+        let rpcs = || vec![make_rpc("Get", "GetRequest", "User")];
+        let old = make_module(Vec::new(), Vec::new(), vec![make_service("UserAPI", rpcs())]);
+        let new = make_module(Vec::new(), Vec::new(), vec![make_service("AccountAPI", rpcs())]);
+        let violations = compare(&old, &new);
+        assert_eq!(violations[0].level, Level::Error);
+        assert_eq!(violations[0].context, "test.AccountAPI");
+    }
+
+    #[test]
+    fn rpc_renamed_reported_once() {
+        let old = make_module(
+            Vec::new(),
+            Vec::new(),
+            vec![make_service("UserAPI", vec![make_rpc("Fetch", "GetRequest", "User")])],
+        );
+        let new = make_module(
+            Vec::new(),
+            Vec::new(),
+            vec![make_service("UserAPI", vec![make_rpc("GetUser", "GetRequest", "User")])],
+        );
+        let violations = compare(&old, &new);
+        assert_eq!(violations.len(), 1, "{:?}", violations);
+        assert_eq!(violations[0].code, "B003");
+        assert_eq!(violations[0].context, "test.UserAPI.GetUser");
+    }
+
+    #[test]
+    fn ambiguous_rpc_rename_not_guessed() {
+        let old = make_module(
+            Vec::new(),
+            Vec::new(),
+            vec![make_service(
+                "UserAPI",
+                vec![make_rpc("Ping", "Empty", "Empty"), make_rpc("Health", "Empty", "Empty")],
+            )],
+        );
+        let new = make_module(
+            Vec::new(),
+            Vec::new(),
+            vec![make_service(
+                "UserAPI",
+                vec![make_rpc("Alive", "Empty", "Empty"), make_rpc("Ready", "Empty", "Empty")],
+            )],
+        );
+        let violations = compare(&old, &new);
+        assert!(!violations.iter().any(|v| v.code == "B003"));
+        assert_eq!(violations.iter().filter(|v| v.code == "B041").count(), 2);
+    }
+
+    #[test]
+    fn source_locations_from_reparse() {
+        let sources = vec![SourceFile {
+            name: "shop.ogham".to_string(),
+            content: r#"package shop;
+
+type User {
+    string email = 1;
+
+    type Address {
+        string city = 1;
+    }
+}
+
+enum Status {
+    Active = 1;
+}
+
+service UserAPI {
+    rpc Get(void) -> User;
+}
+"#
+            .to_string(),
+        }];
+        let locations = SourceLocations::from_sources(&sources);
+        let line = |ctx: &str| locations.find(ctx).map(|l| l.line);
+
+        assert_eq!(locations.find("shop.User").map(|l| l.file.as_str()), Some("shop.ogham"));
+        assert_eq!(line("shop.User"), Some(3));
+        assert_eq!(line("shop.User.email"), Some(4));
+        assert_eq!(line("shop.User.Address.city"), Some(7));
+        assert_eq!(line("shop.Status.Active"), Some(12));
+        assert_eq!(line("shop.UserAPI.Get"), Some(16));
+        // Unknown members fall back to the enclosing declaration
+        assert_eq!(line("shop.User.phone"), Some(3));
+        assert_eq!(line("shop.Order"), None);
+    }
+
+    #[test]
+    fn renamed_declarations_are_located_by_their_old_name() {
+        let source = |content: &str| {
+            SourceLocations::from_sources(&[SourceFile { name: "test.ogham".to_string(), content: content.to_string() }])
+        };
+        let old_locations = source(
+            "package test;\n\ntype User {\n    string email = 1;\n    int32 age = 2;\n    string name = 3;\n    string city = 4;\n    string zip = 5;\n}\n",
+        );
+        let fields = |first: &str, extra: Option<&str>| {
+            let mut fields = vec![make_field(first, 1, 2), make_field("age", 2, 6)];
+            fields.extend(["name", "city", "zip"].iter().zip(3..).map(|(name, n)| make_field(name, n, 2)));
+            fields.extend(extra.map(|name| make_field(name, 6, 2)));
+            fields
+        };
+        let old = make_module(vec![make_type("User", fields("email", None))], Vec::new(), Vec::new());
+        let new = make_module(vec![make_type("Account", fields("mail", Some("id")))], Vec::new(), Vec::new());
+
+        let violations = compare(&old, &new);
+        let renames = renames(&old, &new);
+        let old_line = |code: &str| {
+            let v = violations.iter().find(|v| v.code == code).unwrap_or_else(|| panic!("{} missing: {:?}", code, violations));
+            old_locations.find(&renames.old_context(&v.context)).map(|l| l.line)
+        };
+        assert_eq!(old_line("B003"), Some(3));
+        assert_eq!(old_line("B012"), Some(4));
+        // Added members point at the renamed type
+        assert_eq!(old_line("B011"), Some(3));
+        assert_eq!(renames.old_context("test.Account.age"), "test.User.age");
+        assert_eq!(renames.old_context("test.Other"), "test.Other");
+    }
+
+    #[test]
+    fn consumed_by_keeps_reachable_declarations() {
+        let old = make_module(
+            vec![
+                make_type("Order", vec![message_field("customer", 1, "Customer")]),
+                make_type("Customer", vec![make_field("email", 1, 2)]),
+                make_type("Receipt", vec![make_field("id", 1, 2)]),
+                make_type("Invoice", vec![make_field("total", 1, 7)]),
+            ],
+            Vec::new(),
+            vec![make_service("OrderAPI", vec![make_rpc("Get", "Order", "Receipt")])],
+        );
+        let mut new = old.clone();
+        for ty in &mut new.types[1..] {
+            ty.fields.clear();
+        }
+
+        let roots: HashSet<String> = ["test.Order".to_string()].into();
+        let violations = consumed_by(compare(&old, &new), &old, &new, &roots);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].context, "test.Customer.email");
+
+        let roots: HashSet<String> = ["test.Order".to_string(), "test.OrderAPI".to_string()].into();
+        let mut contexts: Vec<String> = consumed_by(compare(&old, &new), &old, &new, &roots)
+            .into_iter()
+            .map(|v| v.context)
+            .collect();
+        contexts.sort();
+        assert_eq!(contexts, ["test.Customer.email", "test.Receipt.id"]);
+    }
 }
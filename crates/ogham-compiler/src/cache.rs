@@ -0,0 +1,439 @@
+//! The module cache in `$OGHAM_HOME`: `pkg/mod/<module>@<version>/` for
+//! versions fetched from a proxy, `git/checkouts/<module>/<commit>/` for
+//! git dependencies and `gen/<plugin>/<key>/` for plugin responses replayed
+//! by `ogham generate`.
+//!
+//! Every entry is fetched into a scratch directory and renamed into place
+//! while holding an exclusive lock on `<entry>.lock`, so a process that sees
+//! the directory sees it complete, and two processes fetching the same entry
+//! do the work once. Each entry carries two hidden files, left out of its
+//! content hash: `.ogham-hash` with the hash taken when it was fetched, and
+//! `.ogham-used`, whose modification time is the last resolution that used it.
+
+use crate::pkg::hash_dir;
+use fs4::fs_std::FileExt;
+use ogham_proto::oghamproto::compiler::OghamCompileRequest;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const HASH_FILE: &str = ".ogham-hash";
+const USED_FILE: &str = ".ogham-used";
+
+/// Where an entry came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    /// `pkg/mod`, extracted from a proxy.
+    Module,
+    /// `git/checkouts`, one commit of a git dependency.
+    Git,
+    /// `gen`, one plugin response.
+    Generated,
+}
+
+/// One cached module version or git checkout.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub module: String,
+    /// `v1.2.0`, the commit of a git checkout, or the key of a plugin
+    /// response.
+    pub version: String,
+    pub kind: EntryKind,
+    pub path: PathBuf,
+    /// Bytes on disk, including `.git`.
+    pub size: u64,
+    /// Last resolution that used the entry; its fetch time if it was never
+    /// used since.
+    pub last_used: SystemTime,
+}
+
+/// Outcome of re-hashing one entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verified {
+    Ok,
+    /// Fetched before hashes were recorded; nothing to compare with.
+    Unrecorded,
+    Mismatch { recorded: String, actual: String },
+}
+
+/// Every entry under `home`, sorted by module and version.
+pub fn list(home: &Path) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    let mod_root = home.join("pkg").join("mod");
+    walk(&mod_root, &mut |dir| {
+        let rel = relative(&mod_root, dir);
+        let (module, version) = rel.rsplit_once('@')?;
+        Some((module.to_string(), version.to_string(), EntryKind::Module))
+    }, &mut entries)?;
+
+    let git_root = home.join("git").join("checkouts");
+    walk(&git_root, &mut |dir| {
+        let commit = dir.file_name()?.to_str()?;
+        if commit.len() != 40 || !commit.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let module = relative(&git_root, dir.parent()?);
+        Some((module, commit.to_string(), EntryKind::Git))
+    }, &mut entries)?;
+
+    let gen_root = home.join("gen");
+    walk(&gen_root, &mut |dir| {
+        let key = dir.file_name()?.to_str()?;
+        if !is_key(key) {
+            return None;
+        }
+        let plugin = relative(&gen_root, dir.parent()?);
+        Some((plugin, key.to_string(), EntryKind::Generated))
+    }, &mut entries)?;
+
+    entries.sort_by(|a, b| (&a.module, &a.version).cmp(&(&b.module, &b.version)));
+    Ok(entries)
+}
+
+/// Remove entries not used for `older_than`. Returns what was removed.
+pub fn prune(home: &Path, older_than: Duration) -> Result<Vec<Entry>, String> {
+    let cutoff = SystemTime::now().checked_sub(older_than).unwrap_or(SystemTime::UNIX_EPOCH);
+    remove(home, list(home)?.into_iter().filter(|e| e.last_used < cutoff).collect())
+}
+
+/// Remove every entry of `module`, or the whole cache. Returns what was removed.
+pub fn clean(home: &Path, module: Option<&str>) -> Result<Vec<Entry>, String> {
+    let entries = list(home)?
+        .into_iter()
+        .filter(|e| module.map_or(true, |m| e.module == m))
+        .collect();
+    remove(home, entries)
+}
+
+/// Re-hash every entry and compare with the hash recorded when it was
+/// fetched.
+pub fn verify(home: &Path) -> Result<Vec<(Entry, Verified)>, String> {
+    let mut results = Vec::new();
+    for entry in list(home)? {
+        let _lock = lock(&entry.path)?;
+        let verified = match std::fs::read_to_string(entry.path.join(HASH_FILE)) {
+            Err(_) => Verified::Unrecorded,
+            Ok(recorded) => {
+                let recorded = recorded.trim().to_string();
+                let actual = hash_dir(&entry.path)?;
+                if actual == recorded {
+                    Verified::Ok
+                } else {
+                    Verified::Mismatch { recorded, actual }
+                }
+            }
+        };
+        results.push((entry, verified));
+    }
+    Ok(results)
+}
+
+/// Exclusive lock on a cache entry, held until the file is dropped. Taken
+/// around fetching an entry and removing it.
+pub(crate) fn lock(entry: &Path) -> Result<File, String> {
+    let name = entry.file_name().unwrap_or_default().to_string_lossy();
+    let path = entry.with_file_name(format!("{}.lock", name));
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("cannot create cache dir: {}", e))?;
+    }
+    let file = File::create(&path).map_err(|e| format!("cannot open {}: {}", path.display(), e))?;
+    file.lock_exclusive()
+        .map_err(|e| format!("cannot lock {}: {}", path.display(), e))?;
+    Ok(file)
+}
+
+/// Scratch directory an entry is fetched into before it is renamed into place.
+pub(crate) fn partial_dir(entry: &Path) -> PathBuf {
+    let name = entry.file_name().unwrap_or_default().to_string_lossy();
+    entry.with_file_name(format!(".{}.partial-{}", name, std::process::id()))
+}
+
+/// Record the content hash of a freshly fetched entry.
+pub(crate) fn record_hash(dir: &Path, hash: &str) -> Result<(), String> {
+    hide_from_git(dir);
+    std::fs::write(dir.join(HASH_FILE), format!("{}\n", hash))
+        .map_err(|e| format!("cannot write {}: {}", dir.join(HASH_FILE).display(), e))
+}
+
+/// Mark an entry as used now. Failures are ignored: a read-only cache is
+/// still usable.
+pub(crate) fn touch(dir: &Path) {
+    hide_from_git(dir);
+    let _ = std::fs::write(dir.join(USED_FILE), "");
+}
+
+/// Keep the bookkeeping files out of `git status` in a checkout, which
+/// reports changes to a git dependency.
+fn hide_from_git(dir: &Path) {
+    let info = dir.join(".git").join("info");
+    if !info.parent().is_some_and(Path::is_dir) {
+        return;
+    }
+    let exclude = info.join("exclude");
+    let mut content = std::fs::read_to_string(&exclude).unwrap_or_default();
+    let missing: Vec<&str> = [HASH_FILE, USED_FILE]
+        .into_iter()
+        .filter(|name| !content.lines().any(|line| line == format!("/{}", name)))
+        .collect();
+    if missing.is_empty() {
+        return;
+    }
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    for name in missing {
+        content.push_str(&format!("/{}\n", name));
+    }
+    let _ = std::fs::create_dir_all(&info);
+    let _ = std::fs::write(&exclude, content);
+}
+
+/// Bookkeeping files inside an entry, never copied out of the cache.
+pub(crate) fn is_metadata(name: &str) -> bool {
+    name == HASH_FILE || name == USED_FILE
+}
+
+// ── Generated output ───────────────────────────────────────────────────
+
+const RESPONSE_FILE: &str = "response.pb";
+
+/// Cache key of a plugin run: SHA-256 over the request — module, options,
+/// output directory and compiler version — and the plugin binary. The
+/// request is hashed as JSON, whose object keys are sorted, because the
+/// order in which protobuf maps are encoded is not stable.
+pub fn generation_key(request: &OghamCompileRequest, binary: &Path) -> Result<String, String> {
+    let json = serde_json::to_value(request).map_err(|e| format!("cannot serialize request: {}", e))?;
+    let mut file = File::open(binary).map_err(|e| format!("cannot read {}: {}", binary.display(), e))?;
+    let mut binary_hash = Sha256::new();
+    std::io::copy(&mut file, &mut binary_hash).map_err(|e| format!("cannot read {}: {}", binary.display(), e))?;
+
+    let mut key = Sha256::new();
+    key.update(json.to_string());
+    key.update([0]);
+    key.update(binary_hash.finalize());
+    Ok(format!("{:x}", key.finalize()))
+}
+
+/// The response `plugin` gave for `key`, if it is cached.
+pub fn generated(home: &Path, plugin: &str, key: &str) -> Option<Vec<u8>> {
+    let dir = home.join("gen").join(plugin).join(key);
+    let response = std::fs::read(dir.join(RESPONSE_FILE)).ok()?;
+    touch(&dir);
+    Some(response)
+}
+
+/// Cache the response `plugin` gave for `key`.
+pub fn store_generated(home: &Path, plugin: &str, key: &str, response: &[u8]) -> Result<(), String> {
+    let dir = home.join("gen").join(plugin).join(key);
+    let _lock = lock(&dir)?;
+    if dir.is_dir() {
+        return Ok(());
+    }
+    let partial = partial_dir(&dir);
+    let _ = std::fs::remove_dir_all(&partial);
+    let stored = std::fs::create_dir_all(&partial)
+        .and_then(|_| std::fs::write(partial.join(RESPONSE_FILE), response))
+        .map_err(|e| format!("cannot write {}: {}", partial.display(), e))
+        .and_then(|_| record_hash(&partial, &hash_dir(&partial)?))
+        .and_then(|_| {
+            std::fs::rename(&partial, &dir).map_err(|e| format!("cannot write {}: {}", dir.display(), e))
+        });
+    if stored.is_err() {
+        let _ = std::fs::remove_dir_all(&partial);
+    }
+    stored
+}
+
+fn is_key(name: &str) -> bool {
+    name.len() == 64 && name.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+// ── Helpers ────────────────────────────────────────────────────────────
+
+/// Module, version and kind of an entry directory; `None` for a directory
+/// that only contains entries.
+type Classify<'a> = dyn FnMut(&Path) -> Option<(String, String, EntryKind)> + 'a;
+
+/// Find entry directories below `root`, descending into directories that
+/// `entry` does not classify. Hidden directories (scratch fetches, `.git`) are skipped.
+fn walk(
+    root: &Path,
+    entry: &mut Classify,
+    out: &mut Vec<Entry>,
+) -> Result<(), String> {
+    fn visit(
+        dir: &Path,
+        entry: &mut Classify,
+        out: &mut Vec<Entry>,
+    ) -> Result<(), String> {
+        let children = std::fs::read_dir(dir).map_err(|e| format!("cannot read {}: {}", dir.display(), e))?;
+        for child in children.flatten() {
+            let path = child.path();
+            if !path.is_dir() || child.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            match entry(&path) {
+                Some((module, version, kind)) => out.push(Entry {
+                    module,
+                    version,
+                    kind,
+                    size: dir_size(&path),
+                    last_used: last_used(&path),
+                    path,
+                }),
+                None => visit(&path, entry, out)?,
+            }
+        }
+        Ok(())
+    }
+
+    if !root.is_dir() {
+        return Ok(());
+    }
+    visit(root, entry, out)
+}
+
+/// `/`-separated path of `dir` below `root` — the module path.
+fn relative(root: &Path, dir: &Path) -> String {
+    dir.strip_prefix(root)
+        .unwrap_or(dir)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(children) = std::fs::read_dir(dir) else { return 0 };
+    children
+        .flatten()
+        .map(|child| match child.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&child.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+fn last_used(dir: &Path) -> SystemTime {
+    [dir.join(USED_FILE), dir.join(HASH_FILE), dir.to_path_buf()]
+        .iter()
+        .find_map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Delete entries under their locks, then the lock files and any module
+/// directories left empty.
+fn remove(home: &Path, entries: Vec<Entry>) -> Result<Vec<Entry>, String> {
+    for entry in &entries {
+        let lock_file = lock(&entry.path)?;
+        std::fs::remove_dir_all(&entry.path)
+            .map_err(|e| format!("cannot remove {}: {}", entry.path.display(), e))?;
+        drop(lock_file);
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy();
+        let _ = std::fs::remove_file(entry.path.with_file_name(format!("{}.lock", name)));
+
+        let roots = [home.join("pkg").join("mod"), home.join("git").join("checkouts"), home.join("gen")];
+        let mut parent = entry.path.parent();
+        while let Some(dir) = parent.filter(|d| !roots.iter().any(|r| r == d)) {
+            if std::fs::remove_dir(dir).is_err() {
+                break; // not empty
+            }
+            parent = dir.parent();
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_home() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ogham-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn add(home: &Path, rel: &str) -> PathBuf {
+        let dir = home.join(rel);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.ogham"), "package lib;\n").unwrap();
+        record_hash(&dir, &hash_dir(&dir).unwrap()).unwrap();
+        dir
+    }
+
+    #[test]
+    fn list_prune_clean_verify() {
+        let home = temp_home();
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        add(&home, "pkg/mod/example.com/org/db@v1.0.0");
+        let git = add(&home, &format!("git/checkouts/example.com/org/api/{}", commit));
+        fs::create_dir_all(home.join("pkg/mod/example.com/org/.db@v1.1.0.partial-1")).unwrap();
+
+        let entries = list(&home).unwrap();
+        let names: Vec<(&str, &str, EntryKind)> =
+            entries.iter().map(|e| (e.module.as_str(), e.version.as_str(), e.kind)).collect();
+        assert_eq!(names, [
+            ("example.com/org/api", commit, EntryKind::Git),
+            ("example.com/org/db", "v1.0.0", EntryKind::Module),
+        ]);
+        assert!(entries[0].size > 0);
+
+        fs::write(git.join("lib.ogham"), "package changed;\n").unwrap();
+        let verified = verify(&home).unwrap();
+        assert!(matches!(verified[0].1, Verified::Mismatch { .. }));
+        assert_eq!(verified[1].1, Verified::Ok);
+
+        // Nothing is older than an hour; everything is older than nothing
+        assert!(prune(&home, Duration::from_secs(3600)).unwrap().is_empty());
+        touch(&git);
+        let removed = clean(&home, Some("example.com/org/api")).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(!home.join("git/checkouts/example.com").exists());
+        assert_eq!(list(&home).unwrap().len(), 1);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(prune(&home, Duration::from_millis(10)).unwrap().len(), 1);
+        assert!(list(&home).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn generated_responses_are_cached_entries() {
+        let home = std::env::temp_dir().join(format!("ogham-gen-cache-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&home);
+        fs::create_dir_all(&home).unwrap();
+        let binary = home.join("ogham-gen-test");
+        fs::write(&binary, "v1").unwrap();
+
+        let mut request = OghamCompileRequest::default();
+        request.options.insert("a".into(), "1".into());
+        request.options.insert("b".into(), "2".into());
+        let key = generation_key(&request, &binary).unwrap();
+        let mut reordered = OghamCompileRequest::default();
+        reordered.options.insert("b".into(), "2".into());
+        reordered.options.insert("a".into(), "1".into());
+        assert_eq!(generation_key(&reordered, &binary).unwrap(), key);
+        fs::write(&binary, "v2").unwrap();
+        assert_ne!(generation_key(&request, &binary).unwrap(), key);
+
+        assert_eq!(generated(&home, "ogham-gen-test", &key), None);
+        store_generated(&home, "ogham-gen-test", &key, b"response").unwrap();
+        assert_eq!(generated(&home, "ogham-gen-test", &key).as_deref(), Some(&b"response"[..]));
+
+        let entries = list(&home).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].module.as_str(), entries[0].kind), ("ogham-gen-test", EntryKind::Generated));
+        assert_eq!(verify(&home).unwrap()[0].1, Verified::Ok);
+        clean(&home, None).unwrap();
+        assert!(!home.join("gen").join("ogham-gen-test").exists());
+
+        let _ = fs::remove_dir_all(&home);
+    }
+}
@@ -0,0 +1,304 @@
+//! Schema changelog between two compiled IR modules.
+//!
+//! Built on `breaking::compare_with_rules`: every violation — including INFO
+//! ones — becomes a changelog entry, grouped by package and by the top-level
+//! declaration (type, enum, service) it belongs to.
+
+use crate::breaking::{self, AnnotationRules, Level, Violation};
+use ogham_proto::oghamproto::ir;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+// ── Changelog types ────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Renamed,
+    /// Type, number, cardinality or signature changed.
+    Changed,
+    Annotation,
+}
+
+impl ChangeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+            ChangeKind::Renamed => "renamed",
+            ChangeKind::Changed => "changed",
+            ChangeKind::Annotation => "annotation",
+        }
+    }
+
+    fn of(code: &str) -> Self {
+        match code {
+            "B002" | "B011" | "B031" | "B033" | "B042" | "B046" => ChangeKind::Added,
+            "B001" | "B010" | "B020" | "B021" | "B030" | "B032" | "B040" | "B041" => ChangeKind::Removed,
+            "B003" | "B012" | "B034" => ChangeKind::Renamed,
+            "B050" | "B051" | "B052" => ChangeKind::Annotation,
+            _ => ChangeKind::Changed,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub violation: Violation,
+}
+
+impl Change {
+    /// ERROR and WARNING changes break existing clients.
+    pub fn is_breaking(&self) -> bool {
+        self.violation.level != Level::Info
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DeclarationChanges {
+    /// Fully qualified name of the top-level type, enum or service.
+    pub name: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PackageChanges {
+    pub package: String,
+    pub declarations: Vec<DeclarationChanges>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Changelog {
+    pub packages: Vec<PackageChanges>,
+}
+
+impl Changelog {
+    pub fn is_empty(&self) -> bool {
+        self.packages.is_empty()
+    }
+
+    pub fn changes(&self) -> impl Iterator<Item = &Change> {
+        self.packages
+            .iter()
+            .flat_map(|p| &p.declarations)
+            .flat_map(|d| &d.changes)
+    }
+}
+
+// ── Public API ─────────────────────────────────────────────────────────
+
+/// Build the changelog from `old` to `new`.
+pub fn build(old: &ir::Module, new: &ir::Module, rules: &AnnotationRules) -> Changelog {
+    // Declaration full name → package, from both sides so removed and
+    // added declarations are both attributed.
+    let mut owners: HashMap<String, String> = HashMap::new();
+    collect_owners(old, &mut owners);
+    collect_owners(new, &mut owners);
+
+    let mut grouped: BTreeMap<String, BTreeMap<String, Vec<Change>>> = BTreeMap::new();
+    for violation in breaking::compare_with_rules(old, new, rules) {
+        let declaration = owning_declaration(&violation.context, &owners);
+        let package = owners
+            .get(&declaration)
+            .cloned()
+            .unwrap_or_else(|| new.package.clone());
+        grouped
+            .entry(package)
+            .or_default()
+            .entry(declaration)
+            .or_default()
+            .push(Change {
+                kind: ChangeKind::of(violation.code),
+                violation,
+            });
+    }
+
+    let packages = grouped
+        .into_iter()
+        .map(|(package, declarations)| PackageChanges {
+            package,
+            declarations: declarations
+                .into_iter()
+                .map(|(name, mut changes)| {
+                    changes.sort_by(|a, b| {
+                        a.kind
+                            .cmp(&b.kind)
+                            .then_with(|| a.violation.context.cmp(&b.violation.context))
+                            .then_with(|| a.violation.message.cmp(&b.violation.message))
+                    });
+                    DeclarationChanges { name, changes }
+                })
+                .collect(),
+        })
+        .collect();
+
+    Changelog { packages }
+}
+
+/// Render the changelog as Markdown, suitable for release notes.
+pub fn to_markdown(changelog: &Changelog, from: &str, to: &str) -> String {
+    let mut out = format!("# Schema changes: {} → {}\n", from, to);
+
+    if changelog.is_empty() {
+        out.push_str("\nNo schema changes.\n");
+        return out;
+    }
+
+    let breaking = changelog.changes().filter(|c| c.is_breaking()).count();
+    if breaking > 0 {
+        out.push_str(&format!("\n**{} breaking change(s).**\n", breaking));
+    }
+
+    for package in &changelog.packages {
+        out.push_str(&format!("\n## Package `{}`\n", package.package));
+        for decl in &package.declarations {
+            out.push_str(&format!("\n### `{}`\n\n", decl.name));
+            for change in &decl.changes {
+                let v = &change.violation;
+                let marker = match v.level {
+                    Level::Error => " — **breaking**",
+                    Level::Warning => " — *breaks JSON / generated code*",
+                    Level::Info => "",
+                };
+                out.push_str(&format!(
+                    "- **{}** {}{} `{}`\n",
+                    capitalize(change.kind.as_str()),
+                    v.message,
+                    marker,
+                    v.code
+                ));
+            }
+        }
+    }
+
+    out
+}
+
+// ── Helpers ────────────────────────────────────────────────────────────
+
+fn collect_owners(module: &ir::Module, owners: &mut HashMap<String, String>) {
+    let package_of = |info: Option<&ir::ModuleInfo>| {
+        info.map(|m| m.package.clone())
+            .filter(|p| !p.is_empty())
+            .unwrap_or_else(|| module.package.clone())
+    };
+    for ty in &module.types {
+        owners
+            .entry(ty.full_name.clone())
+            .or_insert_with(|| package_of(ty.module.as_ref()));
+    }
+    for e in &module.enums {
+        owners
+            .entry(e.full_name.clone())
+            .or_insert_with(|| package_of(e.module.as_ref()));
+    }
+    for svc in &module.services {
+        owners
+            .entry(svc.full_name.clone())
+            .or_insert_with(|| package_of(svc.module.as_ref()));
+    }
+}
+
+/// The outermost known declaration that `context` is, or is a member of —
+/// nested types are listed under their parent.
+fn owning_declaration(context: &str, owners: &HashMap<String, String>) -> String {
+    let mut best: Option<&str> = None;
+    for name in owners.keys() {
+        let owns = context == name.as_str()
+            || (context.starts_with(name.as_str()) && context[name.len()..].starts_with('.'));
+        if !owns {
+            continue;
+        }
+        match best {
+            Some(b) if b.len() <= name.len() => {}
+            _ => best = Some(name),
+        }
+    }
+    best.unwrap_or(context).to_string()
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::breaking::tests::{make_field, make_module, make_type};
+
+    #[test]
+    fn groups_by_package_and_declaration() {
+        let old = make_module(
+            vec![
+                make_type("User", vec![make_field("email", 1, 2), make_field("phone", 2, 2)]),
+                make_type("Order", vec![make_field("id", 1, 2)]),
+            ],
+            Vec::new(),
+            Vec::new(),
+        );
+        let new = make_module(
+            vec![
+                make_type("User", vec![make_field("email", 1, 2), make_field("nick", 3, 2)]),
+                make_type("Order", vec![make_field("id", 1, 2)]),
+                make_type("Cart", vec![make_field("id", 1, 2), make_field("owner", 2, 2)]),
+            ],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        let changelog = build(&old, &new, &AnnotationRules::std());
+        assert_eq!(changelog.packages.len(), 1);
+        assert_eq!(changelog.packages[0].package, "test");
+
+        let names: Vec<&str> = changelog.packages[0]
+            .declarations
+            .iter()
+            .map(|d| d.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["test.Cart", "test.User"]);
+
+        let user = &changelog.packages[0].declarations[1];
+        let kinds: Vec<ChangeKind> = user.changes.iter().map(|c| c.kind).collect();
+        assert_eq!(kinds, vec![ChangeKind::Added, ChangeKind::Removed]);
+        assert!(user.changes[1].is_breaking());
+    }
+
+    #[test]
+    fn markdown_lists_changes() {
+        let old = make_module(
+            vec![make_type("User", vec![make_field("email", 1, 2)])],
+            Vec::new(),
+            Vec::new(),
+        );
+        let new = make_module(
+            vec![make_type("User", vec![make_field("mail", 1, 2)])],
+            Vec::new(),
+            Vec::new(),
+        );
+
+        let changelog = build(&old, &new, &AnnotationRules::std());
+        let md = to_markdown(&changelog, "v1.0.0", "v1.1.0");
+        assert!(md.starts_with("# Schema changes: v1.0.0 → v1.1.0"));
+        assert!(md.contains("## Package `test`"));
+        assert!(md.contains("### `test.User`"));
+        assert!(md.contains("- **Renamed** field renamed: test.User.email → mail (= 1)"));
+    }
+
+    #[test]
+    fn empty_changelog() {
+        let module = make_module(
+            vec![make_type("User", vec![make_field("email", 1, 2)])],
+            Vec::new(),
+            Vec::new(),
+        );
+        let changelog = build(&module, &module, &AnnotationRules::std());
+        assert!(changelog.is_empty());
+        assert!(to_markdown(&changelog, "a", "b").contains("No schema changes."));
+    }
+}
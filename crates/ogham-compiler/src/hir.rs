@@ -233,6 +233,8 @@ pub struct AnnotationDef {
     pub targets: Vec<AnnotationTarget>,
     pub params: Vec<AnnotationParamDef>,
     pub compositions: Vec<AnnotationCompositionRef>,
+    /// Declared with `@tightens` — adding the annotation tightens validation.
+    pub tightens: bool,
     pub loc: Loc,
 }
 
@@ -249,6 +251,19 @@ pub struct AnnotationParamDef {
     pub ty: ResolvedType,
     pub is_optional: bool,
     pub default_value: Option<LiteralValue>,
+    /// Declared with `@tightens(increase|decrease|change)` — used by breaking change detection.
+    pub tightens: Option<Tightening>,
+}
+
+/// Direction in which changing a parameter value makes an annotation stricter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tightening {
+    /// Raising the value tightens the constraint (e.g. `min`).
+    Increase,
+    /// Lowering the value tightens the constraint (e.g. `max`).
+    Decrease,
+    /// Any change may tighten the constraint (e.g. `pattern`).
+    Change,
 }
 
 #[derive(Debug, Clone)]
@@ -20,7 +20,7 @@ pub struct ParsedFile {
     pub import_path: String,
 }
 
-fn make_loc(file: Sym, node: &SyntaxNode) -> Loc {
+pub(crate) fn make_loc(file: Sym, node: &SyntaxNode) -> Loc {
     let range = node.text_range();
     Loc {
         file: Some(file),
@@ -339,6 +339,7 @@ pub fn collect(
             targets,
             params: Vec::new(),
             compositions: Vec::new(),
+            tightens: false,
             loc: make_loc(file_sym, ann_decl.syntax()),
         };
 
@@ -12,5 +12,11 @@ pub mod resolve;
 pub mod stdlib;
 pub mod lower;
 pub mod breaking;
+pub mod changelog;
+pub mod cache;
+pub mod plugin;
+pub mod output;
 pub mod pkg;
+pub mod proxy;
+pub mod workspace;
 pub mod pipeline;
@@ -1,7 +1,8 @@
-//! Parsing of ogham.mod.yaml and ogham.gen.yaml project files.
+//! Parsing of ogham.mod.yaml, ogham.gen.yaml, ogham.lock.yaml, ogham.work.yaml
+//! and vendor/modules.yaml project files.
 
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 // ── ogham.mod.yaml ─────────────────────────────────────────────────────
@@ -97,6 +98,9 @@ pub struct GenFile {
 pub struct GenerateSection {
     #[serde(default)]
     pub plugins: Vec<PluginEntry>,
+    /// Default plugin timeout (e.g., `30s`, `5m`)
+    #[serde(default)]
+    pub timeout: Option<String>,
 }
 
 /// A single plugin invocation.
@@ -111,11 +115,67 @@ pub struct PluginEntry {
     /// gRPC address (overrides stdio for name plugins)
     #[serde(default)]
     pub grpc: Option<String>,
+    /// Run the plugin's WebAssembly module in-process instead of a binary
+    #[serde(default)]
+    pub wasm: bool,
     /// Output directory for generated files
     pub out: String,
     /// Key-value options passed to the plugin
     #[serde(default)]
     pub opts: HashMap<String, String>,
+    /// How long the plugin may run (e.g., `30s`, `5m`)
+    #[serde(default)]
+    pub timeout: Option<String>,
+}
+
+// ── ogham.lock.yaml ────────────────────────────────────────────────────
+
+/// Lock file — exact versions and commits chosen by `ogham install`.
+#[derive(Debug, Deserialize, Default)]
+pub struct LockFile {
+    #[serde(default)]
+    pub locked: BTreeMap<String, LockedDep>,
+}
+
+/// A single locked dependency.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct LockedDep {
+    pub version: String,
+    /// Commit hash for git dependencies
+    #[serde(default)]
+    pub commit: Option<String>,
+    /// Content hash of the dependency's schema tree (`h1:<sha256>`)
+    #[serde(default)]
+    pub hash: Option<String>,
+    /// "path", "git" or "cache"
+    pub source: String,
+}
+
+// ── vendor/modules.yaml ────────────────────────────────────────────────
+
+/// What `ogham vendor` copied into vendor/.
+#[derive(Debug, Deserialize, Default)]
+pub struct VendorManifest {
+    #[serde(default)]
+    pub modules: BTreeMap<String, VendoredModule>,
+}
+
+/// One module under vendor/<module>.
+#[derive(Debug, Deserialize, Clone, PartialEq, Eq)]
+pub struct VendoredModule {
+    pub version: String,
+    /// Content hash of the vendored copy (`h1:<sha256>`)
+    pub hash: String,
+}
+
+// ── ogham.work.yaml ────────────────────────────────────────────────────
+
+/// Workspace file — modules developed together in one tree.
+#[derive(Debug, Deserialize, Default)]
+pub struct WorkFile {
+    /// Member module directories, relative to the workspace file
+    #[serde(default)]
+    pub members: Vec<String>,
 }
 
 // ── Loading ────────────────────────────────────────────────────────────
@@ -144,6 +204,214 @@ pub fn load_gen_file(dir: &Path) -> Result<GenFile, String> {
         .map_err(|e| format!("invalid ogham.gen.yaml: {}", e))
 }
 
+/// Load ogham.lock.yaml from a directory. `Ok(None)` if the project has no lock file.
+pub fn load_lock_file(dir: &Path) -> Result<Option<LockFile>, String> {
+    let path = dir.join("ogham.lock.yaml");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("invalid ogham.lock.yaml: {}", e))
+}
+
+/// Load vendor/modules.yaml from a project directory. `Ok(None)` if the
+/// project is not vendored.
+pub fn load_vendor_manifest(dir: &Path) -> Result<Option<VendorManifest>, String> {
+    let path = dir.join("vendor").join("modules.yaml");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&content)
+        .map(Some)
+        .map_err(|e| format!("invalid vendor/modules.yaml: {}", e))
+}
+
+/// Load ogham.work.yaml from a directory.
+pub fn load_work_file(dir: &Path) -> Result<WorkFile, String> {
+    let path = dir.join("ogham.work.yaml");
+    if !path.exists() {
+        return Err(format!("ogham.work.yaml not found in {}", dir.display()));
+    }
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    serde_yaml::from_str(&content)
+        .map_err(|e| format!("invalid ogham.work.yaml: {}", e))
+}
+
+// ── Editing ────────────────────────────────────────────────────────────
+//
+// ogham.mod.yaml is edited line by line so comments and formatting survive,
+// but entries are found by parsing keys, never by searching for substrings.
+
+/// A `key:` line: its indentation, the unquoted key and what follows the colon.
+fn line_key(line: &str) -> Option<(usize, String, &str)> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('-') {
+        return None;
+    }
+    let indent = line.len() - trimmed.len();
+    let (key, rest) = match trimmed.chars().next()? {
+        q @ ('"' | '\'') => {
+            let end = trimmed[1..].find(q)? + 1;
+            (trimmed[1..end].to_string(), trimmed[end + 1..].strip_prefix(':')?)
+        }
+        _ => {
+            let colon = trimmed
+                .match_indices(':')
+                .map(|(i, _)| i)
+                .find(|&i| trimmed[i + 1..].is_empty() || trimmed[i + 1..].starts_with([' ', '\t']))?;
+            (trimmed[..colon].trim_end().to_string(), &trimmed[colon + 1..])
+        }
+    };
+    Some((indent, key, rest.trim()))
+}
+
+/// Whether a line is blank or only a comment.
+fn is_trivia(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+/// End (exclusive) of the block starting at `start` whose own line is
+/// indented by `indent`: the next content line indented no deeper.
+/// Trailing blank and comment lines are left to what follows.
+fn block_end(lines: &[&str], start: usize, indent: usize) -> usize {
+    let mut end = start + 1;
+    let mut last = start;
+    while end < lines.len() {
+        let line = lines[end];
+        if !is_trivia(line) {
+            if line.len() - line.trim_start().len() <= indent {
+                break;
+            }
+            last = end;
+        }
+        end += 1;
+    }
+    last + 1
+}
+
+/// Where the `require:` entries are: the header line, the end of the
+/// section, and each entry's key with its line span.
+struct RequireSection {
+    header: usize,
+    end: usize,
+    entries: Vec<(String, usize, usize)>,
+}
+
+fn require_section(lines: &[&str]) -> Option<RequireSection> {
+    let header = lines
+        .iter()
+        .position(|l| matches!(line_key(l), Some((0, ref key, _)) if key == "require"))?;
+    let end = block_end(lines, header, 0);
+
+    let mut entries = Vec::new();
+    let mut i = header + 1;
+    while i < end {
+        match line_key(lines[i]) {
+            Some((indent, key, _)) if indent > 0 => {
+                let entry_end = block_end(lines, i, indent);
+                entries.push((key, i, entry_end));
+                i = entry_end;
+            }
+            _ => i += 1,
+        }
+    }
+    Some(RequireSection { header, end, entries })
+}
+
+/// Quote a scalar that YAML would otherwise read as something else
+/// (`*` is an alias, `>=` starts a folded block, ...).
+fn yaml_scalar(s: &str) -> String {
+    let plain = s
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphanumeric() || c == '^' || c == '~' || c == '.' || c == '/')
+        && !s.contains(": ")
+        && !s.contains(" #");
+    if plain {
+        s.to_string()
+    } else {
+        format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Lines of a `require` entry at the given indentation.
+fn render_require(module: &str, entry: &RequireEntry, indent: &str) -> Vec<String> {
+    let key = format!("{}{}:", indent, yaml_scalar(module));
+    let field = |name: &str, value: &str| format!("{}  {}: {}", indent, name, yaml_scalar(value));
+    match entry {
+        RequireEntry::Version(v) => vec![format!("{} {}", key, yaml_scalar(v))],
+        RequireEntry::Git { git, tag, branch, rev } => {
+            let mut lines = vec![key, field("git", git)];
+            for (name, value) in [("tag", tag), ("branch", branch), ("rev", rev)] {
+                if let Some(value) = value {
+                    lines.push(field(name, value));
+                }
+            }
+            lines
+        }
+        RequireEntry::Path { path } => vec![key, field("path", path)],
+    }
+}
+
+/// Add a `require` entry to ogham.mod.yaml content, or replace the existing
+/// entry for `module`. Everything else — comments, order, other entries —
+/// is kept as written.
+pub fn set_require(content: &str, module: &str, entry: &RequireEntry) -> String {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+
+    match require_section(&lines) {
+        None => {
+            if out.last().is_some_and(|l| !l.trim().is_empty()) {
+                out.push(String::new());
+            }
+            out.push("require:".to_string());
+            out.extend(render_require(module, entry, "  "));
+        }
+        Some(section) => {
+            let indent = section
+                .entries
+                .first()
+                .map(|&(_, start, _)| lines[start].len() - lines[start].trim_start().len())
+                .unwrap_or(2);
+            let rendered = render_require(module, entry, &" ".repeat(indent));
+            match section.entries.iter().find(|(key, ..)| key == module) {
+                Some(&(_, start, end)) => {
+                    out.splice(start..end, rendered);
+                }
+                None => {
+                    // `require: {}` becomes a block
+                    if line_key(lines[section.header]).is_some_and(|(_, _, rest)| rest.starts_with('{')) {
+                        out[section.header] = "require:".to_string();
+                    }
+                    out.splice(section.end..section.end, rendered);
+                }
+            }
+        }
+    }
+
+    out.join("\n") + "\n"
+}
+
+/// Remove the `require` entry for `module` from ogham.mod.yaml content.
+/// Returns `None` if there is no such entry.
+pub fn remove_require(content: &str, module: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let section = require_section(&lines)?;
+    let &(_, start, end) = section.entries.iter().find(|(key, ..)| key == module)?;
+
+    let mut out: Vec<&str> = lines[..start].to_vec();
+    out.extend_from_slice(&lines[end..]);
+    Some(out.join("\n") + "\n")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,6 +534,27 @@ generate:
         assert_eq!(g.generate.plugins[0].grpc.as_deref(), Some("localhost:50051"));
     }
 
+    #[test]
+    fn parse_gen_file_with_timeouts() {
+        let yaml = r#"
+generate:
+  timeout: 2m
+  plugins:
+    - name: github.com/org/go
+      out: gen/
+      timeout: 30s
+    - path: ./tools/plugin.wasm
+      wasm: true
+      out: gen/
+"#;
+        let g: GenFile = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(g.generate.timeout.as_deref(), Some("2m"));
+        assert_eq!(g.generate.plugins[0].timeout.as_deref(), Some("30s"));
+        assert_eq!(g.generate.plugins[1].timeout, None);
+        assert!(!g.generate.plugins[0].wasm);
+        assert!(g.generate.plugins[1].wasm);
+    }
+
     #[test]
     fn parse_mod_file_with_breaking() {
         let yaml = r#"
@@ -294,4 +583,76 @@ breaking:
         assert_eq!(b.against, "git:v1.0.0");
         assert_eq!(b.policy, "warn");
     }
+
+    #[test]
+    fn parse_lock_file() {
+        let yaml = r#"
+# Auto-generated by ogham install. Do not edit.
+locked:
+  github.com/org/database:
+    version: "v2.1.0"
+    commit: "abc123def456"
+    hash: "h1:0f1e2d"
+    source: git
+  mylib:
+    version: "local"
+    source: path
+"#;
+        let l: LockFile = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(l.locked.len(), 2);
+        let db = &l.locked["github.com/org/database"];
+        assert_eq!(db.version, "v2.1.0");
+        assert_eq!(db.commit.as_deref(), Some("abc123def456"));
+        assert_eq!(db.hash.as_deref(), Some("h1:0f1e2d"));
+        assert_eq!(l.locked["mylib"].commit, None);
+    }
+
+    #[test]
+    fn parse_work_file() {
+        let yaml = r#"
+members:
+  - ./schemas/common
+  - ./services/shop
+"#;
+        let w: WorkFile = serde_yaml::from_str(yaml).unwrap();
+        assert_eq!(w.members, vec!["./schemas/common", "./services/shop"]);
+    }
+
+    #[test]
+    fn set_require_keeps_comments_and_matches_keys_exactly() {
+        let content = "\
+module: github.com/test/proj # the module
+require:
+  # pinned for the Money type
+  github.com/org/database-extras: ^1.0.0
+  github.com/org/timestamps:
+    git: https://github.com/org/timestamps.git
+    tag: v1.0.0
+
+# generation lives in ogham.gen.yaml
+breaking:
+  against: git:main
+";
+        // `database` is a prefix of `database-extras`, not an entry
+        let added = set_require(content, "github.com/org/database", &RequireEntry::Version("*".into()));
+        let m: ModFile = serde_yaml::from_str(&added).unwrap();
+        assert_eq!(m.require.len(), 3);
+        assert!(matches!(&m.require["github.com/org/database"], RequireEntry::Version(v) if v == "*"));
+        assert!(added.contains("  # pinned for the Money type\n"));
+        assert!(added.contains("    tag: v1.0.0\n  github.com/org/database: \"*\"\n\n# generation"));
+        assert_eq!(m.breaking.unwrap().against, "git:main");
+
+        let replaced = set_require(&added, "github.com/org/timestamps", &RequireEntry::Path { path: "../ts".into() });
+        let m: ModFile = serde_yaml::from_str(&replaced).unwrap();
+        assert!(matches!(&m.require["github.com/org/timestamps"], RequireEntry::Path { path } if path == "../ts"));
+
+        let removed = remove_require(&replaced, "github.com/org/timestamps").unwrap();
+        let m: ModFile = serde_yaml::from_str(&removed).unwrap();
+        assert_eq!(m.require.len(), 2);
+        assert!(!removed.contains("../ts"));
+        assert!(remove_require(&removed, "github.com/org/timestamps").is_none());
+
+        let fresh = set_require("module: x\n", "github.com/org/lib", &RequireEntry::Version(">= 1.0.0".into()));
+        assert_eq!(fresh, "module: x\n\nrequire:\n  github.com/org/lib: \">= 1.0.0\"\n");
+    }
 }
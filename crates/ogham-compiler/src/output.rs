@@ -0,0 +1,340 @@
+//! Writing plugin output into an output directory.
+//!
+//! File names come from plugins and are confined to the output directory.
+//! Every output directory keeps `.ogham-generated.json`, listing the files
+//! each plugin wrote there, so that files a plugin stops producing are
+//! deleted on the next run and hand-written files are never overwritten.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Component, Path, PathBuf};
+
+use ogham_proto::oghamproto::compiler::GeneratedFile;
+use serde::{Deserialize, Serialize};
+
+/// Name of the manifest in each output directory.
+pub const MANIFEST_FILE: &str = ".ogham-generated.json";
+
+/// Text in the first lines of a file that marks it as generated, as in
+/// `// Code generated by ... DO NOT EDIT.`
+pub const GENERATED_MARKER: &str = "DO NOT EDIT";
+
+/// Lines searched for [`GENERATED_MARKER`].
+const MARKER_LINES: usize = 5;
+
+/// Files written by each plugin into one output directory, by
+/// `/`-separated path relative to it.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub plugins: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl Manifest {
+    /// The manifest of `out_dir`; empty if there is none yet.
+    pub fn load(out_dir: &Path) -> Result<Self, String> {
+        let path = out_dir.join(MANIFEST_FILE);
+        match std::fs::read_to_string(&path) {
+            Ok(content) => serde_json::from_str(&content)
+                .map_err(|e| format!("invalid {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Write the manifest, or remove it once no plugin has files left.
+    pub fn save(&self, out_dir: &Path) -> Result<(), String> {
+        let path = out_dir.join(MANIFEST_FILE);
+        if self.plugins.values().all(|files| files.is_empty()) {
+            return match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(format!("cannot remove {}: {}", path.display(), e))
+                }
+                _ => Ok(()),
+            };
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json + "\n").map_err(|e| format!("cannot write {}: {}", path.display(), e))
+    }
+
+    fn owned_by_other(&self, plugin: &str, name: &str) -> bool {
+        self.plugins.iter().any(|(p, files)| p != plugin && files.contains(name))
+    }
+
+    fn owned(&self, name: &str) -> bool {
+        self.plugins.values().any(|files| files.contains(name))
+    }
+}
+
+/// Validate a plugin-provided file name and normalize it to a `/`-separated
+/// path inside the output directory. Absolute names and names with `..`
+/// are rejected.
+pub fn check_name(name: &str) -> Result<String, String> {
+    let mut parts = Vec::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().to_string()),
+            Component::CurDir => {}
+            Component::ParentDir => return Err(format!("file name escapes the output directory: {}", name)),
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("file name must be relative to the output directory: {}", name))
+            }
+        }
+    }
+    if parts.is_empty() {
+        return Err(format!("invalid file name: {:?}", name));
+    }
+    let normalized = parts.join("/");
+    if normalized == MANIFEST_FILE {
+        return Err(format!("file name is reserved: {}", name));
+    }
+    Ok(normalized)
+}
+
+/// Whether `content` carries [`GENERATED_MARKER`] in its first lines.
+pub fn has_marker(content: &[u8]) -> bool {
+    String::from_utf8_lossy(content)
+        .lines()
+        .take(MARKER_LINES)
+        .any(|line| line.contains(GENERATED_MARKER))
+}
+
+/// What writing one plugin's files into an output directory changes.
+#[derive(Debug, Default)]
+pub struct Plan {
+    /// Final content of every file the plugin produces, by normalized name.
+    pub files: BTreeMap<String, Vec<u8>>,
+    /// Files the plugin wrote last time but no longer produces.
+    pub stale: Vec<String>,
+    manifest: Manifest,
+    plugin: String,
+}
+
+/// Work out the result of writing `files` from `plugin` into `out_dir`
+/// without touching the disk. Appends are applied to the file as another
+/// plugin left it on disk, or as produced earlier in `files`. Existing files that neither a
+/// plugin wrote nor carry the generated marker are refused unless `force`.
+pub fn plan(out_dir: &Path, plugin: &str, files: &[GeneratedFile], force: bool) -> Result<Plan, String> {
+    let manifest = Manifest::load(out_dir)?;
+
+    let mut produced: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+    let mut foreign = Vec::new();
+    for file in files {
+        let name = check_name(&file.name)?;
+        let content = match produced.remove(&name) {
+            Some(mut earlier) if file.append => {
+                earlier.extend_from_slice(&file.content);
+                earlier
+            }
+            Some(_) => file.content.clone(),
+            None => {
+                let existing = read_existing(&out_dir.join(&name))?;
+                if let Some(existing) = &existing {
+                    if !force && !manifest.owned(&name) && !has_marker(existing) {
+                        foreign.push(name.clone());
+                    }
+                }
+                // Appends extend what other plugins wrote, not this
+                // plugin's own output from an earlier run.
+                let own = manifest.plugins.get(plugin).is_some_and(|f| f.contains(&name))
+                    && !manifest.owned_by_other(plugin, &name);
+                match existing {
+                    Some(mut existing) if file.append && !own => {
+                        existing.extend_from_slice(&file.content);
+                        existing
+                    }
+                    _ => file.content.clone(),
+                }
+            }
+        };
+        produced.insert(name, content);
+    }
+    if !foreign.is_empty() {
+        return Err(format!(
+            "refusing to overwrite file(s) not generated by ogham in {} (use --force): {}",
+            out_dir.display(),
+            foreign.join(", ")
+        ));
+    }
+
+    let stale = manifest
+        .plugins
+        .get(plugin)
+        .into_iter()
+        .flatten()
+        .filter(|name| !produced.contains_key(*name) && !manifest.owned_by_other(plugin, name))
+        .filter(|name| check_name(name).is_ok())
+        .cloned()
+        .collect();
+
+    Ok(Plan {
+        files: produced,
+        stale,
+        manifest,
+        plugin: plugin.to_string(),
+    })
+}
+
+impl Plan {
+    /// Write the files, delete stale ones and record the plugin's files in
+    /// the manifest.
+    pub fn apply(mut self, out_dir: &Path) -> Result<(), String> {
+        for (name, content) in &self.files {
+            let path = out_dir.join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)
+                    .map_err(|e| format!("cannot create dir {}: {}", parent.display(), e))?;
+            }
+            std::fs::write(&path, content).map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+        }
+        for name in &self.stale {
+            let path = out_dir.join(name);
+            match std::fs::remove_file(&path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    return Err(format!("cannot remove {}: {}", path.display(), e));
+                }
+                _ => remove_empty_parents(out_dir, &path),
+            }
+        }
+
+        self.manifest
+            .plugins
+            .insert(self.plugin.clone(), self.files.keys().cloned().collect());
+        self.manifest.save(out_dir)
+    }
+}
+
+/// A file whose content on disk differs from what a plan would write.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    /// Would be created.
+    Added { name: String, new: Vec<u8> },
+    /// Would be overwritten with different content.
+    Changed { name: String, old: Vec<u8>, new: Vec<u8> },
+    /// Would be deleted as stale.
+    Removed { name: String, old: Vec<u8> },
+}
+
+impl Difference {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Added { name, .. } | Self::Changed { name, .. } | Self::Removed { name, .. } => name,
+        }
+    }
+}
+
+impl Plan {
+    /// Compare the plan with the files in `out_dir`, without writing.
+    pub fn differences(&self, out_dir: &Path) -> Result<Vec<Difference>, String> {
+        let mut differences = Vec::new();
+        for (name, new) in &self.files {
+            match read_existing(&out_dir.join(name))? {
+                None => differences.push(Difference::Added { name: name.clone(), new: new.clone() }),
+                Some(old) if old != *new => differences.push(Difference::Changed {
+                    name: name.clone(),
+                    old,
+                    new: new.clone(),
+                }),
+                Some(_) => {}
+            }
+        }
+        for name in &self.stale {
+            if let Some(old) = read_existing(&out_dir.join(name))? {
+                differences.push(Difference::Removed { name: name.clone(), old });
+            }
+        }
+        Ok(differences)
+    }
+}
+
+fn read_existing(path: &Path) -> Result<Option<Vec<u8>>, String> {
+    match std::fs::read(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(format!("cannot read {}: {}", path.display(), e)),
+    }
+}
+
+/// Remove directories left empty by a deleted file, up to `out_dir`.
+fn remove_empty_parents(out_dir: &Path, file: &Path) {
+    let mut dir: Option<PathBuf> = file.parent().map(Path::to_path_buf);
+    while let Some(d) = dir {
+        if d == out_dir || !d.starts_with(out_dir) || std::fs::remove_dir(&d).is_err() {
+            break;
+        }
+        dir = d.parent().map(Path::to_path_buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn file(name: &str, content: &str) -> GeneratedFile {
+        GeneratedFile {
+            name: name.to_string(),
+            content: content.as_bytes().to_vec(),
+            append: false,
+        }
+    }
+
+    #[test]
+    fn check_name_confines_to_out_dir() {
+        assert_eq!(check_name("a/./b.go").unwrap(), "a/b.go");
+        assert!(check_name("../../.git/config").is_err());
+        assert!(check_name("a/../../b").is_err());
+        assert!(check_name("/etc/passwd").is_err());
+        assert!(check_name("").is_err());
+        assert!(check_name(MANIFEST_FILE).is_err());
+    }
+
+    #[test]
+    fn removes_stale_and_protects_hand_written_files() {
+        let dir = std::env::temp_dir().join(format!("ogham-output-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        plan(&dir, "go", &[file("a.go", "a"), file("sub/b.go", "b")], false)
+            .unwrap()
+            .apply(&dir)
+            .unwrap();
+        assert!(dir.join("sub/b.go").is_file());
+
+        // b.go is no longer produced: deleted with its directory.
+        let next = plan(&dir, "go", &[file("a.go", "a2")], false).unwrap();
+        assert_eq!(next.stale, vec!["sub/b.go".to_string()]);
+        next.apply(&dir).unwrap();
+        assert!(!dir.join("sub").exists());
+        assert_eq!(fs::read_to_string(dir.join("a.go")).unwrap(), "a2");
+
+        // A hand-written file is refused unless forced or marked.
+        fs::write(dir.join("main.go"), "package main\n").unwrap();
+        let err = plan(&dir, "go", &[file("main.go", "x")], false).unwrap_err();
+        assert!(err.contains("main.go"), "{}", err);
+        assert!(plan(&dir, "go", &[file("main.go", "x")], true).is_ok());
+        fs::write(dir.join("main.go"), "// Code generated by x. DO NOT EDIT.\n").unwrap();
+        assert!(plan(&dir, "go", &[file("main.go", "x")], false).is_ok());
+
+        // Comparing writes nothing.
+        let check = plan(&dir, "go", &[file("a.go", "a2"), file("c.go", "c")], true).unwrap();
+        let differences = check.differences(&dir).unwrap();
+        assert_eq!(differences, vec![Difference::Added { name: "c.go".into(), new: b"c".to_vec() }]);
+        assert!(!dir.join("c.go").exists());
+        let changed = plan(&dir, "go", &[file("a.go", "a3")], false).unwrap();
+        assert_eq!(
+            changed.differences(&dir).unwrap(),
+            vec![Difference::Changed { name: "a.go".into(), old: b"a2".to_vec(), new: b"a3".to_vec() }]
+        );
+        assert_eq!(fs::read_to_string(dir.join("a.go")).unwrap(), "a2");
+        assert!(matches!(
+            &plan(&dir, "go", &[], false).unwrap().differences(&dir).unwrap()[..],
+            [Difference::Removed { name, .. }] if name == "a.go"
+        ));
+
+        // Nothing produced any more: the manifest goes away as well.
+        plan(&dir, "go", &[], false).unwrap().apply(&dir).unwrap();
+        assert!(!dir.join("a.go").exists());
+        assert!(!dir.join(MANIFEST_FILE).exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
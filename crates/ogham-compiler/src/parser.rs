@@ -911,6 +911,11 @@ impl<'s> Parser<'s> {
 
     fn parse_annotation_member(&mut self) {
         self.eat_trivia();
+        // Parameter annotations, e.g. `@tightens(increase) int64? min;`
+        while self.current_non_trivia() == Some(At) {
+            self.parse_annotation_call();
+            self.eat_trivia();
+        }
         // Could be:
         // 1. type_ref [?] ident [= default] ;           (annotation field)
         // 2. ident : { ... }                             (inline annotation type)
@@ -1698,6 +1703,16 @@ type User {
         assert!(kinds.contains(&AnnotationField));
     }
 
+    #[test]
+    fn parse_annotation_param_annotations() {
+        let root = parse_ok(
+            "annotation Range for field { @tightens(increase) int64? min; @tightens(decrease) int64? max; }",
+        );
+        let kinds = node_kinds(&root);
+        assert!(kinds.contains(&AnnotationField));
+        assert!(kinds.contains(&AnnotationCall));
+    }
+
     #[test]
     fn parse_annotation_multi_target() {
         let root = parse_ok(
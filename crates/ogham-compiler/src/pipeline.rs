@@ -2,7 +2,7 @@
 
 use crate::ast::{self, AstNode};
 use crate::diagnostics::Diagnostics;
-use crate::hir::{Arenas, Interner, SymbolTable};
+use crate::hir::{Arenas, Interner, Loc, SymbolTable};
 use crate::index::{self, ParsedFile};
 use crate::parser;
 use crate::resolve;
@@ -16,6 +16,40 @@ pub struct CompileResult {
     pub diagnostics: Diagnostics,
 }
 
+impl CompileResult {
+    /// Source file and span of a declaration given by its full name
+    /// (`pkg.Type`, `pkg.Enum`, `pkg.Service`), or of one of its members — a
+    /// field, oneof, enum value or rpc. Members without a location of their
+    /// own, such as fields injected from shapes, resolve to the declaration.
+    pub fn locate(&self, full_name: &str, member: &str) -> Option<(String, std::ops::Range<usize>)> {
+        let sym = self.interner.intern_lookup(full_name)?;
+        let member = self.interner.intern_lookup(member);
+        let (loc, members): (&Loc, Vec<_>) = if let Some(&id) = self.symbols.types.get(&sym) {
+            let t = &self.arenas.types[id];
+            let fields = t.fields.iter().map(|f| (f.name, &f.loc));
+            let oneofs = t.oneofs.iter().flat_map(|o| {
+                std::iter::once((o.name, &o.loc)).chain(o.fields.iter().map(|f| (f.name, &f.loc)))
+            });
+            (&t.loc, fields.chain(oneofs).collect())
+        } else if let Some(&id) = self.symbols.enums.get(&sym) {
+            let e = &self.arenas.enums[id];
+            (&e.loc, e.values.iter().map(|v| (v.name, &v.loc)).collect())
+        } else if let Some(&id) = self.symbols.services.get(&sym) {
+            let s = &self.arenas.services[id];
+            (&s.loc, s.rpcs.iter().map(|r| (r.name, &r.loc)).collect())
+        } else {
+            return None;
+        };
+
+        let own = member
+            .and_then(|m| members.into_iter().find(|(name, _)| *name == m))
+            .map(|(_, l)| l)
+            .filter(|l| l.file.is_some());
+        let loc = own.unwrap_or(loc);
+        Some((self.interner.resolve(loc.file?).to_string(), loc.span.clone()))
+    }
+}
+
 /// A source file to compile.
 pub struct SourceFile {
     pub name: String,
@@ -224,6 +258,25 @@ type User { Address home = 1; []Address all = 2; }
         assert!(!result.arenas.types[addr_id].back_references.is_empty());
     }
 
+    #[test]
+    fn locate_declarations_and_members() {
+        let source = "package example;\ntype User {\n    string email = 1;\n}\nenum Status {\n    Active = 1;\n}\n";
+        let result = compile_one(source);
+        assert!(!result.diagnostics.has_errors());
+
+        let text = |(file, span): (String, std::ops::Range<usize>)| {
+            assert_eq!(file, "test.ogham");
+            source[span].to_string()
+        };
+        assert!(text(result.locate("example.User", "email").unwrap()).starts_with("string email"));
+        assert!(text(result.locate("example.Status", "Active").unwrap()).starts_with("Active"));
+        // Unknown members fall back to the declaration.
+        let decl = result.locate("example.User", "").unwrap();
+        assert_eq!(result.locate("example.User", "missing").unwrap(), decl);
+        assert!(text(decl).starts_with("type User"));
+        assert_eq!(result.locate("example.Missing", ""), None);
+    }
+
     #[test]
     fn compile_multi_file() {
         let result = compile(&[
@@ -463,6 +516,26 @@ type User {
         assert_eq!(result.interner.resolve(def.params[1].name), "max");
     }
 
+    #[test]
+    fn annotation_param_tightening_populated() {
+        let result = compile_one(r#"package example;
+annotation Size for field(string) {
+    @tightens(increase) uint32? min;
+    @tightens(decrease) uint32? max;
+    string? label;
+}
+"#);
+        assert!(!result.diagnostics.has_errors(), "errors: {:?}", result.diagnostics.all());
+
+        let lib = result.interner.inner.get("example").unwrap();
+        let name = result.interner.inner.get("Size").unwrap();
+        let ids = &result.symbols.annotations[&(lib, name)];
+        let def = &result.arenas.annotation_defs[ids[0]];
+        assert_eq!(def.params[0].tightens, Some(crate::hir::Tightening::Increase));
+        assert_eq!(def.params[1].tightens, Some(crate::hir::Tightening::Decrease));
+        assert_eq!(def.params[2].tightens, None);
+    }
+
     #[test]
     fn annotation_param_annotations_projection() {
         let result = compile_one(r#"package example;
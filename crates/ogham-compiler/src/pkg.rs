@@ -4,11 +4,16 @@
 //! fetches them into $OGHAM_HOME/pkg/mod/, and makes them available
 //! for compilation.
 
+use crate::cache;
 use crate::manifest::{self, RequireEntry, ReplaceEntry};
 use crate::pipeline::SourceFile;
+use crate::proxy::{self, ProxyEntry};
+use crate::workspace::Workspace;
+use sha2::{Digest, Sha256};
 use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 // ── Semver ─────────────────────────────────────────────────────────────
 
@@ -167,44 +172,362 @@ pub enum DepSource {
     Cache,
 }
 
+/// One edge of the dependency graph: `by` requires a module with `entry`.
+#[derive(Debug, Clone)]
+pub struct Requirement {
+    /// The requiring module — the root module path, or `module@version`
+    /// for a dependency.
+    pub by: String,
+    pub entry: RequireEntry,
+    /// Directory that `path:` entries are relative to.
+    base_dir: PathBuf,
+}
+
+impl Requirement {
+    /// A requirement not yet placed in a graph, for describing an entry.
+    fn from_entry(entry: RequireEntry) -> Self {
+        Requirement {
+            by: String::new(),
+            entry,
+            base_dir: PathBuf::new(),
+        }
+    }
+
+    /// Human-readable form of the requirement: `^1.2.0`, `git tag v1.2.0`, ...
+    pub fn describe(&self) -> String {
+        match &self.entry {
+            RequireEntry::Version(v) => v.clone(),
+            RequireEntry::Git { tag: Some(t), .. } => format!("git tag {}", t),
+            RequireEntry::Git { branch: Some(b), .. } => format!("git branch {}", b),
+            RequireEntry::Git { rev: Some(r), .. } => format!("git rev {}", r),
+            RequireEntry::Git { .. } => "git HEAD".to_string(),
+            RequireEntry::Path { path } => format!("path {}", path),
+        }
+    }
+
+    /// The semver range this requirement takes part in MVS with: version
+    /// ranges as written, and git tags that look like versions as `^tag`.
+    /// Branches, revs and paths pin a source instead.
+    fn range_str(&self) -> Option<String> {
+        match &self.entry {
+            RequireEntry::Version(v) => Some(v.clone()),
+            RequireEntry::Git { tag: Some(t), branch: None, rev: None, .. } => {
+                parse_tag(t).map(|v| format!("^{}", v))
+            }
+            _ => None,
+        }
+    }
+
+    fn range(&self) -> Option<VersionRange> {
+        self.range_str().and_then(|r| VersionRange::parse(&r))
+    }
+}
+
+/// The version selected for one module, and why.
+#[derive(Debug, Clone)]
+pub struct Selection {
+    pub module: String,
+    pub version: String,
+    pub reason: String,
+    /// Every requirement on the module found in the graph, root first.
+    pub requirements: Vec<Requirement>,
+}
+
+/// Result of resolving the dependency graph.
+#[derive(Debug, Clone, Default)]
+pub struct Resolution {
+    /// One entry per module, in discovery order.
+    pub deps: Vec<ResolvedDep>,
+    pub selections: Vec<Selection>,
+}
+
+/// A requirement between two modules at their selected versions.
+#[derive(Debug, Clone)]
+pub struct Edge {
+    /// Requiring module — a root module, or a selected dependency.
+    pub from: String,
+    pub to: String,
+    /// What `from` asks for: `^1.2.0`, `git tag v1.2.0`, `path ../lib`, ...
+    pub requirement: String,
+}
+
+impl Resolution {
+    /// Edges of the resolved graph. Requirements from versions that were
+    /// not selected (a module MVS moved to a higher version) are left out.
+    pub fn edges(&self) -> Vec<Edge> {
+        let selected: HashMap<&str, &str> = self
+            .selections
+            .iter()
+            .map(|s| (s.module.as_str(), s.version.as_str()))
+            .collect();
+        let mut edges = Vec::new();
+        for sel in &self.selections {
+            for req in &sel.requirements {
+                let from = match req.by.split_once('@') {
+                    Some((module, version)) if selected.get(module) == Some(&version) => module,
+                    Some(_) => continue,
+                    None => req.by.as_str(),
+                };
+                edges.push(Edge {
+                    from: from.to_string(),
+                    to: sel.module.clone(),
+                    requirement: req.describe(),
+                });
+            }
+        }
+        edges
+    }
+
+    /// Every path from a root module to `module`, as the edges along it.
+    pub fn paths_to(&self, module: &str) -> Vec<Vec<Edge>> {
+        fn walk(edges: &[Edge], roots: &HashSet<&str>, path: &mut Vec<Edge>, paths: &mut Vec<Vec<Edge>>) {
+            let to = path.last().map(|e| e.from.clone()).unwrap_or_default();
+            if roots.contains(to.as_str()) {
+                let mut found = path.clone();
+                found.reverse();
+                paths.push(found);
+                return;
+            }
+            for edge in edges.iter().filter(|e| e.to == to) {
+                // A module already on the path is a cycle
+                if path.iter().any(|e| e.to == edge.from) {
+                    continue;
+                }
+                path.push(edge.clone());
+                walk(edges, roots, path, paths);
+                path.pop();
+            }
+        }
+
+        let edges = self.edges();
+        let modules: HashSet<&str> = self.selections.iter().map(|s| s.module.as_str()).collect();
+        let roots: HashSet<&str> = edges
+            .iter()
+            .map(|e| e.from.as_str())
+            .filter(|m| !modules.contains(m))
+            .collect();
+
+        let mut paths = Vec::new();
+        for edge in edges.iter().filter(|e| e.to == module) {
+            let mut path = vec![edge.clone()];
+            walk(&edges, &roots, &mut path, &mut paths);
+        }
+        paths
+    }
+
+    /// Dependencies reachable from `module`, one root of a workspace
+    /// resolution — what compiling that member needs.
+    pub fn deps_of(&self, module: &str) -> Vec<ResolvedDep> {
+        let mut reached: HashSet<&str> = HashSet::from([module]);
+        loop {
+            let before = reached.len();
+            for sel in &self.selections {
+                let required = sel
+                    .requirements
+                    .iter()
+                    .any(|r| reached.contains(r.by.split('@').next().unwrap_or_default()));
+                if required {
+                    reached.insert(&sel.module);
+                }
+            }
+            if reached.len() == before {
+                break;
+            }
+        }
+        self.deps
+            .iter()
+            .filter(|d| d.module != module && reached.contains(d.module.as_str()))
+            .cloned()
+            .collect()
+    }
+}
+
+/// How strictly resolution follows ogham.lock.yaml.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockMode {
+    /// Use locked commits where the lock still matches the manifest;
+    /// resolve anything new from its source.
+    #[default]
+    Auto,
+    /// Fail if ogham.mod.yaml and ogham.lock.yaml disagree.
+    Locked,
+    /// Like `Locked`, and never touch the network — everything must
+    /// already be in the cache.
+    Frozen,
+}
+
+/// Whether compilation takes dependencies from vendor/ (`--mod`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VendorMode {
+    /// Use vendor/ when vendor/modules.yaml exists.
+    #[default]
+    Auto,
+    /// Require vendor/ (`--mod=vendor`).
+    Vendor,
+    /// Ignore vendor/ and resolve through the cache (`--mod=mod`).
+    Off,
+}
+
+/// Why dependency resolution failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    /// A dependency's content does not match the hash it was locked or
+    /// published with. Unlike other resolution errors it is fatal whatever
+    /// the lock mode: compiling without the dependency would hide that it
+    /// was tampered with.
+    ChecksumMismatch(String),
+    /// Anything else — a missing module, an unreachable source, a lock
+    /// file that disagrees with the manifest.
+    Other(String),
+}
+
+impl ResolveError {
+    /// Rewrite the message, keeping the kind of error.
+    fn map(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            ResolveError::ChecksumMismatch(msg) => ResolveError::ChecksumMismatch(f(msg)),
+            ResolveError::Other(msg) => ResolveError::Other(f(msg)),
+        }
+    }
+}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ResolveError::ChecksumMismatch(msg) | ResolveError::Other(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl From<String> for ResolveError {
+    fn from(msg: String) -> Self {
+        ResolveError::Other(msg)
+    }
+}
+
+impl From<ResolveError> for String {
+    fn from(err: ResolveError) -> Self {
+        err.to_string()
+    }
+}
+
+/// Lock state and proxy chain threaded through resolution.
+#[derive(Default)]
+struct ResolveCtx {
+    lock: Option<manifest::LockFile>,
+    mode: LockMode,
+    /// `OGHAM_PROXY` chain; empty means `direct`.
+    proxies: Vec<ProxyEntry>,
+    /// Workspace members by module path — always used from their directory.
+    members: HashMap<String, PathBuf>,
+    /// Cache root, `$OGHAM_HOME`.
+    home: PathBuf,
+}
+
+impl ResolveCtx {
+    fn from_env() -> Self {
+        ResolveCtx {
+            proxies: proxy::chain_from_env(),
+            home: ogham_home(),
+            ..Default::default()
+        }
+    }
+
+    fn proxies(&self) -> &[ProxyEntry] {
+        if self.proxies.is_empty() {
+            &[ProxyEntry::Direct]
+        } else {
+            &self.proxies
+        }
+    }
+
+    fn locked(&self, module: &str) -> Option<&manifest::LockedDep> {
+        self.lock.as_ref()?.locked.get(module)
+    }
+
+    fn strict(&self) -> bool {
+        self.mode != LockMode::Auto
+    }
+}
+
 /// Resolve and fetch all dependencies from ogham.mod.yaml,
 /// including transitive dependencies. Uses Minimal Version Selection (MVS):
 /// for each package, select the minimum version that satisfies all requirements.
-pub fn resolve_deps(project_dir: &Path) -> Result<Vec<ResolvedDep>, String> {
-    let mod_file = manifest::load_mod_file(project_dir)?;
+///
+/// Git dependencies are checked out at the commit recorded in
+/// ogham.lock.yaml when the lock matches the manifest.
+pub fn resolve_deps(project_dir: &Path) -> Result<Vec<ResolvedDep>, ResolveError> {
+    resolve_deps_with(project_dir, LockMode::Auto)
+}
 
-    // Validate mod file
-    validate_mod_file(&mod_file)?;
+/// Like `resolve_deps`, with an explicit lock mode.
+pub fn resolve_deps_with(project_dir: &Path, mode: LockMode) -> Result<Vec<ResolvedDep>, ResolveError> {
+    resolve_graph(project_dir, mode).map(|r| r.deps)
+}
 
-    let mut resolved: Vec<ResolvedDep> = Vec::new();
-    let mut seen = HashSet::new();
-    let mut version_requirements: HashMap<String, Vec<String>> = HashMap::new();
+/// Resolve the full dependency graph and record, for each module, which
+/// version was selected and why.
+pub fn resolve_graph(project_dir: &Path, mode: LockMode) -> Result<Resolution, ResolveError> {
+    resolve_graph_in(project_dir, mode, &ogham_home())
+}
 
-    // Resolve direct and transitive dependencies
-    resolve_deps_recursive(
-        &mod_file.require,
-        &mod_file.replace,
-        project_dir,
-        &mut resolved,
-        &mut seen,
-        &mut version_requirements,
-        0,
-        &[], // no parent chain yet
-    )?;
+fn resolve_graph_in(project_dir: &Path, mode: LockMode, home: &Path) -> Result<Resolution, ResolveError> {
+    let lock = manifest::load_lock_file(project_dir)?;
+    if lock.is_none() && mode != LockMode::Auto {
+        return Err(ResolveError::Other("ogham.lock.yaml not found; run `ogham install` to create it".into()));
+    }
 
-    // Check for version conflicts (MVS: pick minimum satisfying version)
-    let _selected_versions = check_version_conflicts(&version_requirements)?;
+    let ctx = ResolveCtx {
+        lock,
+        mode,
+        home: home.to_path_buf(),
+        ..ResolveCtx::from_env()
+    };
+    let resolution = resolve_with(project_dir, &ctx)?;
 
-    // Deduplicate: keep only first occurrence of each module
-    let mut deduped: Vec<ResolvedDep> = Vec::new();
-    let mut dedup_seen = HashSet::new();
-    for dep in resolved {
-        if dedup_seen.insert(dep.module.clone()) {
-            deduped.push(dep);
+    if let Some(lock) = &ctx.lock {
+        verify_hashes(&resolution.deps, lock)?;
+        if ctx.strict() {
+            check_lock(&resolution.deps, lock)?;
         }
     }
 
-    Ok(deduped)
+    Ok(resolution)
+}
+
+/// Resolve every member of a workspace as one dependency graph, with the
+/// lock file next to ogham.work.yaml. Members required by other members
+/// resolve to their directories, whatever version is required.
+pub fn resolve_workspace(ws: &Workspace, mode: LockMode) -> Result<Resolution, ResolveError> {
+    let lock = manifest::load_lock_file(&ws.root)?;
+    if lock.is_none() && mode != LockMode::Auto {
+        return Err(ResolveError::Other(
+            "ogham.lock.yaml not found in the workspace root; run `ogham install` there to create it".into(),
+        ));
+    }
+
+    let ctx = ResolveCtx {
+        lock,
+        mode,
+        members: ws
+            .members
+            .iter()
+            .map(|m| (m.module().to_string(), m.dir.clone()))
+            .collect(),
+        ..ResolveCtx::from_env()
+    };
+    let roots: Vec<(&Path, &manifest::ModFile)> =
+        ws.members.iter().map(|m| (m.dir.as_path(), &m.mod_file)).collect();
+    let resolution = resolve_roots(&roots, &ctx)?;
+
+    if let Some(lock) = &ctx.lock {
+        verify_hashes(&resolution.deps, lock)?;
+        if ctx.strict() {
+            check_lock(&resolution.deps, lock)?;
+        }
+    }
+
+    Ok(resolution)
 }
 
 /// Validate ogham.mod.yaml for common issues.
@@ -213,12 +536,9 @@ fn validate_mod_file(mod_file: &manifest::ModFile) -> Result<(), String> {
         return Err("ogham.mod.yaml: 'module' field is required".into());
     }
 
-    // Path and replace deps are not allowed in published packages
-    // (we check this but don't block — just warn for now)
+    // Path and replace deps are fine for local development;
+    // `validate_publishable` rejects them for `ogham publish`
     for (module, entry) in &mod_file.require {
-        if let RequireEntry::Path { .. } = entry {
-            // Path deps are fine for local dev, but ogham publish would reject
-        }
         // Validate version syntax
         if let RequireEntry::Version(v) = entry {
             if !v.starts_with('^') && !v.starts_with('~') && !v.starts_with('=')
@@ -235,84 +555,518 @@ fn validate_mod_file(mod_file: &manifest::ModFile) -> Result<(), String> {
     Ok(())
 }
 
-const MAX_DEPTH: usize = 32;
+/// Check that a module can be published: a semver `version`, no `path:`
+/// requires and no `replace` directives — neither resolves for consumers.
+pub fn validate_publishable(mod_file: &manifest::ModFile) -> Result<(), String> {
+    validate_mod_file(mod_file)?;
 
-#[allow(clippy::too_many_arguments)]
-fn resolve_deps_recursive(
-    require: &HashMap<String, RequireEntry>,
-    replace: &HashMap<String, ReplaceEntry>,
-    project_dir: &Path,
-    resolved: &mut Vec<ResolvedDep>,
-    seen: &mut HashSet<String>,
-    version_reqs: &mut HashMap<String, Vec<String>>,
-    depth: usize,
-    parent_chain: &[String], // for error messages: ["root", "dep-a"]
-) -> Result<(), String> {
-    if depth > MAX_DEPTH {
-        return Err(format!(
-            "dependency resolution depth limit exceeded ({}). Possible circular dependency: {}",
-            MAX_DEPTH,
-            parent_chain.join(" → ")
+    let mut problems = Vec::new();
+    if parse_tag(&mod_file.version).is_none() {
+        problems.push(format!(
+            "'version' must be a semver version such as 1.2.0, got '{}'",
+            mod_file.version
         ));
     }
+    let mut path_requires: Vec<&String> = mod_file
+        .require
+        .iter()
+        .filter(|(_, entry)| matches!(entry, RequireEntry::Path { .. }))
+        .map(|(module, _)| module)
+        .collect();
+    path_requires.sort();
+    for module in path_requires {
+        problems.push(format!("{} is a path dependency", module));
+    }
+    let mut replaced: Vec<&String> = mod_file.replace.keys().collect();
+    replaced.sort();
+    for module in replaced {
+        problems.push(format!("{} is replaced", module));
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "{} cannot be published:\n  {}",
+        mod_file.module,
+        problems.join("\n  ")
+    ))
+}
+
+const MAX_DEPTH: usize = 32;
 
-    for (module, entry) in require {
-        if seen.contains(module) {
-            // Already resolved — just record the version requirement for conflict check
-            if let RequireEntry::Version(v) = entry {
-                version_reqs.entry(module.clone()).or_default().push(v.clone());
+/// Requirements collected so far, per module.
+///
+/// Requirements are only ever added: when a module moves to a higher
+/// version, the requirements of the version it moved away from stay in the
+/// graph, as in Go's MVS. Selected versions therefore only go up, and
+/// resolution reaches a fixed point.
+#[derive(Default)]
+struct Graph {
+    /// Modules in discovery order.
+    order: Vec<String>,
+    requirements: HashMap<String, Vec<Requirement>>,
+    /// Module that first required each module, for depth and error messages.
+    parent: HashMap<String, String>,
+}
+
+impl Graph {
+    fn add(
+        &mut self,
+        by: &str,
+        parent: Option<&str>,
+        require: &HashMap<String, RequireEntry>,
+        base_dir: &Path,
+    ) {
+        let mut modules: Vec<&String> = require.keys().collect();
+        modules.sort();
+        for module in modules {
+            if !self.requirements.contains_key(module.as_str()) {
+                self.order.push(module.clone());
+                if let Some(parent) = parent {
+                    self.parent.insert(module.clone(), parent.to_string());
+                }
             }
-            continue;
+            let reqs = self.requirements.entry(module.clone()).or_default();
+            if reqs.iter().any(|r| r.by == by) {
+                continue;
+            }
+            reqs.push(Requirement {
+                by: by.to_string(),
+                entry: require[module].clone(),
+                base_dir: base_dir.to_path_buf(),
+            });
         }
+    }
 
-        seen.insert(module.clone());
+    /// `a (^1.0.0) → b (path ../b) → ...` — each module of a chain with
+    /// what the previous one requires of it.
+    fn describe_chain(&self, chain: &[String]) -> String {
+        let mut by: Option<&str> = None;
+        let mut hops = Vec::new();
+        for module in chain {
+            let req = self.requirements[module]
+                .iter()
+                .find(|r| by.map_or(!r.by.contains('@'), |by| r.by.split('@').next() == Some(by)));
+            hops.push(match req {
+                Some(req) => format!("{} ({})", module, req.describe()),
+                None => module.clone(),
+            });
+            by = Some(module);
+        }
+        hops.join(" → ")
+    }
 
-        // Track version requirements
-        if let RequireEntry::Version(v) = entry {
-            version_reqs.entry(module.clone()).or_default().push(v.clone());
+    /// Modules from the root's direct dependency down to `module`.
+    fn chain(&self, module: &str) -> Vec<String> {
+        let mut chain = vec![module.to_string()];
+        let mut current = module;
+        while let Some(parent) = self.parent.get(current) {
+            if chain.len() > MAX_DEPTH + 1 {
+                break;
+            }
+            chain.push(parent.clone());
+            current = parent;
         }
+        chain.reverse();
+        chain
+    }
+}
 
-        // Resolve this dependency
-        let dep = if let Some(rep) = replace.get(module) {
-            resolve_replace(module, rep, project_dir)
-        } else {
-            resolve_require(module, entry, project_dir)
-        }.map_err(|e| {
-            if parent_chain.is_empty() {
-                e
-            } else {
-                format!("{} (required by {})", e, parent_chain.last().unwrap())
+/// What to fetch for a module.
+#[derive(Debug, Clone)]
+enum Choice {
+    /// Workspace member, used from its directory.
+    Member(PathBuf),
+    /// `replace` entry in a root ogham.mod.yaml, with the directory it is
+    /// relative to.
+    Replace(ReplaceEntry, PathBuf),
+    /// A path, branch or rev requirement, fetched as written.
+    Entry(RequireEntry, PathBuf),
+    /// A version selected by MVS — a git tag when the module has a git
+    /// source, otherwise a cached or embedded module.
+    Version {
+        version: SemVer,
+        git: Option<String>,
+        tag: Option<String>,
+    },
+}
+
+impl Choice {
+    /// Identity of the choice — a module is re-fetched only when this changes.
+    fn key(&self) -> String {
+        match self {
+            Choice::Member(_) => "workspace".to_string(),
+            Choice::Replace(..) => "replace".to_string(),
+            Choice::Entry(entry, base) => format!("{:?} in {}", entry, base.display()),
+            Choice::Version { version, .. } => format!("v{}", version),
+        }
+    }
+}
+
+fn resolve_with(project_dir: &Path, ctx: &ResolveCtx) -> Result<Resolution, ResolveError> {
+    let mod_file = manifest::load_mod_file(project_dir)?;
+    resolve_roots(&[(project_dir, &mod_file)], ctx)
+}
+
+/// Resolve the graph below one or more root modules — the project, or
+/// every member of a workspace.
+fn resolve_roots(roots: &[(&Path, &manifest::ModFile)], ctx: &ResolveCtx) -> Result<Resolution, ResolveError> {
+    let mut graph = Graph::default();
+    let mut replaces: HashMap<&str, (&ReplaceEntry, &Path, &str)> = HashMap::new();
+    for &(dir, mod_file) in roots {
+        // Validate mod file
+        validate_mod_file(mod_file)?;
+        graph.add(&mod_file.module, None, &mod_file.require, dir);
+
+        for (module, rep) in &mod_file.replace {
+            if let Some(&(other, other_dir, by)) = replaces.get(module.as_str()) {
+                if replace_target(other, other_dir) != replace_target(rep, dir) {
+                    return Err(format!(
+                        "{} is replaced differently by {} and {}",
+                        module, by, mod_file.module
+                    )
+                    .into());
+                }
+                continue;
             }
-        })?;
+            replaces.insert(module, (rep, dir, &mod_file.module));
+        }
+    }
 
-        // Try to load transitive dependencies from the dep's ogham.mod.yaml
-        let dep_dir = dep.path.clone();
-        let transitive_mod = if dep_dir.is_dir() {
-            manifest::load_mod_file(&dep_dir).ok()
-        } else {
-            None
+    let mut fetched: HashMap<String, (String, ResolvedDep)> = HashMap::new();
+    let mut reasons: HashMap<String, String> = HashMap::new();
+    let mut versions = VersionLists::default();
+
+    // Select a version for every module, fetch it and add its requirements;
+    // repeat until no selection changes.
+    loop {
+        let mut changed = false;
+
+        let mut i = 0;
+        while i < graph.order.len() {
+            let module = graph.order[i].clone();
+            i += 1;
+
+            let chain = graph.chain(&module);
+            if chain.len() > MAX_DEPTH {
+                return Err(format!(
+                    "dependency resolution depth limit exceeded ({}). Possible circular dependency: {}",
+                    MAX_DEPTH,
+                    graph.describe_chain(&chain)
+                )
+                .into());
+            }
+            let blame = |e: ResolveError| match graph.parent.get(&module) {
+                Some(parent) => e.map(|msg| format!("{} (required by {})", msg, parent)),
+                None => e,
+            };
+
+            let reqs = &graph.requirements[&module];
+            let replace = replaces.get(module.as_str()).map(|&(rep, dir, _)| (rep, dir));
+            let (choice, reason) = select(&module, reqs, replace, ctx, &mut versions).map_err(|e| blame(e.into()))?;
+            let key = choice.key();
+            if fetched.get(&module).is_some_and(|(k, _)| *k == key) {
+                continue;
+            }
+
+            let dep = fetch_choice(&module, &choice, ctx).map_err(blame)?;
+            changed = true;
+
+            // Transitive dependencies — paths relative to the dep's directory,
+            // replace only applies to the root modules. A workspace member's
+            // requirements are already in the graph as a root.
+            if dep.path.is_dir() && !ctx.members.contains_key(&module) {
+                if let Ok(trans_mod) = manifest::load_mod_file(&dep.path) {
+                    let by = format!("{}@{}", module, dep.version);
+                    graph.add(&by, Some(&module), &trans_mod.require, &dep.path);
+                }
+            }
+
+            reasons.insert(module.clone(), reason);
+            fetched.insert(module, (key, dep));
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    let mut resolution = Resolution::default();
+    for module in &graph.order {
+        let Some((_, dep)) = fetched.remove(module) else {
+            continue;
         };
+        resolution.selections.push(Selection {
+            module: module.clone(),
+            version: dep.version.clone(),
+            reason: reasons.remove(module).unwrap_or_default(),
+            requirements: graph.requirements[module].clone(),
+        });
+        resolution.deps.push(dep);
+    }
+
+    for dep in &resolution.deps {
+        if !matches!(dep.source, DepSource::Path) && dep.path.starts_with(&ctx.home) {
+            cache::touch(&dep.path);
+        }
+    }
+    Ok(resolution)
+}
+
+/// Pick what to fetch for `module` given every requirement on it.
+///
+/// A workspace member wins, then a `replace`, then the first
+/// path/branch/rev requirement (closest to the root). Otherwise MVS: the minimum version satisfying all semver
+/// requirements — the locked version if it still satisfies them, else the
+/// lowest matching version listed by the proxy chain or git tag.
+fn select(
+    module: &str,
+    reqs: &[Requirement],
+    replace: Option<(&ReplaceEntry, &Path)>,
+    ctx: &ResolveCtx,
+    versions: &mut VersionLists,
+) -> Result<(Choice, String), String> {
+    if let Some(dir) = ctx.members.get(module) {
+        return Ok((Choice::Member(dir.clone()), "workspace member".to_string()));
+    }
+
+    if let Some((rep, dir)) = replace {
+        return Ok((
+            Choice::Replace(rep.clone(), dir.to_path_buf()),
+            "replaced in ogham.mod.yaml".to_string(),
+        ));
+    }
 
-        resolved.push(dep);
+    if let Some(req) = reqs.iter().find(|r| r.range().is_none()) {
+        let reason = format!("{} requires {}", req.by, req.describe());
+        return Ok((Choice::Entry(req.entry.clone(), req.base_dir.clone()), reason));
+    }
 
-        // Recurse into transitive deps — resolve paths relative to the dep's directory
-        if let Some(trans_mod) = transitive_mod {
-            let mut chain = parent_chain.to_vec();
-            chain.push(module.clone());
-            resolve_deps_recursive(
-                &trans_mod.require,
-                &HashMap::new(), // replace only applies to root module
-                &dep_dir,
-                resolved,
-                seen,
-                version_reqs,
-                depth + 1,
-                &chain,
-            )?;
+    // All requirements are semver ranges
+    let ranges: Vec<String> = reqs.iter().filter_map(|r| r.range_str()).collect();
+    let minimum = check_version_conflicts(&HashMap::from([(module.to_string(), ranges)]))
+        .map_err(|e| explain_conflict(e, reqs))?
+        .remove(module)
+        .unwrap_or(SemVer { major: 0, minor: 0, patch: 0 });
+    let satisfies_all = |v: &SemVer| reqs.iter().all(|r| r.range().is_some_and(|range| range.matches(v)));
+
+    let git = if is_embedded(module) {
+        None
+    } else {
+        reqs.iter()
+            .find_map(|r| match &r.entry {
+                RequireEntry::Git { git, .. } => Some(git.clone()),
+                _ => None,
+            })
+            .or_else(|| git_source(module))
+    };
+    let version_choice = |version: SemVer, tag: Option<String>| Choice::Version {
+        version,
+        git: git.clone(),
+        tag,
+    };
+
+    // Keep the locked version while it satisfies every requirement
+    if let Some(locked) = ctx.locked(module) {
+        if let Some(v) = parse_tag(&locked.version) {
+            if v >= minimum && satisfies_all(&v) {
+                let tag = git.as_ref().map(|_| locked.version.clone());
+                return Ok((version_choice(v, tag), "locked in ogham.lock.yaml".to_string()));
+            }
         }
     }
 
-    Ok(())
+    let setter = reqs
+        .iter()
+        .find(|r| r.range().and_then(|range| range.minimum()).as_ref() == Some(&minimum))
+        .unwrap_or(&reqs[0]);
+    let reason = if reqs.len() > 1 {
+        format!(
+            "{} requires {} (highest minimum of {} requirements)",
+            setter.by,
+            setter.describe(),
+            reqs.len()
+        )
+    } else {
+        format!("{} requires {}", setter.by, setter.describe())
+    };
+
+    // A requirement names a tag at exactly the minimum
+    let exact_tag = reqs.iter().find_map(|r| match &r.entry {
+        RequireEntry::Git { tag: Some(t), .. } if parse_tag(t).as_ref() == Some(&minimum) => Some(t.clone()),
+        _ => None,
+    });
+    if let Some(tag) = exact_tag {
+        return Ok((version_choice(minimum, Some(tag)), reason));
+    }
+
+    if is_embedded(module) {
+        return Ok((version_choice(minimum, None), reason));
+    }
+
+    if ctx.mode == LockMode::Frozen {
+        if git.is_none() {
+            return Ok((version_choice(minimum, None), reason)); // from the cache
+        }
+        return Err(format!(
+            "{}: no locked version satisfies {}, and --frozen forbids listing tags",
+            module,
+            describe_all(reqs)
+        ));
+    }
+
+    // No proxy has the module and there is no git source: only the cache can have it
+    let Some((source, available)) = versions.get(module, git.as_deref(), ctx)? else {
+        return Ok((version_choice(minimum, None), reason));
+    };
+    let best = available
+        .iter()
+        .filter_map(|t| parse_tag(t).map(|v| (v, t)))
+        .filter(|(v, _)| *v >= minimum && satisfies_all(v))
+        .min_by(|a, b| a.0.cmp(&b.0));
+    match best {
+        Some((v, tag)) => Ok((version_choice(v, Some(tag.clone())), reason)),
+        None => Err(format!(
+            "no version of {} from {} satisfies {}; available: {}",
+            module,
+            source,
+            describe_all(reqs),
+            if available.is_empty() { "none".to_string() } else { available.join(", ") }
+        )),
+    }
+}
+
+fn fetch_choice(module: &str, choice: &Choice, ctx: &ResolveCtx) -> Result<ResolvedDep, ResolveError> {
+    match choice {
+        Choice::Member(dir) => Ok(ResolvedDep {
+            module: module.to_string(),
+            version: "workspace".to_string(),
+            path: dir.clone(),
+            source: DepSource::Path,
+        }),
+        Choice::Replace(rep, dir) => Ok(resolve_replace(module, rep, dir, ctx)?),
+        Choice::Entry(entry, base_dir) => resolve_require(module, entry, base_dir, ctx),
+        Choice::Version { version, git, tag } => {
+            fetch_version(module, version, git.as_deref(), tag.as_deref(), ctx)
+        }
+    }
+}
+
+/// What a `replace` points at, for comparing replaces from different
+/// workspace members.
+fn replace_target(rep: &ReplaceEntry, dir: &Path) -> String {
+    match rep {
+        ReplaceEntry::Path { path } => {
+            let target = dir.join(path);
+            target.canonicalize().unwrap_or(target).display().to_string()
+        }
+        ReplaceEntry::Git { git, branch } => format!("{} {}", git, branch.as_deref().unwrap_or("HEAD")),
+    }
+}
+
+/// Append who requires what to a version conflict error.
+fn explain_conflict(err: String, reqs: &[Requirement]) -> String {
+    let mut out = err;
+    out.push_str("\nrequirements:");
+    for req in reqs {
+        out.push_str(&format!("\n  {} requires {}", req.by, req.describe()));
+    }
+    out
+}
+
+fn describe_all(reqs: &[Requirement]) -> String {
+    reqs.iter().map(|r| r.describe()).collect::<Vec<_>>().join(", ")
+}
+
+/// Parse a version-like git tag: `v1.2.3`, `1.2`, `v2`. Tags with anything
+/// else (`v1.2.3-rc1`, `release-1`) are not versions.
+pub(crate) fn parse_tag(tag: &str) -> Option<SemVer> {
+    let digits = tag.strip_prefix('v').unwrap_or(tag);
+    let parts: Vec<&str> = digits.split('.').collect();
+    if parts.len() > 3 || parts.iter().any(|p| p.is_empty() || !p.bytes().all(|b| b.is_ascii_digit())) {
+        return None;
+    }
+    SemVer::parse(digits)
+}
+
+/// Available versions per module, listed once per resolution.
+#[derive(Default)]
+struct VersionLists {
+    /// module → (where the list came from, versions)
+    by_module: HashMap<String, Option<(String, Vec<String>)>>,
+}
+
+impl VersionLists {
+    /// Walk the proxy chain: the first proxy that knows the module lists its
+    /// versions; `direct` lists the tags of its git source.
+    fn get(
+        &mut self,
+        module: &str,
+        git: Option<&str>,
+        ctx: &ResolveCtx,
+    ) -> Result<Option<(&str, &Vec<String>)>, String> {
+        if !self.by_module.contains_key(module) {
+            let found = list_along(module, git, ctx.proxies())?;
+            self.by_module.insert(module.to_string(), found);
+        }
+        Ok(self.by_module[module]
+            .as_ref()
+            .map(|(source, list)| (source.as_str(), list)))
+    }
+}
+
+fn list_along(
+    module: &str,
+    git: Option<&str>,
+    proxies: &[ProxyEntry],
+) -> Result<Option<(String, Vec<String>)>, String> {
+    for entry in proxies {
+        match entry {
+            ProxyEntry::Url(base) => {
+                if let Some(list) = proxy::list(base, module)? {
+                    return Ok(Some((base.clone(), list)));
+                }
+            }
+            ProxyEntry::Direct => {
+                return match git {
+                    Some(url) => Ok(Some((url.to_string(), list_tags(url)?))),
+                    None => Ok(None),
+                };
+            }
+        }
+    }
+    Ok(None)
+}
+
+/// Git source implied by a module path (`github.com/org/lib` → its https URL).
+fn git_source(module: &str) -> Option<String> {
+    match auto_detect_source(module) {
+        RequireEntry::Git { git, .. } => Some(git),
+        _ => None,
+    }
+}
+
+/// Tags of a git remote, via `git ls-remote`.
+fn list_tags(url: &str) -> Result<Vec<String>, String> {
+    eprintln!("  listing tags of {}", url);
+    let output = Command::new("git")
+        .args(["ls-remote", "--tags", "--refs", url])
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| format!("git ls-remote failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "cannot list tags of {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| line.split('\t').nth(1)?.strip_prefix("refs/tags/"))
+        .map(str::to_string)
+        .collect())
 }
 
 /// Check for conflicting version requirements using full semver range matching.
@@ -369,6 +1123,127 @@ fn check_version_conflicts(
     Ok(selected)
 }
 
+/// Compare resolved dependencies with the lock file (`--locked`).
+fn check_lock(deps: &[ResolvedDep], lock: &manifest::LockFile) -> Result<(), String> {
+    let mut problems = Vec::new();
+
+    for dep in deps {
+        match lock.locked.get(&dep.module) {
+            None => problems.push(format!("{} is required but not locked", dep.module)),
+            Some(locked) if locked.version != dep.version => problems.push(format!(
+                "{} is locked at {} but ogham.mod.yaml resolves {}",
+                dep.module, locked.version, dep.version
+            )),
+            Some(_) => {}
+        }
+    }
+
+    let required: HashSet<&str> = deps.iter().map(|d| d.module.as_str()).collect();
+    for module in lock.locked.keys() {
+        if !required.contains(module.as_str()) {
+            problems.push(format!("{} is locked but no longer required", module));
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(format!(
+        "ogham.mod.yaml and ogham.lock.yaml disagree:\n  {}\nrun `ogham install` to update the lock file",
+        problems.join("\n  ")
+    ))
+}
+
+/// Check each fetched dependency against the content hash in the lock file.
+/// A mismatch means a tag was force-pushed or the cache was modified.
+fn verify_hashes(deps: &[ResolvedDep], lock: &manifest::LockFile) -> Result<(), ResolveError> {
+    for dep in deps {
+        let Some(expected) = lock
+            .locked
+            .get(&dep.module)
+            .filter(|l| l.version == dep.version)
+            .and_then(|l| l.hash.as_deref())
+        else {
+            continue;
+        };
+        let Some(actual) = dep_hash(dep)? else {
+            continue;
+        };
+        if actual != expected {
+            return Err(ResolveError::ChecksumMismatch(format!(
+                "checksum mismatch for {} {}:\n  ogham.lock.yaml: {}\n  downloaded:      {}\n\
+                 the dependency changed since it was locked (force-pushed tag or modified cache in {})",
+                dep.module,
+                dep.version,
+                expected,
+                actual,
+                dep.path.display()
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Content hash of a fetched dependency. Path dependencies are local
+/// working copies and embedded std has no files, so neither is hashed.
+fn dep_hash(dep: &ResolvedDep) -> Result<Option<String>, String> {
+    if matches!(dep.source, DepSource::Path) || !dep.path.is_dir() {
+        return Ok(None);
+    }
+    hash_dir(&dep.path).map(Some)
+}
+
+/// The files of a module under `dir` as `(/-separated relative path, path)`,
+/// sorted by path: everything outside hidden entries and `exclude`. Both the
+/// zip [`proxy::pack_zip`] builds and [`hash_dir`] cover exactly these.
+pub(crate) fn module_files(dir: &Path, exclude: &[&Path]) -> Result<Vec<(String, PathBuf)>, String> {
+    fn collect(root: &Path, dir: &Path, exclude: &[&Path], out: &mut Vec<(String, PathBuf)>) -> Result<(), String> {
+        let entries = std::fs::read_dir(dir)
+            .map_err(|e| format!("cannot read {}: {}", dir.display(), e))?;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry.file_name().to_string_lossy().starts_with('.') || exclude.contains(&path.as_path()) {
+                continue;
+            }
+            if path.is_dir() {
+                collect(root, &path, exclude, out)?;
+            } else {
+                let rel = path.strip_prefix(root).unwrap_or(&path);
+                let rel = rel
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                out.push((rel, path));
+            }
+        }
+        Ok(())
+    }
+
+    let mut files = Vec::new();
+    collect(dir, dir, exclude, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+/// Deterministic hash of a module: every file outside hidden entries
+/// (`.git`), keyed by `/`-separated relative path — the files a module zip
+/// carries, and a plugin module builds from.
+///
+/// Format: `h1:` + hex SHA-256 over lines of `<file sha256>  <path>\n`,
+/// sorted by path.
+pub fn hash_dir(dir: &Path) -> Result<String, String> {
+    let files = module_files(dir, &[])?;
+
+    let mut summary = Sha256::new();
+    for (rel, path) in &files {
+        let content = std::fs::read(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        summary.update(format!("{:x}  {}\n", Sha256::digest(&content), rel));
+    }
+    Ok(format!("h1:{:x}", summary.finalize()))
+}
+
 /// Write lock file with resolved dependency versions and git commits.
 pub fn write_lock_file(project_dir: &Path, deps: &[ResolvedDep]) -> Result<(), String> {
     let lock_path = project_dir.join("ogham.lock.yaml");
@@ -381,18 +1256,15 @@ pub fn write_lock_file(project_dir: &Path, deps: &[ResolvedDep]) -> Result<(), S
 
         // For git deps, record the commit hash
         if matches!(dep.source, DepSource::Git) && dep.path.is_dir() {
-            if let Ok(output) = Command::new("git")
-                .args(["rev-parse", "HEAD"])
-                .current_dir(&dep.path)
-                .output()
-            {
-                if output.status.success() {
-                    let hash = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                    content.push_str(&format!("    commit: \"{}\"\n", hash));
-                }
+            if let Some(hash) = head_commit(&dep.path) {
+                content.push_str(&format!("    commit: \"{}\"\n", hash));
             }
         }
 
+        if let Some(hash) = dep_hash(dep)? {
+            content.push_str(&format!("    hash: \"{}\"\n", hash));
+        }
+
         let source = match dep.source {
             DepSource::Path => "path",
             DepSource::Git => "git",
@@ -462,6 +1334,7 @@ pub fn check_integrity(deps: &[ResolvedDep]) -> Vec<String> {
 /// Auto-detect dependency source from a spec string.
 /// `github.com/org/lib` → git clone from https://github.com/org/lib.git
 /// `github.com/org/lib@v1.0.0` → git clone + tag
+/// `github.com/org/lib@^1.0.0` → version range (tags of the git source)
 pub fn auto_detect_source(spec: &str) -> RequireEntry {
     let (module, version) = if let Some(at) = spec.find('@') {
         (&spec[..at], Some(&spec[at + 1..]))
@@ -469,6 +1342,11 @@ pub fn auto_detect_source(spec: &str) -> RequireEntry {
         (spec, None)
     };
 
+    // A range is never a tag name
+    if let Some(range) = version.filter(|v| v.starts_with(['^', '~', '=', '>', '*'])) {
+        return RequireEntry::Version(range.to_string());
+    }
+
     // GitHub/GitLab/etc → git dependency
     if module.starts_with("github.com/")
         || module.starts_with("gitlab.com/")
@@ -487,19 +1365,86 @@ pub fn auto_detect_source(spec: &str) -> RequireEntry {
     RequireEntry::Version(version.unwrap_or("*").to_string())
 }
 
-/// Update dependencies to the maximum version within their range.
-pub fn update_deps(project_dir: &Path) -> Result<Vec<ResolvedDep>, String> {
-    // For git deps: fetch latest
-    // For version deps: would query registry for latest within range
-    // For now: re-resolve (which fetches latest git refs if not cached)
+/// A dependency whose locked version or commit changed in `update`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub module: String,
+    /// `v1.2.0`, or `main @ 1a2b3c4d5e6f` for git; `None` if newly added.
+    pub old: Option<String>,
+    /// `None` if no longer required.
+    pub new: Option<String>,
+}
+
+/// Result of `update`: the new resolution, for writing the lock file, and
+/// what changed compared to the old lock.
+#[derive(Debug)]
+pub struct Update {
+    pub deps: Vec<ResolvedDep>,
+    pub changes: Vec<Change>,
+}
 
-    // Clear git cache to force re-fetch
-    let git_cache = ogham_home().join("git").join("checkouts");
-    if git_cache.is_dir() {
-        let _ = std::fs::remove_dir_all(&git_cache);
+/// Re-resolve `modules` (all dependencies if empty) as if they were not
+/// locked: branches and tags are looked up on their remotes again and
+/// version ranges re-run MVS. Every other dependency stays at its locked
+/// version and commit. Nothing is written — the caller rewrites the lock
+/// from `Update::deps`.
+pub fn update(project_dir: &Path, modules: &[String]) -> Result<Update, String> {
+    update_in(project_dir, modules, &ogham_home())
+}
+
+fn update_in(project_dir: &Path, modules: &[String], home: &Path) -> Result<Update, String> {
+    let old = manifest::load_lock_file(project_dir)?.unwrap_or_default();
+    let mod_file = manifest::load_mod_file(project_dir)?;
+    for module in modules {
+        if !old.locked.contains_key(module) && !mod_file.require.contains_key(module) {
+            return Err(format!("{} is not a dependency of {}", module, mod_file.module));
+        }
+    }
+
+    let mut lock = manifest::LockFile::default();
+    if !modules.is_empty() {
+        lock.locked = old
+            .locked
+            .iter()
+            .filter(|(module, _)| !modules.contains(module))
+            .map(|(module, locked)| (module.clone(), locked.clone()))
+            .collect();
+    }
+    let ctx = ResolveCtx {
+        lock: Some(lock),
+        home: home.to_path_buf(),
+        ..ResolveCtx::from_env()
+    };
+    let deps = resolve_with(project_dir, &ctx)?.deps;
+
+    let describe = |version: &str, commit: Option<&str>| match commit {
+        Some(commit) => format!("{} @ {}", version, short_commit(commit)),
+        None => version.to_string(),
+    };
+    let mut changes = Vec::new();
+    for dep in &deps {
+        let commit = matches!(dep.source, DepSource::Git).then(|| head_commit(&dep.path)).flatten();
+        let new = describe(&dep.version, commit.as_deref());
+        let old = old.locked.get(&dep.module).map(|l| describe(&l.version, l.commit.as_deref()));
+        if old.as_ref() != Some(&new) {
+            changes.push(Change {
+                module: dep.module.clone(),
+                old,
+                new: Some(new),
+            });
+        }
+    }
+    for (module, locked) in &old.locked {
+        if !deps.iter().any(|d| &d.module == module) {
+            changes.push(Change {
+                module: module.clone(),
+                old: Some(describe(&locked.version, locked.commit.as_deref())),
+                new: None,
+            });
+        }
     }
 
-    resolve_deps(project_dir)
+    Ok(Update { deps, changes })
 }
 
 /// Collect .ogham source files from all resolved dependencies.
@@ -512,10 +1457,21 @@ pub fn collect_dep_sources(deps: &[ResolvedDep]) -> Result<Vec<SourceFile>, Stri
 }
 
 /// Install all dependencies (fetch + cache).
-pub fn install(project_dir: &Path) -> Result<Vec<ResolvedDep>, String> {
-    let deps = resolve_deps(project_dir)?;
+pub fn install(project_dir: &Path, mode: LockMode) -> Result<Vec<ResolvedDep>, String> {
+    let deps = resolve_deps_with(project_dir, mode)?;
+    report_installed(&deps);
+    Ok(deps)
+}
 
-    for dep in &deps {
+/// Install the dependencies of every workspace member.
+pub fn install_workspace(ws: &Workspace, mode: LockMode) -> Result<Vec<ResolvedDep>, String> {
+    let deps = resolve_workspace(ws, mode)?.deps;
+    report_installed(&deps);
+    Ok(deps)
+}
+
+fn report_installed(deps: &[ResolvedDep]) {
+    for dep in deps {
         eprintln!(
             "  {} v{} ({})",
             dep.module,
@@ -527,66 +1483,339 @@ pub fn install(project_dir: &Path) -> Result<Vec<ResolvedDep>, String> {
             }
         );
     }
-
-    Ok(deps)
 }
 
 /// Add a dependency to ogham.mod.yaml.
 pub fn add_dependency(project_dir: &Path, spec: &str) -> Result<(), String> {
     let mod_path = project_dir.join("ogham.mod.yaml");
-    let mut content = if mod_path.exists() {
-        std::fs::read_to_string(&mod_path)
-            .map_err(|e| format!("cannot read ogham.mod.yaml: {}", e))?
-    } else {
-        return Err("ogham.mod.yaml not found".into());
+    let mod_file = manifest::load_mod_file(project_dir)?;
+    let content = std::fs::read_to_string(&mod_path)
+        .map_err(|e| format!("cannot read ogham.mod.yaml: {}", e))?;
+
+    let module = spec.split('@').next().unwrap_or(spec);
+    if mod_file.require.contains_key(module) {
+        return Err(format!("{} already in dependencies", module));
+    }
+
+    let entry = auto_detect_source(spec);
+    std::fs::write(&mod_path, manifest::set_require(&content, module, &entry))
+        .map_err(|e| format!("cannot write ogham.mod.yaml: {}", e))?;
+
+    eprintln!("added {} ({})", module, Requirement::from_entry(entry).describe());
+
+    // Fetch the dependency
+    let _ = resolve_deps(project_dir);
+
+    Ok(())
+}
+
+/// What `ogham tidy` changed in ogham.mod.yaml.
+#[derive(Debug, Default)]
+pub struct Tidy {
+    pub added: Vec<(String, RequireEntry)>,
+    pub removed: Vec<String>,
+}
+
+/// Sync `require` with the imports of the project: add entries for imported
+/// modules that are not required (source from `auto_detect_source`), drop
+/// entries that no file imports — directly or through another dependency —
+/// and no plugin in ogham.gen.yaml names. Rewrites ogham.mod.yaml and the
+/// lock file.
+pub fn tidy(project_dir: &Path) -> Result<Tidy, String> {
+    let mod_path = project_dir.join("ogham.mod.yaml");
+    let mut mod_file = manifest::load_mod_file(project_dir)?;
+    let mut content = std::fs::read_to_string(&mod_path)
+        .map_err(|e| format!("cannot read ogham.mod.yaml: {}", e))?;
+
+    // A workspace member resolves with the other members, and the
+    // workspace has the lock file
+    let workspace = Workspace::find(project_dir)?.filter(|ws| ws.member_at(project_dir).is_some());
+    let ctx = ResolveCtx {
+        lock: manifest::load_lock_file(workspace.as_ref().map_or(project_dir, |ws| ws.root.as_path()))?,
+        members: workspace
+            .iter()
+            .flat_map(|ws| &ws.members)
+            .map(|m| (m.module().to_string(), m.dir.clone()))
+            .collect(),
+        ..ResolveCtx::from_env()
     };
 
-    // Parse module@version or just module
-    let (module, version) = if let Some(at) = spec.find('@') {
-        (&spec[..at], &spec[at + 1..])
-    } else {
-        (spec, "*")
+    let schemas_dir = project_dir.join("schemas");
+    let mut sources = Vec::new();
+    collect_ogham_files(if schemas_dir.is_dir() { &schemas_dir } else { project_dir }, &mut sources)?;
+    let imports = imported_paths(&sources);
+
+    // Add modules that are imported but not required. Workspace members
+    // and modules the project already depends on are known by name
+    let resolved = resolve_roots(&[(project_dir, &mod_file)], &ctx).map(|r| r.deps).unwrap_or_default();
+    let known: Vec<&str> = ctx
+        .members
+        .keys()
+        .map(String::as_str)
+        .chain(resolved.iter().map(|d| d.module.as_str()))
+        .collect();
+    let mut tidy = Tidy::default();
+    let mut missing: Vec<String> = imports
+        .iter()
+        .filter(|path| !is_embedded(path) && !is_within(path, &mod_file.module))
+        .filter(|path| !mod_file.require.keys().any(|m| is_within(path, m)))
+        .map(|path| guess_module(path, &known))
+        .collect();
+    missing.sort();
+    missing.dedup();
+    for module in missing {
+        let entry = auto_detect_source(&module);
+        content = manifest::set_require(&content, &module, &entry);
+        mod_file.require.insert(module.clone(), entry.clone());
+        tidy.added.push((module, entry));
+    }
+
+    // Modules in use: imported by the project, named by a plugin, or
+    // imported by a dependency in use
+    let resolution = resolve_roots(&[(project_dir, &mod_file)], &ctx)?;
+    let modules: Vec<&str> = resolution.deps.iter().map(|d| d.module.as_str()).collect();
+    let owner = |path: &str| {
+        modules
+            .iter()
+            .filter(|m| is_within(path, m))
+            .max_by_key(|m| m.len())
+            .map(|m| m.to_string())
     };
+    let mut used: HashSet<String> = imports.iter().filter_map(|p| owner(p)).collect();
+    if let Ok(gen) = manifest::load_gen_file(project_dir) {
+        used.extend(gen.generate.plugins.into_iter().filter_map(|p| p.name));
+    }
+    let mut scanned: HashSet<String> = HashSet::new();
+    while let Some(dep) = resolution
+        .deps
+        .iter()
+        .find(|d| used.contains(&d.module) && !scanned.contains(&d.module))
+    {
+        scanned.insert(dep.module.clone());
+        let mut dep_sources = Vec::new();
+        collect_ogham_files(&dep.path, &mut dep_sources)?;
+        used.extend(imported_paths(&dep_sources).iter().filter_map(|p| owner(p)));
+    }
 
-    // Check if already in require
-    if content.contains(module) {
-        return Err(format!("{} already in dependencies", module));
+    let mut unused: Vec<String> = mod_file.require.keys().filter(|m| !used.contains(*m)).cloned().collect();
+    unused.sort();
+    for module in unused {
+        if let Some(edited) = manifest::remove_require(&content, &module) {
+            content = edited;
+        }
+        mod_file.require.remove(&module);
+        tidy.removed.push(module);
     }
 
-    // Append to require section
-    if content.contains("require:") {
-        // Add after require:
-        let insert_pos = content
-            .find("require:")
-            .map(|p| {
-                // Find end of require line
-                content[p..].find('\n').map(|n| p + n + 1).unwrap_or(content.len())
-            })
-            .unwrap_or(content.len());
+    // The edit must parse back to exactly the intended requirements
+    let written: manifest::ModFile =
+        serde_yaml::from_str(&content).map_err(|e| format!("tidy produced invalid ogham.mod.yaml: {}", e))?;
+    let mut expected: Vec<&String> = mod_file.require.keys().collect();
+    let mut actual: Vec<&String> = written.require.keys().collect();
+    expected.sort();
+    actual.sort();
+    if expected != actual {
+        return Err("tidy could not edit the require section of ogham.mod.yaml; edit it by hand".into());
+    }
 
-        let line = format!("  {}: {}\n", module, version);
-        content.insert_str(insert_pos, &line);
-    } else {
-        content.push_str(&format!("\nrequire:\n  {}: {}\n", module, version));
+    std::fs::write(&mod_path, &content).map_err(|e| format!("cannot write ogham.mod.yaml: {}", e))?;
+
+    match &workspace {
+        // Reloaded: the member manifest has just changed
+        Some(ws) => {
+            let ws = Workspace::load(&ws.root)?;
+            write_lock_file(&ws.root, &resolve_workspace(&ws, LockMode::Auto)?.deps)?
+        }
+        None => write_lock_file(project_dir, &resolve_with(project_dir, &ctx)?.deps)?,
     }
 
-    std::fs::write(&mod_path, &content)
-        .map_err(|e| format!("cannot write ogham.mod.yaml: {}", e))?;
+    Ok(tidy)
+}
 
-    eprintln!("added {} v{}", module, version);
+/// Every import path in `sources`.
+fn imported_paths(sources: &[SourceFile]) -> HashSet<String> {
+    use crate::ast::AstNode;
 
-    // Fetch the dependency
-    let _ = resolve_deps(project_dir);
+    let mut paths = HashSet::new();
+    for source in sources {
+        let parse = crate::parser::parse(&source.content);
+        if let Some(root) = crate::ast::Root::cast(parse.syntax()) {
+            paths.extend(root.imports().iter().filter_map(|i| i.path()).map(|p| p.text()));
+        }
+    }
+    paths
+}
 
-    Ok(())
+/// Whether import `path` is module `module` or one of its packages.
+fn is_within(path: &str, module: &str) -> bool {
+    path.strip_prefix(module).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// The module an import path most likely belongs to: the longest of the
+/// `known` modules it is within, else `host/org/repo` on known git hosts
+/// and the whole path elsewhere.
+fn guess_module(path: &str, known: &[&str]) -> String {
+    if let Some(module) = known.iter().filter(|m| is_within(path, m)).max_by_key(|m| m.len()) {
+        return module.to_string();
+    }
+    if git_source(path).is_some() {
+        return path.splitn(4, '/').take(3).collect::<Vec<_>>().join("/");
+    }
+    path.to_string()
+}
+
+/// Fetch a single module (`module@version`) into the cache and return its directory.
+/// Used to compare against a published version of a module.
+pub fn fetch_module(spec: &str) -> Result<PathBuf, String> {
+    Ok(fetch_dep(spec)?.path)
+}
+
+/// Fetch a single module (`module[@version]`) into the cache, the same way
+/// a `require` entry written as the spec would be.
+pub fn fetch_dep(spec: &str) -> Result<ResolvedDep, String> {
+    let module = spec.split('@').next().unwrap_or(spec);
+    Ok(resolve_require(module, &auto_detect_source(spec), Path::new("."), &ResolveCtx::from_env())?)
 }
 
-/// Copy all dependencies into vendor/ directory.
-pub fn vendor(project_dir: &Path) -> Result<(), String> {
+/// Versions of `module` available along a proxy chain: from the first proxy
+/// that has it, or the version-like tags of its git source on `direct`.
+/// `None` if nothing in the chain knows the module.
+pub fn list_versions(module: &str, proxies: &[ProxyEntry]) -> Result<Option<Vec<String>>, String> {
+    let found = list_along(module, git_source(module).as_deref(), proxies)?;
+    Ok(found.map(|(_, list)| list.into_iter().filter(|v| parse_tag(v).is_some()).collect()))
+}
+
+/// Fetch `module` at exactly `version` (a listed version or tag) along a
+/// proxy chain, the same way resolution does.
+pub fn fetch_exact(module: &str, version: &str, proxies: &[ProxyEntry]) -> Result<ResolvedDep, String> {
+    let semver = parse_tag(version).ok_or_else(|| format!("invalid version: {}", version))?;
+    let ctx = ResolveCtx {
+        proxies: proxies.to_vec(),
+        ..ResolveCtx::from_env()
+    };
+    Ok(fetch_version(module, &semver, git_source(module).as_deref(), Some(version), &ctx)?)
+}
+
+/// Highest version of `module` below `version` with the same major version,
+/// listed along a proxy chain — what a new release must stay compatible
+/// with. Errors if `version` itself is already listed.
+pub fn previous_version(
+    module: &str,
+    version: &str,
+    proxies: &[ProxyEntry],
+) -> Result<Option<String>, String> {
+    let current = parse_tag(version).ok_or_else(|| format!("invalid version: {}", version))?;
+    let listed = list_versions(module, proxies)?.unwrap_or_default();
+    if listed.iter().any(|v| parse_tag(v).as_ref() == Some(&current)) {
+        return Err(format!("{} {} is already published", module, version));
+    }
+    Ok(listed
+        .into_iter()
+        .filter_map(|v| parse_tag(&v).map(|s| (s, v)))
+        .filter(|(s, _)| s.major == current.major && *s < current)
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, v)| v))
+}
+
+/// Release artifacts of a module version, as a proxy serves them.
+#[derive(Debug, Clone)]
+pub struct Artifacts {
+    pub module: String,
+    pub version: String,
+    pub zip: Vec<u8>,
+    pub mod_file: Vec<u8>,
+    pub info: proxy::VersionInfo,
+}
+
+impl Artifacts {
+    /// Write the artifacts in the proxy layout, `<out>/<module>/@v/<version>.*`,
+    /// so `out` can be served by `ogham-proxy` as is.
+    pub fn write_to(&self, out: &Path) -> Result<PathBuf, String> {
+        let dir = out.join(&self.module).join("@v");
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        let info = serde_json::to_vec_pretty(&self.info)
+            .map_err(|e| format!("cannot serialize info: {}", e))?;
+        for (ext, content) in [("zip", &self.zip), ("mod", &self.mod_file), ("info", &info)] {
+            let path = dir.join(format!("{}.{}", self.version, ext));
+            std::fs::write(&path, content)
+                .map_err(|e| format!("cannot write {}: {}", path.display(), e))?;
+        }
+        Ok(dir)
+    }
+}
+
+/// Validate a module and build its release artifacts. The zip leaves out
+/// hidden files, `vendor/` and anything in `exclude`; the `.info` hash is
+/// computed from the zip's contents, so it is exactly what consumers verify.
+pub fn pack(dir: &Path, exclude: &[&Path]) -> Result<Artifacts, String> {
+    let mod_file = manifest::load_mod_file(dir)?;
+    validate_publishable(&mod_file)?;
+
+    let module = mod_file.module.clone();
+    let version = parse_tag(&mod_file.version)
+        .map(|v| format!("v{}", v))
+        .unwrap_or_default();
+
+    let vendor = dir.join("vendor");
+    let mut skipped: Vec<&Path> = exclude.to_vec();
+    skipped.push(&vendor);
+    let zip = proxy::pack_zip(dir, &module, &version, &skipped)?;
+
+    // Unique per call: threads of one process, such as a proxy packing
+    // uploads or parallel tests, share the pid
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let scratch = std::env::temp_dir().join(format!(
+        "ogham-pack-{}-{}-{}",
+        std::process::id(),
+        module.replace('/', "_"),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    let _ = std::fs::remove_dir_all(&scratch);
+    let hash = proxy::extract_zip(&zip, &proxy::zip_prefix(&module, &version), &scratch)
+        .and_then(|_| hash_dir(&scratch));
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    let mod_path = dir.join("ogham.mod.yaml");
+    let mod_content = std::fs::read(&mod_path)
+        .map_err(|e| format!("cannot read {}: {}", mod_path.display(), e))?;
+
+    Ok(Artifacts {
+        info: proxy::VersionInfo {
+            version: version.clone(),
+            time: commit_time(dir),
+            hash: Some(hash?),
+        },
+        module,
+        version,
+        zip,
+        mod_file: mod_content,
+    })
+}
+
+/// Copy all dependencies into vendor/, write vendor/modules.yaml and
+/// bring ogham.lock.yaml up to date, so `vendored_deps` can check one
+/// against the other. Embedded std modules ship with the compiler and are
+/// not copied.
+pub fn vendor(project_dir: &Path) -> Result<Vec<ResolvedDep>, String> {
+    if Workspace::find(project_dir)?.is_some() {
+        return Err("vendor/ is not supported in workspaces; use `ogham install` at the workspace root".into());
+    }
+
     let deps = resolve_deps(project_dir)?;
+    write_lock_file(project_dir, &deps)?;
+
+    // vendor/ is generated — start over so removed dependencies go away
     let vendor_dir = project_dir.join("vendor");
+    if vendor_dir.exists() {
+        std::fs::remove_dir_all(&vendor_dir)
+            .map_err(|e| format!("cannot remove {}: {}", vendor_dir.display(), e))?;
+    }
+    std::fs::create_dir_all(&vendor_dir)
+        .map_err(|e| format!("cannot create {}: {}", vendor_dir.display(), e))?;
 
-    for dep in &deps {
+    let vendored: Vec<&ResolvedDep> = deps.iter().filter(|d| !is_embedded(&d.module)).collect();
+    for dep in &vendored {
         let target = vendor_dir.join(&dep.module);
         if let Some(parent) = target.parent() {
             std::fs::create_dir_all(parent)
@@ -597,7 +1826,122 @@ pub fn vendor(project_dir: &Path) -> Result<(), String> {
         eprintln!("  vendored {} → {}", dep.module, target.display());
     }
 
-    Ok(())
+    // Hashed once everything is copied — a module nested in another's
+    // directory is part of both trees
+    let mut modules = String::new();
+    for dep in &vendored {
+        modules.push_str(&format!("  {}:\n", dep.module));
+        modules.push_str(&format!("    version: \"{}\"\n", dep.version));
+        modules.push_str(&format!("    hash: \"{}\"\n", hash_dir(&vendor_dir.join(&dep.module))?));
+    }
+
+    let manifest = format!(
+        "# Auto-generated by ogham vendor. Do not edit.\nmodules:{}",
+        if modules.is_empty() { " {}\n".to_string() } else { format!("\n{}", modules) }
+    );
+    let manifest_path = vendor_dir.join("modules.yaml");
+    std::fs::write(&manifest_path, manifest)
+        .map_err(|e| format!("cannot write {}: {}", manifest_path.display(), e))?;
+    Ok(deps)
+}
+
+/// Dependencies from vendor/, for compiling without the cache or network.
+///
+/// `Ok(None)` means resolve as usual: vendoring is off, or the project has
+/// no vendor/modules.yaml in `Auto` mode. A vendored tree that disagrees
+/// with ogham.lock.yaml, or whose files changed since `ogham vendor`, is an
+/// error listing every difference.
+pub fn vendored_deps(project_dir: &Path, mode: VendorMode) -> Result<Option<Vec<ResolvedDep>>, String> {
+    if mode == VendorMode::Off {
+        return Ok(None);
+    }
+    let Some(vendored) = manifest::load_vendor_manifest(project_dir)? else {
+        if mode == VendorMode::Vendor {
+            return Err("vendor/modules.yaml not found; run `ogham vendor` to create it".into());
+        }
+        return Ok(None);
+    };
+    let Some(lock) = manifest::load_lock_file(project_dir)? else {
+        return Err("vendor/ cannot be checked without ogham.lock.yaml; run `ogham vendor` to write both".into());
+    };
+    let mod_file = manifest::load_mod_file(project_dir)?;
+
+    let vendor_dir = project_dir.join("vendor");
+    let diff = vendor_diff(&vendor_dir, &vendored, &lock, &mod_file)?;
+    if !diff.is_empty() {
+        return Err(format!(
+            "vendor/ does not match ogham.lock.yaml:\n  {}\nrun `ogham vendor` to refresh vendor/",
+            diff.join("\n  ")
+        ));
+    }
+
+    Ok(Some(
+        vendored
+            .modules
+            .iter()
+            .map(|(module, v)| ResolvedDep {
+                module: module.clone(),
+                version: v.version.clone(),
+                path: vendor_dir.join(module),
+                source: DepSource::Path,
+            })
+            .collect(),
+    ))
+}
+
+/// Differences between vendor/ and the lock, one line each: `-` locked but
+/// not vendored, `+` vendored but not locked, `~` vendored differently.
+fn vendor_diff(
+    vendor_dir: &Path,
+    vendored: &manifest::VendorManifest,
+    lock: &manifest::LockFile,
+    mod_file: &manifest::ModFile,
+) -> Result<Vec<String>, String> {
+    let mut diff = Vec::new();
+
+    for (module, locked) in &lock.locked {
+        if is_embedded(module) {
+            continue;
+        }
+        match vendored.modules.get(module) {
+            None => diff.push(format!("- {} {} (locked, not in vendor/)", module, locked.version)),
+            Some(v) if v.version != locked.version => diff.push(format!(
+                "~ {}: ogham.lock.yaml has {}, vendor/ has {}",
+                module, locked.version, v.version
+            )),
+            Some(v) if locked.hash.as_ref().is_some_and(|h| *h != v.hash) => diff.push(format!(
+                "~ {} {}: vendored content differs from ogham.lock.yaml ({} vs {})",
+                module,
+                v.version,
+                v.hash,
+                locked.hash.as_deref().unwrap_or_default()
+            )),
+            Some(_) => {}
+        }
+    }
+
+    let mut required: Vec<&String> = mod_file.require.keys().collect();
+    required.sort();
+    for module in required {
+        if !is_embedded(module) && !lock.locked.contains_key(module) && !vendored.modules.contains_key(module) {
+            diff.push(format!("- {} (required by ogham.mod.yaml, not locked or vendored)", module));
+        }
+    }
+
+    for (module, v) in &vendored.modules {
+        if !lock.locked.contains_key(module) {
+            diff.push(format!("+ {} {} (in vendor/, not locked)", module, v.version));
+            continue;
+        }
+        let dir = vendor_dir.join(module);
+        if !dir.is_dir() {
+            diff.push(format!("- {} {} (listed in vendor/modules.yaml, directory missing)", module, v.version));
+        } else if hash_dir(&dir)? != v.hash {
+            diff.push(format!("~ {} {}: files in vendor/{} were modified", module, v.version, module));
+        }
+    }
+
+    Ok(diff)
 }
 
 // ── Resolution ─────────────────────────────────────────────────────────
@@ -606,12 +1950,13 @@ fn resolve_require(
     module: &str,
     entry: &RequireEntry,
     project_dir: &Path,
-) -> Result<ResolvedDep, String> {
+    ctx: &ResolveCtx,
+) -> Result<ResolvedDep, ResolveError> {
     match entry {
         RequireEntry::Path { path } => {
             let resolved = project_dir.join(path);
             if !resolved.is_dir() {
-                return Err(format!("path dependency not found: {} ({})", module, resolved.display()));
+                return Err(format!("path dependency not found: {} ({})", module, resolved.display()).into());
             }
             Ok(ResolvedDep {
                 module: module.to_string(),
@@ -621,42 +1966,89 @@ fn resolve_require(
             })
         }
         RequireEntry::Git { git, tag, branch, rev } => {
-            resolve_git(module, git, tag.as_deref(), branch.as_deref(), rev.as_deref())
-        }
-        RequireEntry::Version(version) => {
-            // Check cache first
-            let cache_dir = pkg_cache_dir(module, version);
-            if cache_dir.is_dir() {
-                return Ok(ResolvedDep {
-                    module: module.to_string(),
-                    version: version.clone(),
-                    path: cache_dir,
-                    source: DepSource::Cache,
-                });
-            }
+            Ok(resolve_git(module, git, tag.as_deref(), branch.as_deref(), rev.as_deref(), ctx)?)
+        }
+        RequireEntry::Version(range) => {
+            let minimum = VersionRange::parse(range)
+                .and_then(|r| r.minimum())
+                .ok_or_else(|| format!("invalid version range for {}: '{}'", module, range))?;
+            fetch_version(module, &minimum, None, None, ctx)
+        }
+    }
+}
 
-            // For std packages, they're embedded — create a marker
-            if module.starts_with("github.com/oghamlang/std") {
-                return Ok(ResolvedDep {
-                    module: module.to_string(),
-                    version: version.clone(),
-                    path: PathBuf::from("(embedded)"),
-                    source: DepSource::Cache,
-                });
-            }
+/// Fetch a module at an exact version: from the cache, the embedded std,
+/// or along the proxy chain — `direct` checks out the git tag.
+fn fetch_version(
+    module: &str,
+    version: &SemVer,
+    git: Option<&str>,
+    tag: Option<&str>,
+    ctx: &ResolveCtx,
+) -> Result<ResolvedDep, ResolveError> {
+    let name = format!("v{}", version);
+    let cached = |path: PathBuf| ResolvedDep {
+        module: module.to_string(),
+        version: name.clone(),
+        path,
+        source: DepSource::Cache,
+    };
+
+    // Check cache first
+    let cache_dir = pkg_cache_dir(&ctx.home, module, &name);
+    if cache_dir.is_dir() {
+        return Ok(cached(cache_dir));
+    }
+
+    // For std packages, they're embedded — create a marker
+    if is_embedded(module) {
+        return Ok(cached(PathBuf::from("(embedded)")));
+    }
 
-            Err(format!(
-                "dependency {} v{} not in cache. Use git or path dependency, or run `ogham install` with a registry configured.",
-                module, version
-            ))
+    let _lock = cache::lock(&cache_dir)?;
+    if cache_dir.is_dir() {
+        return Ok(cached(cache_dir)); // fetched by another process while we waited
+    }
+
+    let expected_hash = ctx
+        .locked(module)
+        .filter(|l| l.version == name)
+        .and_then(|l| l.hash.as_deref());
+    for entry in ctx.proxies() {
+        match entry {
+            ProxyEntry::Url(_) if ctx.mode == LockMode::Frozen => {}
+            ProxyEntry::Url(base) => {
+                if proxy::download(base, module, &name, &cache_dir, expected_hash)? {
+                    return Ok(cached(cache_dir));
+                }
+            }
+            ProxyEntry::Direct => {
+                if let Some(url) = git {
+                    let tag = tag.unwrap_or(&name);
+                    return Ok(resolve_git(module, url, Some(tag), None, None, ctx)?);
+                }
+                break;
+            }
         }
     }
+
+    Err(format!(
+        "dependency {} {} not in cache and not available from OGHAM_PROXY. Use git or path dependency, or configure a proxy.",
+        module, name
+    )
+    .into())
+}
+
+/// Std modules ship with the compiler.
+fn is_embedded(module: &str) -> bool {
+    module.starts_with("github.com/oghamlang/std")
 }
 
 fn resolve_replace(
     module: &str,
     replace: &ReplaceEntry,
     project_dir: &Path,
+    ctx: &ResolveCtx,
 ) -> Result<ResolvedDep, String> {
     match replace {
         ReplaceEntry::Path { path } => {
@@ -677,7 +2069,7 @@ fn resolve_replace(
             })
         }
         ReplaceEntry::Git { git, branch } => {
-            resolve_git(module, git, None, branch.as_deref(), None)
+            resolve_git(module, git, None, branch.as_deref(), None, ctx)
         }
     }
 }
@@ -688,83 +2080,280 @@ fn resolve_git(
     tag: Option<&str>,
     branch: Option<&str>,
     rev: Option<&str>,
+    ctx: &ResolveCtx,
 ) -> Result<ResolvedDep, String> {
     let git_ref = tag
         .or(branch)
         .or(rev)
         .unwrap_or("HEAD");
 
-    let version = tag.unwrap_or(git_ref);
-    let cache_dir = git_cache_dir(module, git_ref);
+    let version = tag.unwrap_or(git_ref);
+
+    // Pinned by the lock file?
+    if let Some(locked) = ctx.locked(module) {
+        match &locked.commit {
+            Some(commit) if locked.version == version => {
+                return resolve_git_commit(module, git_url, git_ref, version, commit, ctx);
+            }
+            _ if ctx.strict() => {
+                return Err(format!(
+                    "{} is locked at {} but ogham.mod.yaml requires {}; run `ogham install` to update the lock file",
+                    module, locked.version, version
+                ));
+            }
+            _ => {} // manifest changed — resolve afresh, `ogham install` rewrites the lock
+        }
+    } else if ctx.strict() {
+        return Err(format!(
+            "{} is required but not in ogham.lock.yaml; run `ogham install` to update the lock file",
+            module
+        ));
+    }
+
+    let resolved = |path: PathBuf| ResolvedDep {
+        module: module.to_string(),
+        version: version.to_string(),
+        path,
+        source: DepSource::Git,
+    };
+
+    // A full commit hash needs no lookup; tags, branches and HEAD are
+    // resolved on the remote so a moved branch gets a new checkout
+    let commit = match rev {
+        Some(r) if is_commit_hash(r) => r.to_string(),
+        Some(r) => return fetch_rev(module, git_url, r, ctx).map(resolved),
+        None => {
+            let ref_name = match (tag, branch) {
+                (Some(t), _) => format!("tags/{}", t),
+                (None, Some(b)) => format!("heads/{}", b),
+                (None, None) => "HEAD".to_string(),
+            };
+            match ls_remote(git_url, tag, branch) {
+                Ok(commit) => {
+                    remember_ref(&ctx.home, module, git_url, &ref_name, &commit);
+                    commit
+                }
+                // Offline: the commit the ref pointed to last time, if checked out
+                Err(e) => match remembered_ref(&ctx.home, module, git_url, &ref_name) {
+                    Some(commit) => {
+                        eprintln!("  {}; using the cached checkout at {}", e, short_commit(&commit));
+                        commit
+                    }
+                    None => return Err(e),
+                },
+            }
+        }
+    };
 
-    // Already cloned?
+    let cache_dir = git_cache_dir(&ctx.home, module, &commit);
     if cache_dir.is_dir() {
-        return Ok(ResolvedDep {
-            module: module.to_string(),
-            version: version.to_string(),
-            path: cache_dir,
-            source: DepSource::Git,
-        });
+        return Ok(resolved(cache_dir));
     }
 
-    // Clone
-    if let Some(parent) = cache_dir.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("cannot create cache dir: {}", e))?;
-    }
+    eprintln!("  fetching {} from {} ({} → {})", module, git_url, git_ref, short_commit(&commit));
+    fetch_commit(module, git_url, &commit, &cache_dir)?;
+    Ok(resolved(cache_dir))
+}
 
-    eprintln!("  fetching {} from {} ({})", module, git_url, git_ref);
+/// Check out an abbreviated rev, which `git ls-remote` and shallow fetches
+/// cannot name: fetch the branches, check out the rev, and move the
+/// checkout to the cache directory of its full commit.
+fn fetch_rev(module: &str, git_url: &str, rev: &str, ctx: &ResolveCtx) -> Result<PathBuf, String> {
+    if ctx.mode == LockMode::Frozen {
+        return Err(format!("{} @ {} is not in the cache and --frozen forbids fetching it", module, rev));
+    }
+    eprintln!("  fetching {} from {} ({})", module, git_url, rev);
+
+    let scratch = git_cache_dir(&ctx.home, module, &format!(".fetch-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&scratch);
+    std::fs::create_dir_all(&scratch).map_err(|e| format!("cannot create cache dir: {}", e))?;
+    let fetched = run_git(&["init", "--quiet"], &scratch)
+        .and_then(|_| run_git(&["fetch", "--quiet", git_url, "+refs/heads/*:refs/remotes/origin/*"], &scratch))
+        .and_then(|_| run_git(&["checkout", "--quiet", rev], &scratch));
+    let commit = match fetched.map(|_| head_commit(&scratch)) {
+        Ok(Some(commit)) => commit,
+        Ok(None) => {
+            let _ = std::fs::remove_dir_all(&scratch);
+            return Err(format!("cannot read the commit of {} @ {}", module, rev));
+        }
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&scratch);
+            return Err(format!("cannot fetch {} @ {}: {}", module, rev, e));
+        }
+    };
 
-    let mut cmd = Command::new("git");
-    cmd.args(["clone", "--depth", "1"]);
+    let cache_dir = git_cache_dir(&ctx.home, module, &commit);
+    let lock = cache::lock(&cache_dir);
+    if lock.is_err() || cache_dir.is_dir() {
+        let _ = std::fs::remove_dir_all(&scratch);
+    } else {
+        install_checkout(&scratch, &cache_dir)?;
+    }
+    lock?;
+    Ok(cache_dir)
+}
 
-    if let Some(b) = branch {
-        cmd.args(["--branch", b]);
-    } else if let Some(t) = tag {
-        cmd.args(["--branch", t]);
+/// Shallow-fetch one commit into `cache_dir`. The checkout is made in a
+/// scratch directory and moved into place under the entry's lock, so other
+/// processes never see it half-finished. Nothing is left behind on failure.
+fn fetch_commit(module: &str, git_url: &str, commit: &str, cache_dir: &Path) -> Result<(), String> {
+    let _lock = cache::lock(cache_dir)?;
+    if cache_dir.is_dir() {
+        return Ok(()); // fetched by another process while we waited
     }
 
-    cmd.arg(git_url);
-    cmd.arg(&cache_dir);
+    let scratch = cache::partial_dir(cache_dir);
+    let _ = std::fs::remove_dir_all(&scratch);
+    std::fs::create_dir_all(&scratch)
+        .map_err(|e| format!("cannot create cache dir: {}", e))?;
+    let fetched = run_git(&["init", "--quiet"], &scratch)
+        .and_then(|_| run_git(&["fetch", "--quiet", "--depth", "1", git_url, commit], &scratch))
+        .and_then(|_| run_git(&["checkout", "--quiet", "FETCH_HEAD"], &scratch));
+    if let Err(e) = fetched {
+        let _ = std::fs::remove_dir_all(&scratch);
+        return Err(format!("cannot fetch commit {} of {}: {}", commit, module, e));
+    }
+    install_checkout(&scratch, cache_dir)
+}
 
-    let output = cmd.output().map_err(|e| format!("git clone failed: {}", e))?;
+/// Record the content hash of a finished checkout and rename it into the
+/// cache. The caller holds the entry's lock.
+fn install_checkout(scratch: &Path, cache_dir: &Path) -> Result<(), String> {
+    let installed = hash_dir(scratch)
+        .and_then(|hash| cache::record_hash(scratch, &hash))
+        .and_then(|_| {
+            std::fs::rename(scratch, cache_dir)
+                .map_err(|e| format!("cannot move checkout to {}: {}", cache_dir.display(), e))
+        });
+    if installed.is_err() {
+        let _ = std::fs::remove_dir_all(scratch);
+    }
+    installed
+}
 
+/// Commit a tag, branch or (with neither) HEAD of a remote points to, via
+/// `git ls-remote`. Annotated tags resolve to the commit they tag.
+fn ls_remote(url: &str, tag: Option<&str>, branch: Option<&str>) -> Result<String, String> {
+    let (refs, name) = match (tag, branch) {
+        (Some(t), _) => (vec![format!("refs/tags/{}", t), format!("refs/tags/{}^{{}}", t)], format!("tag {}", t)),
+        (None, Some(b)) => (vec![format!("refs/heads/{}", b)], format!("branch {}", b)),
+        (None, None) => (vec!["HEAD".to_string()], "HEAD".to_string()),
+    };
+    let output = Command::new("git")
+        .arg("ls-remote")
+        .arg(url)
+        .args(&refs)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .output()
+        .map_err(|e| format!("git ls-remote failed: {}", e))?;
     if !output.status.success() {
         return Err(format!(
-            "git clone failed for {}: {}",
-            git_url,
+            "cannot resolve {} of {}: {}",
+            name,
+            url,
             String::from_utf8_lossy(&output.stderr).trim()
         ));
     }
 
-    // Checkout specific rev if provided
-    if let Some(r) = rev {
-        let output = Command::new("git")
-            .args(["checkout", r])
-            .current_dir(&cache_dir)
-            .output()
-            .map_err(|e| format!("git checkout failed: {}", e))?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let found: Vec<(&str, &str)> = stdout.lines().filter_map(|line| line.split_once('\t')).collect();
+    // The peeled `^{}` entry of an annotated tag is the commit itself
+    found
+        .iter()
+        .find(|(_, r)| r.ends_with("^{}"))
+        .or_else(|| found.iter().find(|(_, r)| refs.iter().any(|want| want == r)))
+        .map(|(commit, _)| commit.to_string())
+        .ok_or_else(|| format!("{} not found in {}", name, url))
+}
 
-        if !output.status.success() {
-            return Err(format!(
-                "git checkout {} failed: {}",
-                r,
-                String::from_utf8_lossy(&output.stderr).trim()
-            ));
-        }
-    }
+fn is_commit_hash(rev: &str) -> bool {
+    rev.len() == 40 && rev.chars().all(|c| c.is_ascii_hexdigit())
+}
 
-    Ok(ResolvedDep {
+pub(crate) fn short_commit(commit: &str) -> &str {
+    &commit[..commit.len().min(12)]
+}
+
+/// Check out a git dependency at the commit recorded in the lock file.
+fn resolve_git_commit(
+    module: &str,
+    git_url: &str,
+    git_ref: &str,
+    version: &str,
+    commit: &str,
+    ctx: &ResolveCtx,
+) -> Result<ResolvedDep, String> {
+    let resolved = |path: PathBuf| ResolvedDep {
         module: module.to_string(),
         version: version.to_string(),
-        path: cache_dir,
+        path,
         source: DepSource::Git,
-    })
+    };
+
+    let cache_dir = git_cache_dir(&ctx.home, module, commit);
+    if cache_dir.is_dir() {
+        return Ok(resolved(cache_dir));
+    }
+
+    if ctx.mode == LockMode::Frozen {
+        return Err(format!(
+            "{} @ {} is not in the cache and --frozen forbids fetching it",
+            module, commit
+        ));
+    }
+
+    eprintln!("  fetching {} from {} ({} @ {})", module, git_url, git_ref, short_commit(commit));
+    fetch_commit(module, git_url, commit, &cache_dir)?;
+    Ok(resolved(cache_dir))
+}
+
+/// Time of the commit checked out in a git working tree, RFC 3339.
+pub fn commit_time(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["log", "-1", "--format=%cI"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let time = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!time.is_empty()).then_some(time)
+}
+
+/// Commit checked out in a git working tree.
+fn head_commit(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "HEAD"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn run_git(args: &[&str], dir: &Path) -> Result<(), String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .map_err(|e| format!("git {} failed: {}", args[0], e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed: {}",
+            args[0],
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
 }
 
 // ── Paths ──────────────────────────────────────────────────────────────
 
-fn ogham_home() -> PathBuf {
+/// `$OGHAM_HOME`, by default `~/.ogham`.
+pub fn ogham_home() -> PathBuf {
     if let Ok(home) = std::env::var("OGHAM_HOME") {
         PathBuf::from(home)
     } else if let Ok(home) = std::env::var("HOME") {
@@ -774,26 +2363,47 @@ fn ogham_home() -> PathBuf {
     }
 }
 
-fn pkg_cache_dir(module: &str, version: &str) -> PathBuf {
-    ogham_home()
-        .join("pkg")
+fn pkg_cache_dir(home: &Path, module: &str, version: &str) -> PathBuf {
+    home.join("pkg")
         .join("mod")
         .join(format!("{}@{}", module, version))
 }
 
-fn git_cache_dir(module: &str, git_ref: &str) -> PathBuf {
-    // Sanitize ref for filesystem
-    let safe_ref = git_ref.replace('/', "_");
-    ogham_home()
-        .join("git")
+/// Checkout of one commit of a git dependency. Checkouts are immutable:
+/// a branch that moves resolves to a different directory.
+fn git_cache_dir(home: &Path, module: &str, commit: &str) -> PathBuf {
+    home.join("git")
         .join("checkouts")
         .join(module)
-        .join(safe_ref)
+        .join(commit)
+}
+
+/// Record of the commit a tag, branch or HEAD of a git dependency pointed to
+/// when last looked up, with the remote it was looked up on.
+fn git_ref_file(home: &Path, module: &str, git_ref: &str) -> PathBuf {
+    home.join("git").join("refs").join(module).join(git_ref)
+}
+
+fn remember_ref(home: &Path, module: &str, url: &str, git_ref: &str, commit: &str) {
+    let file = git_ref_file(home, module, git_ref);
+    if let Some(parent) = file.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    // Best effort: the record only spares a lookup when offline.
+    let _ = std::fs::write(file, format!("{} {}\n", commit, url));
+}
+
+/// The remembered commit of `git_ref`, if it came from `url` and its
+/// checkout is still cached.
+fn remembered_ref(home: &Path, module: &str, url: &str, git_ref: &str) -> Option<String> {
+    let record = std::fs::read_to_string(git_ref_file(home, module, git_ref)).ok()?;
+    let (commit, from) = record.trim_end().split_once(' ')?;
+    (from == url && git_cache_dir(home, module, commit).is_dir()).then(|| commit.to_string())
 }
 
 // ── Helpers ────────────────────────────────────────────────────────────
 
-fn collect_ogham_files(dir: &Path, sources: &mut Vec<SourceFile>) -> Result<(), String> {
+pub(crate) fn collect_ogham_files(dir: &Path, sources: &mut Vec<SourceFile>) -> Result<(), String> {
     if !dir.is_dir() {
         return Ok(()); // embedded or missing — skip
     }
@@ -823,7 +2433,7 @@ fn collect_ogham_files(dir: &Path, sources: &mut Vec<SourceFile>) -> Result<(),
     Ok(())
 }
 
-fn copy_dir(src: &Path, dst: &Path) -> Result<(), String> {
+pub(crate) fn copy_dir(src: &Path, dst: &Path) -> Result<(), String> {
     if !src.is_dir() {
         return Ok(());
     }
@@ -842,6 +2452,8 @@ fn copy_dir(src: &Path, dst: &Path) -> Result<(), String> {
                 continue; // skip .git in vendor
             }
             copy_dir(&path, &target)?;
+        } else if cache::is_metadata(&entry.file_name().to_string_lossy()) {
+            continue;
         } else {
             std::fs::copy(&path, &target)
                 .map_err(|e| format!("copy error: {}", e))?;
@@ -1077,7 +2689,7 @@ mod tests {
 
         let result = resolve_deps(&dir);
         assert!(result.is_err());
-        assert!(result.unwrap_err().contains("invalid version range"));
+        assert!(result.unwrap_err().to_string().contains("invalid version range"));
 
         let _ = fs::remove_dir_all(&dir);
     }
@@ -1263,4 +2875,544 @@ mod tests {
 
         let _ = fs::remove_dir_all(&dir);
     }
+
+    fn path_dep_project() -> PathBuf {
+        let dir = temp_dir();
+        let dep_dir = dir.join("libs").join("mylib");
+        fs::create_dir_all(&dep_dir).unwrap();
+        fs::write(dep_dir.join("types.ogham"), "package mylib;\n").unwrap();
+        fs::write(dir.join("ogham.mod.yaml"),
+            "module: github.com/test/proj\nversion: 0.1.0\nrequire:\n  mylib:\n    path: ./libs/mylib\n",
+        ).unwrap();
+        dir
+    }
+
+    #[test]
+    fn locked_requires_lock_file() {
+        let dir = path_dep_project();
+
+        let err = resolve_deps_with(&dir, LockMode::Locked).unwrap_err().to_string();
+        assert!(err.contains("ogham.lock.yaml not found"), "{}", err);
+        // Without --locked a missing lock is fine
+        assert_eq!(resolve_deps(&dir).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn locked_accepts_matching_lock() {
+        let dir = path_dep_project();
+        let deps = resolve_deps(&dir).unwrap();
+        write_lock_file(&dir, &deps).unwrap();
+
+        assert_eq!(resolve_deps_with(&dir, LockMode::Locked).unwrap().len(), 1);
+        assert_eq!(resolve_deps_with(&dir, LockMode::Frozen).unwrap().len(), 1);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn locked_rejects_stale_and_missing_entries() {
+        let dir = path_dep_project();
+        fs::write(dir.join("ogham.lock.yaml"),
+            "locked:\n  github.com/org/old:\n    version: \"v1.0.0\"\n    commit: \"abc\"\n    source: git\n",
+        ).unwrap();
+
+        let err = resolve_deps_with(&dir, LockMode::Locked).unwrap_err().to_string();
+        assert!(err.contains("mylib is required but not locked"), "{}", err);
+        assert!(err.contains("github.com/org/old is locked but no longer required"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn vendored_deps_are_checked_against_lock() {
+        let dir = path_dep_project();
+        assert!(vendored_deps(&dir, VendorMode::Auto).unwrap().is_none());
+        let err = vendored_deps(&dir, VendorMode::Vendor).unwrap_err();
+        assert!(err.contains("run `ogham vendor`"), "{}", err);
+
+        vendor(&dir).unwrap();
+        assert!(dir.join("ogham.lock.yaml").is_file());
+        let deps = vendored_deps(&dir, VendorMode::Auto).unwrap().unwrap();
+        assert_eq!(deps.len(), 1);
+        assert_eq!(deps[0].path, dir.join("vendor").join("mylib"));
+        assert!(vendored_deps(&dir, VendorMode::Off).unwrap().is_none());
+
+        fs::write(dir.join("vendor/mylib/types.ogham"), "package mylib;\ntype Changed {}\n").unwrap();
+        fs::write(dir.join("ogham.lock.yaml"),
+            "locked:\n  mylib:\n    version: \"local\"\n    source: path\n  github.com/org/lib:\n    version: \"v1.0.0\"\n    source: git\n",
+        ).unwrap();
+        let err = vendored_deps(&dir, VendorMode::Auto).unwrap_err();
+        assert!(err.contains("- github.com/org/lib v1.0.0 (locked, not in vendor/)"), "{}", err);
+        assert!(err.contains("files in vendor/mylib were modified"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn frozen_refuses_unlocked_git_dep() {
+        let dir = temp_dir();
+        fs::write(dir.join("ogham.mod.yaml"),
+            "module: github.com/test/proj\nversion: 0.1.0\nrequire:\n  github.com/org/lib:\n    git: https://example.invalid/lib.git\n    tag: v1.0.0\n",
+        ).unwrap();
+        fs::write(dir.join("ogham.lock.yaml"), "locked: {}\n").unwrap();
+
+        let err = resolve_deps_with(&dir, LockMode::Frozen).unwrap_err().to_string();
+        assert!(err.contains("not in ogham.lock.yaml"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_tag_accepts_versions_only() {
+        assert_eq!(parse_tag("v1.2.3"), SemVer::parse("1.2.3"));
+        assert_eq!(parse_tag("1.2"), SemVer::parse("1.2.0"));
+        assert_eq!(parse_tag("v2"), SemVer::parse("2.0.0"));
+        assert_eq!(parse_tag("v1.2.3-rc1"), None);
+        assert_eq!(parse_tag("release-1"), None);
+        assert_eq!(parse_tag("v1..2"), None);
+    }
+
+    /// Root requires dep-a and dep-b by path; each requires std at a different minimum.
+    fn std_diamond(a_range: &str, b_range: &str) -> PathBuf {
+        let dir = temp_dir();
+        for (name, range) in [("dep-a", a_range), ("dep-b", b_range)] {
+            let dep = dir.join("libs").join(name);
+            fs::create_dir_all(&dep).unwrap();
+            fs::write(dep.join("x.ogham"), "package x;\n").unwrap();
+            fs::write(dep.join("ogham.mod.yaml"), format!(
+                "module: {}\nversion: 1.0.0\nrequire:\n  github.com/oghamlang/std: {}\n", name, range,
+            )).unwrap();
+        }
+        fs::write(dir.join("ogham.mod.yaml"),
+            "module: github.com/test/root\nversion: 0.1.0\nrequire:\n  dep-a:\n    path: ./libs/dep-a\n  dep-b:\n    path: ./libs/dep-b\n",
+        ).unwrap();
+        dir
+    }
+
+    #[test]
+    fn mvs_selects_highest_minimum_across_graph() {
+        let dir = std_diamond("^0.1.0", "^0.1.4");
+
+        let resolution = resolve_graph(&dir, LockMode::Auto).unwrap();
+        let std = resolution
+            .selections
+            .iter()
+            .find(|s| s.module == "github.com/oghamlang/std")
+            .unwrap();
+        assert_eq!(std.version, "v0.1.4");
+        assert_eq!(std.requirements.len(), 2);
+        assert_eq!(std.requirements[0].by, "dep-a@local");
+        assert!(std.reason.starts_with("dep-b@local requires ^0.1.4"), "{}", std.reason);
+
+        let dep_a = &resolution.selections[0];
+        assert_eq!(dep_a.module, "dep-a");
+        assert_eq!(dep_a.reason, "github.com/test/root requires path ./libs/dep-a");
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn paths_to_lists_every_requiring_path() {
+        let dir = std_diamond("^0.1.0", "^0.1.4");
+
+        let resolution = resolve_graph(&dir, LockMode::Auto).unwrap();
+        let paths = resolution.paths_to("github.com/oghamlang/std");
+        let rendered: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                let mut out = path[0].from.clone();
+                for edge in path {
+                    out.push_str(&format!(" → {} ({})", edge.to, edge.requirement));
+                }
+                out
+            })
+            .collect();
+        assert_eq!(rendered, vec![
+            "github.com/test/root → dep-a (path ./libs/dep-a) → github.com/oghamlang/std (^0.1.0)",
+            "github.com/test/root → dep-b (path ./libs/dep-b) → github.com/oghamlang/std (^0.1.4)",
+        ]);
+        assert_eq!(resolution.edges().len(), 4);
+        assert!(resolution.paths_to("dep-c").is_empty());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn version_conflict_names_requirers() {
+        let dir = std_diamond("^0.1.0", "^1.0.0");
+
+        let err = resolve_deps(&dir).unwrap_err().to_string();
+        assert!(err.contains("version conflict for github.com/oghamlang/std"), "{}", err);
+        assert!(err.contains("dep-a@local requires ^0.1.0"), "{}", err);
+        assert!(err.contains("dep-b@local requires ^1.0.0"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn workspace_members_override_required_versions() {
+        let dir = temp_dir();
+        for (name, require) in [
+            ("common", "  github.com/oghamlang/std: ^0.1.4\n"),
+            ("shop", "  example.com/common: ^3.0.0\n  github.com/oghamlang/std: ^0.1.0\n"),
+        ] {
+            let member = dir.join(name);
+            fs::create_dir_all(&member).unwrap();
+            fs::write(member.join("x.ogham"), "package x;\n").unwrap();
+            fs::write(member.join("ogham.mod.yaml"), format!(
+                "module: example.com/{}\nversion: 1.0.0\nrequire:\n{}", name, require,
+            )).unwrap();
+        }
+        fs::write(dir.join("ogham.work.yaml"), "members:\n  - ./common\n  - ./shop\n").unwrap();
+
+        let ws = Workspace::load(&dir).unwrap();
+        let resolution = resolve_workspace(&ws, LockMode::Auto).unwrap();
+        let common = resolution.selections.iter().find(|s| s.module == "example.com/common").unwrap();
+        assert_eq!(common.version, "workspace");
+        assert_eq!(common.reason, "workspace member");
+        // Shared resolution: shop's ^0.1.0 is raised by common's ^0.1.4
+        let std = resolution.selections.iter().find(|s| s.module == "github.com/oghamlang/std").unwrap();
+        assert_eq!(std.version, "v0.1.4");
+
+        let shop_deps: Vec<String> = resolution.deps_of("example.com/shop").into_iter().map(|d| d.module).collect();
+        assert_eq!(shop_deps, vec!["github.com/oghamlang/std", "example.com/common"]);
+        let common_deps: Vec<String> = resolution.deps_of("example.com/common").into_iter().map(|d| d.module).collect();
+        assert_eq!(common_deps, vec!["github.com/oghamlang/std"]);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn guess_module_prefers_known_modules() {
+        let known = ["example.com/fresh", "github.com/org/mono/api", "github.com/org/mono"];
+        assert_eq!(guess_module("example.com/fresh/money", &known), "example.com/fresh");
+        assert_eq!(guess_module("github.com/org/mono/api/v1", &known), "github.com/org/mono/api");
+        assert_eq!(guess_module("github.com/org/lib/types", &known), "github.com/org/lib");
+        assert_eq!(guess_module("example.com/other/x", &known), "example.com/other/x");
+    }
+
+    #[test]
+    fn tidy_adds_imported_and_removes_unused_requires() {
+        let dir = temp_dir();
+        let write = |path: &str, content: &str| {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("ogham.work.yaml", "members:\n  - ./proj\n  - ./fresh\n");
+        write("fresh/ogham.mod.yaml", "module: example.com/fresh\n");
+        write("fresh/money.ogham", "package money;\n");
+        for lib in ["used", "trans", "unused", "plugin"] {
+            write(&format!("libs/{}/ogham.mod.yaml", lib), &format!("module: example.com/{}\n", lib));
+        }
+        write("libs/used/a.ogham", "package a;\nimport example.com/trans/b;\n");
+        write("libs/trans/b.ogham", "package b;\n");
+        write("libs/unused/c.ogham", "package c;\n");
+        write("proj/ogham.mod.yaml", "\
+module: example.com/proj
+require:
+  # libraries
+  example.com/used:
+    path: ../libs/used
+  example.com/trans:
+    path: ../libs/trans
+  example.com/unused:
+    path: ../libs/unused
+  example.com/plugin:
+    path: ../libs/plugin
+");
+        write("proj/ogham.gen.yaml", "generate:\n  plugins:\n    - name: example.com/plugin\n      out: gen/\n");
+        write("proj/x.ogham", "package proj;\nimport example.com/used/a;\nimport example.com/fresh/money;\n");
+
+        let tidy = tidy(&dir.join("proj")).unwrap();
+        assert_eq!(tidy.added.len(), 1);
+        assert_eq!(tidy.added[0].0, "example.com/fresh");
+        assert_eq!(tidy.removed, vec!["example.com/unused"]);
+
+        let content = fs::read_to_string(dir.join("proj/ogham.mod.yaml")).unwrap();
+        assert!(content.contains("  # libraries\n"), "{}", content);
+        let mod_file = manifest::load_mod_file(&dir.join("proj")).unwrap();
+        let mut required: Vec<&String> = mod_file.require.keys().collect();
+        required.sort();
+        assert_eq!(required, ["example.com/fresh", "example.com/plugin", "example.com/trans", "example.com/used"]);
+
+        // The workspace holds the lock; the new member dependency is in it
+        let lock = manifest::load_lock_file(&dir).unwrap().unwrap();
+        assert_eq!(lock.locked["example.com/fresh"].version, "workspace");
+        assert!(!lock.locked.contains_key("example.com/unused"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn git(args: &[&str], dir: &Path) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(status.status.success(), "git {:?}: {}", args, String::from_utf8_lossy(&status.stderr));
+    }
+
+    #[test]
+    fn mvs_picks_lowest_matching_git_tag() {
+        let dir = temp_dir();
+        let repo = dir.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        git(&["init", "--quiet"], &repo);
+        for tag in ["v1.0.0", "v1.1.0", "v1.2.0", "v2.0.0"] {
+            fs::write(repo.join("lib.ogham"), format!("package lib; // {}\n", tag)).unwrap();
+            git(&["add", "."], &repo);
+            git(&["commit", "--quiet", "-m", tag], &repo);
+            git(&["tag", tag], &repo);
+        }
+        let url = format!("file://{}", repo.display());
+        let module = format!("example.test/{}", dir.file_name().unwrap().to_string_lossy());
+
+        let dep_a = dir.join("dep-a");
+        fs::create_dir_all(&dep_a).unwrap();
+        fs::write(dep_a.join("ogham.mod.yaml"), format!(
+            "module: dep-a\nversion: 1.0.0\nrequire:\n  {}: ^1.1.0\n", module,
+        )).unwrap();
+        fs::write(dir.join("ogham.mod.yaml"), format!(
+            "module: github.com/test/root\nversion: 0.1.0\nrequire:\n  dep-a:\n    path: ./dep-a\n  {}:\n    git: {}\n    tag: v1.0.0\n",
+            module, url,
+        )).unwrap();
+
+        let resolution = resolve_graph_in(&dir, LockMode::Auto, &dir.join("home")).unwrap();
+        let lib = resolution.deps.iter().find(|d| d.module == module).unwrap();
+        assert_eq!(lib.version, "v1.1.0");
+        assert!(lib.path.starts_with(dir.join("home")));
+        assert!(fs::read_to_string(lib.path.join("lib.ogham")).unwrap().contains("v1.1.0"));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn branch_dep_is_pinned_until_updated() {
+        let dir = temp_dir();
+        let repo = dir.join("repo");
+        fs::create_dir_all(&repo).unwrap();
+        git(&["init", "--quiet", "--initial-branch", "main"], &repo);
+        let commit = |message: &str| {
+            fs::write(repo.join("lib.ogham"), format!("package lib; // {}\n", message)).unwrap();
+            git(&["add", "."], &repo);
+            git(&["commit", "--quiet", "-m", message], &repo);
+            head_commit(&repo).unwrap()
+        };
+        let first = commit("first");
+        let url = format!("file://{}", repo.display());
+        let home = dir.join("home");
+        let module = format!("example.test/{}", dir.file_name().unwrap().to_string_lossy());
+        fs::write(dir.join("ogham.mod.yaml"), format!(
+            "module: github.com/test/root\nversion: 0.1.0\nrequire:\n  {}:\n    git: {}\n    branch: main\n",
+            module, url,
+        )).unwrap();
+
+        let deps = resolve_graph_in(&dir, LockMode::Auto, &home).unwrap().deps;
+        assert_eq!(deps[0].path, git_cache_dir(&home, &module, &first));
+        write_lock_file(&dir, &deps).unwrap();
+
+        // The lock keeps the old commit after the branch moves
+        let second = commit("second");
+        let locked = resolve_graph_in(&dir, LockMode::Auto, &home).unwrap().deps;
+        assert_eq!(locked[0].path, git_cache_dir(&home, &module, &first));
+
+        let err = update_in(&dir, &["example.test/other".to_string()], &home).unwrap_err();
+        assert!(err.contains("is not a dependency"), "{}", err);
+
+        let update = update_in(&dir, std::slice::from_ref(&module), &home).unwrap();
+        assert_eq!(update.deps[0].path, git_cache_dir(&home, &module, &second));
+        assert_eq!(update.changes, vec![Change {
+            module: module.clone(),
+            old: Some(format!("main @ {}", &first[..12])),
+            new: Some(format!("main @ {}", &second[..12])),
+        }]);
+
+        // Offline, the branch falls back to the checkout it was last seen at
+        fs::remove_file(dir.join("ogham.lock.yaml")).unwrap();
+        fs::rename(&repo, dir.join("gone")).unwrap();
+        let offline = resolve_graph_in(&dir, LockMode::Auto, &home).unwrap().deps;
+        assert_eq!(offline[0].path, git_cache_dir(&home, &module, &second));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn unreachable_remote_falls_back_to_remembered_checkout() {
+        let dir = temp_dir();
+        let ctx = ResolveCtx { home: dir.join("home"), ..Default::default() };
+        let module = "example.test/offline";
+        let url = format!("file://{}", dir.join("gone").display());
+        let commit = "0123456789abcdef0123456789abcdef01234567";
+        let checkout = git_cache_dir(&ctx.home, module, commit);
+        fs::create_dir_all(&checkout).unwrap();
+        remember_ref(&ctx.home, module, &url, "tags/v1.0.0", commit);
+
+        let dep = resolve_git(module, &url, Some("v1.0.0"), None, None, &ctx).unwrap();
+        assert_eq!(dep.path, checkout);
+        assert_eq!(dep.version, "v1.0.0");
+
+        // Recorded for another remote: not trusted
+        let other = format!("file://{}", dir.join("other").display());
+        let err = resolve_git(module, &other, Some("v1.0.0"), None, None, &ctx).unwrap_err();
+        assert!(err.contains("cannot resolve tag v1.0.0"), "{}", err);
+
+        // The checkout was pruned: the lookup error stands
+        fs::remove_dir_all(&checkout).unwrap();
+        let err = resolve_git(module, &url, Some("v1.0.0"), None, None, &ctx).unwrap_err();
+        assert!(err.contains("cannot resolve tag v1.0.0"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn version_dep_fetched_through_proxy_chain() {
+        use crate::proxy::tests::{module_routes, serve};
+
+        let dir = temp_dir();
+        let module = format!("example.test/{}", dir.file_name().unwrap().to_string_lossy());
+        let src = dir.join("published");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("lib.ogham"), "package lib;\n").unwrap();
+
+        let mut routes = module_routes(&src, &module, "v1.1.0");
+        routes.insert(format!("/{}/@v/list", module), b"v1.0.0\nv1.1.0\nv2.0.0\n".to_vec());
+        let empty = serve(std::collections::HashMap::new());
+        let full = serve(routes);
+
+        fs::write(dir.join("ogham.mod.yaml"), format!(
+            "module: github.com/test/proj\nversion: 0.1.0\nrequire:\n  {}: ^1.0.1\n", module,
+        )).unwrap();
+
+        // The first proxy doesn't have the module and passes it on
+        let home = dir.join("home");
+        let ctx = ResolveCtx {
+            proxies: vec![ProxyEntry::Url(empty), ProxyEntry::Url(full), ProxyEntry::Direct],
+            home: home.clone(),
+            ..Default::default()
+        };
+        let resolution = resolve_with(&dir, &ctx).unwrap();
+        let dep = &resolution.deps[0];
+        assert_eq!(dep.version, "v1.1.0");
+        assert!(matches!(dep.source, DepSource::Cache));
+        assert_eq!(dep.path, pkg_cache_dir(&home, &module, "v1.1.0"));
+        assert!(dep.path.join("lib.ogham").exists());
+
+        // Without a proxy the version can only come from the cache
+        let _ = fs::remove_dir_all(&dep.path);
+        let ctx = ResolveCtx {
+            home,
+            ..Default::default()
+        };
+        let err = resolve_with(&dir, &ctx).unwrap_err().to_string();
+        assert!(err.contains("not available from OGHAM_PROXY"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn publishable_rejects_local_only_requirements() {
+        let dir = temp_dir();
+        fs::write(dir.join("ogham.mod.yaml"),
+            "module: github.com/test/proj\nversion: next\nrequire:\n  mylib:\n    path: ./libs/mylib\n  github.com/org/lib: ^1.0.0\nreplace:\n  github.com/org/lib:\n    path: ../fork\n",
+        ).unwrap();
+        let mod_file = manifest::load_mod_file(&dir).unwrap();
+
+        let err = validate_publishable(&mod_file).unwrap_err();
+        assert!(err.contains("'version' must be a semver version"), "{}", err);
+        assert!(err.contains("mylib is a path dependency"), "{}", err);
+        assert!(err.contains("github.com/org/lib is replaced"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn pack_is_deterministic() {
+        let dir = temp_dir();
+        fs::create_dir_all(dir.join("vendor").join("dep")).unwrap();
+        fs::write(dir.join("vendor").join("dep").join("dep.ogham"), "package dep;\n").unwrap();
+        fs::write(dir.join("user.ogham"), "package shop;\ntype User { string id = 1; }\n").unwrap();
+        fs::write(dir.join("ogham.mod.yaml"),
+            "module: github.com/test/shop\nversion: 1.2.0\nrequire:\n  github.com/oghamlang/std: ^0.1.0\n",
+        ).unwrap();
+
+        let first = pack(&dir, &[]).unwrap();
+        let second = pack(&dir, &[]).unwrap();
+        assert_eq!(first.version, "v1.2.0");
+        assert_eq!(first.zip, second.zip);
+        assert_eq!(first.info, second.info);
+
+        // vendor/ is not shipped, and the hash is that of the shipped tree
+        fs::remove_dir_all(dir.join("vendor")).unwrap();
+        assert_eq!(first.info.hash, Some(hash_dir(&dir).unwrap()));
+
+        let out = first.write_to(&dir.join("dist")).unwrap();
+        assert!(out.join("v1.2.0.zip").exists());
+        assert!(out.join("v1.2.0.info").exists());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn hash_dir_covers_every_file_but_hidden_ones() {
+        let dir = temp_dir();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join("a.ogham"), "package a;\n").unwrap();
+        fs::write(dir.join("sub").join("b.ogham"), "package b;\n").unwrap();
+        fs::write(dir.join("ogham.mod.yaml"), "module: x\n").unwrap();
+
+        let hash = hash_dir(&dir).unwrap();
+        assert!(hash.starts_with("h1:"));
+        assert_eq!(hash, hash_dir(&dir).unwrap());
+
+        // Hidden files and .git don't count
+        fs::write(dir.join(".DS_Store"), "x").unwrap();
+        fs::write(dir.join(".git").join("HEAD"), "ref").unwrap();
+        assert_eq!(hash, hash_dir(&dir).unwrap());
+
+        fs::write(dir.join("sub").join("b.ogham"), "package b;\ntype X {}\n").unwrap();
+        let changed = hash_dir(&dir).unwrap();
+        assert_ne!(hash, changed);
+
+        // Every other file does: a plugin builds from them
+        fs::write(dir.join("build.sh"), "echo hi").unwrap();
+        assert_ne!(changed, hash_dir(&dir).unwrap());
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn tampered_dependency_is_rejected() {
+        let dir = temp_dir();
+        fs::write(dir.join("lib.ogham"), "package lib;\n").unwrap();
+        let deps = vec![ResolvedDep {
+            module: "github.com/org/lib".to_string(),
+            version: "v1.0.0".to_string(),
+            path: dir.clone(),
+            source: DepSource::Git,
+        }];
+
+        let mut lock = manifest::LockFile::default();
+        lock.locked.insert(
+            "github.com/org/lib".to_string(),
+            manifest::LockedDep {
+                version: "v1.0.0".to_string(),
+                commit: None,
+                hash: Some(hash_dir(&dir).unwrap()),
+                source: "git".to_string(),
+            },
+        );
+        assert!(verify_hashes(&deps, &lock).is_ok());
+
+        fs::write(dir.join("lib.ogham"), "package lib;\ntype Evil {}\n").unwrap();
+        let err = verify_hashes(&deps, &lock).unwrap_err();
+        assert!(matches!(err, ResolveError::ChecksumMismatch(_)), "{}", err);
+        assert!(err.to_string().contains("checksum mismatch for github.com/org/lib"), "{}", err);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
 }
@@ -0,0 +1,251 @@
+//! Building and installing generator plugins from source.
+//!
+//! A plugin module declares a `plugin.build` command in its `ogham.mod.yaml`.
+//! Installing runs that command in a scratch copy of the module and moves the
+//! resulting `ogham-gen-<name>` binary into `$OGHAM_BIN` as
+//! `ogham-gen-<name>@<version>`, so several versions can live side by side.
+//! A build may also, or instead, produce a WebAssembly module
+//! `ogham-gen-<name>.wasm`, installed as `ogham-gen-<name>@<version>.wasm`.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cache;
+use crate::manifest;
+use crate::pkg::{self, DepSource, ResolvedDep};
+
+/// Directory installed plugin binaries live in: `$OGHAM_BIN`, by default
+/// `$OGHAM_HOME/bin`.
+pub fn bin_dir() -> PathBuf {
+    match std::env::var("OGHAM_BIN") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => pkg::ogham_home().join("bin"),
+    }
+}
+
+/// Name of the binary a plugin module builds: `ogham-gen-<last path segment>`.
+pub fn binary_name(module: &str) -> String {
+    let short = module.rsplit('/').next().unwrap_or(module);
+    if short.starts_with("ogham-gen-") {
+        short.to_string()
+    } else {
+        format!("ogham-gen-{}", short)
+    }
+}
+
+/// Version an installed binary is named after: the version for released
+/// modules, the commit for git branches and revisions, `local-<hash>` of the
+/// sources for path dependencies, so an edited path plugin is rebuilt.
+pub fn version_label(dep: &ResolvedDep) -> String {
+    match dep.source {
+        DepSource::Path => match pkg::hash_dir(&dep.path) {
+            Ok(hash) => format!("local-{}", &hash.trim_start_matches("h1:")[..12]),
+            Err(_) => "local".to_string(),
+        },
+        DepSource::Git if pkg::parse_tag(&dep.version).is_none() => {
+            let commit = dep.path.file_name().unwrap_or_default().to_string_lossy();
+            pkg::short_commit(&commit).to_string()
+        }
+        _ => dep.version.clone(),
+    }
+}
+
+/// Where the binary for `dep` is installed in `bin`.
+pub fn installed_path(bin: &Path, dep: &ResolvedDep) -> PathBuf {
+    bin.join(format!(
+        "{}@{}{}",
+        binary_name(&dep.module),
+        version_label(dep),
+        std::env::consts::EXE_SUFFIX
+    ))
+}
+
+/// Where the WebAssembly module for `dep` is installed in `bin`.
+pub fn installed_wasm_path(bin: &Path, dep: &ResolvedDep) -> PathBuf {
+    bin.join(format!("{}@{}.wasm", binary_name(&dep.module), version_label(dep)))
+}
+
+/// Whether a fetched module is a plugin, i.e. declares `plugin.build`.
+pub fn is_plugin(dep: &ResolvedDep) -> bool {
+    manifest::load_mod_file(&dep.path)
+        .map(|m| m.plugin.is_some())
+        .unwrap_or(false)
+}
+
+/// Build the plugin in `dep` and install its binary and WebAssembly module,
+/// whichever the build produces, into `bin`, replacing an earlier install of
+/// the same version. Returns the installed files.
+pub fn install(bin: &Path, dep: &ResolvedDep) -> Result<Vec<PathBuf>, String> {
+    let mod_file = manifest::load_mod_file(&dep.path)?;
+    let build = mod_file
+        .plugin
+        .map(|p| p.build)
+        .ok_or_else(|| format!("{} is not a plugin: no `plugin.build` in its ogham.mod.yaml", dep.module))?;
+
+    let target = installed_path(bin, dep);
+    let _lock = cache::lock(&target)?;
+
+    let scratch = cache::partial_dir(&target);
+    let _ = std::fs::remove_dir_all(&scratch);
+    let result = build_in(&scratch, dep, &build, bin);
+    let _ = std::fs::remove_dir_all(&scratch);
+    result
+}
+
+/// The installed binary for `dep`, building it first if it is a plugin that
+/// is not installed yet. `None` if `dep` is not a plugin.
+pub fn ensure_installed(bin: &Path, dep: &ResolvedDep) -> Result<Option<PathBuf>, String> {
+    ensure(bin, dep, installed_path(bin, dep))
+}
+
+/// The WebAssembly module for `dep`: installed, shipped prebuilt at the root
+/// of the module, or built first. `None` if `dep` is not a plugin.
+pub fn ensure_installed_wasm(bin: &Path, dep: &ResolvedDep) -> Result<Option<PathBuf>, String> {
+    let shipped = dep.path.join(format!("{}.wasm", binary_name(&dep.module)));
+    let target = installed_wasm_path(bin, dep);
+    if !target.is_file() && shipped.is_file() {
+        return Ok(Some(shipped));
+    }
+    ensure(bin, dep, target)
+}
+
+fn ensure(bin: &Path, dep: &ResolvedDep, target: PathBuf) -> Result<Option<PathBuf>, String> {
+    if target.is_file() {
+        return Ok(Some(target));
+    }
+    if !is_plugin(dep) {
+        return Ok(None);
+    }
+    eprintln!("building plugin {} {}", dep.module, version_label(dep));
+    install(bin, dep)?;
+    if !target.is_file() {
+        return Err(format!(
+            "build of {} did not produce {}",
+            dep.module,
+            target.file_name().unwrap_or_default().to_string_lossy()
+        ));
+    }
+    Ok(Some(target))
+}
+
+fn build_in(scratch: &Path, dep: &ResolvedDep, build: &str, bin: &Path) -> Result<Vec<PathBuf>, String> {
+    pkg::copy_dir(&dep.path, scratch)?;
+
+    let mut command = if cfg!(windows) {
+        let mut c = Command::new("cmd");
+        c.arg("/C").arg(build);
+        c
+    } else {
+        let mut c = Command::new("sh");
+        c.arg("-c").arg(build);
+        c
+    };
+    // Build output goes to stderr: stdout belongs to the command's own output.
+    let status = command
+        .current_dir(scratch)
+        .stdout(std::io::stderr())
+        .status()
+        .map_err(|e| format!("cannot run build of {}: {}", dep.module, e))?;
+    if !status.success() {
+        return Err(format!("build of {} failed ({}): {}", dep.module, status, build));
+    }
+
+    let name = binary_name(&dep.module);
+    let artifacts = [
+        (scratch.join(format!("{}{}", name, std::env::consts::EXE_SUFFIX)), installed_path(bin, dep)),
+        (scratch.join(format!("{}.wasm", name)), installed_wasm_path(bin, dep)),
+    ];
+    let mut installed = Vec::new();
+    for (built, target) in artifacts.into_iter().filter(|(built, _)| built.is_file()) {
+        // The scratch directory sits next to the target, so this is a rename
+        // within one directory and an existing install is replaced atomically.
+        std::fs::rename(&built, &target)
+            .map_err(|e| format!("cannot install {}: {}", target.display(), e))?;
+        installed.push(target);
+    }
+    if installed.is_empty() {
+        return Err(format!("build of {} produced neither {} nor {}.wasm", dep.module, name, name));
+    }
+    Ok(installed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn dep(module: &str, version: &str, path: PathBuf, source: DepSource) -> ResolvedDep {
+        ResolvedDep { module: module.to_string(), version: version.to_string(), path, source }
+    }
+
+    #[test]
+    fn names_binaries_after_module_and_version() {
+        assert_eq!(binary_name("github.com/org/go"), "ogham-gen-go");
+        assert_eq!(binary_name("github.com/org/ogham-gen-go"), "ogham-gen-go");
+
+        let commit = PathBuf::from("/cache/git/checkouts/github.com/org/go/0123456789abcdef0123456789abcdef01234567");
+        assert_eq!(version_label(&dep("github.com/org/go", "v1.2.0", commit.clone(), DepSource::Git)), "v1.2.0");
+        assert_eq!(version_label(&dep("github.com/org/go", "main", commit, DepSource::Git)), "0123456789ab");
+        assert_eq!(version_label(&dep("github.com/org/go", "local", PathBuf::from("../go"), DepSource::Path)), "local");
+    }
+
+    #[test]
+    fn path_plugins_are_named_after_their_sources() {
+        let dir = std::env::temp_dir().join(format!("ogham-plugin-label-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}\n").unwrap();
+        let plugin = dep("example.test/gen", "local", dir.clone(), DepSource::Path);
+
+        let label = version_label(&plugin);
+        assert!(label.starts_with("local-") && label.len() == "local-".len() + 12, "{}", label);
+        assert_eq!(version_label(&plugin), label);
+
+        fs::write(dir.join("main.rs"), "fn main() { println!(); }\n").unwrap();
+        assert_ne!(version_label(&plugin), label, "an edit must change the label");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn install_runs_build_in_scratch_copy() {
+        let dir = std::env::temp_dir().join(format!("ogham-plugin-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let module = dir.join("gen");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(
+            module.join("ogham.mod.yaml"),
+            "module: example.test/gen\nplugin:\n  build: printf '#!/bin/sh\\n' > ogham-gen-gen && chmod +x ogham-gen-gen\n",
+        )
+        .unwrap();
+        let bin = dir.join("bin");
+
+        let plugin = dep("example.test/gen", "v0.1.0", module.clone(), DepSource::Cache);
+        let installed = ensure_installed(&bin, &plugin).unwrap().unwrap();
+        assert_eq!(installed, bin.join("ogham-gen-gen@v0.1.0"));
+        assert!(installed.is_file());
+        assert!(!module.join("ogham-gen-gen").exists(), "build must not touch the module");
+
+        let other = dir.join("lib");
+        fs::create_dir_all(&other).unwrap();
+        fs::write(other.join("ogham.mod.yaml"), "module: example.test/lib\n").unwrap();
+        let lib = dep("example.test/lib", "v0.1.0", other, DepSource::Cache);
+        assert_eq!(ensure_installed(&bin, &lib).unwrap(), None);
+        assert!(install(&bin, &lib).unwrap_err().contains("not a plugin"));
+
+        let wasm = dir.join("wasm");
+        fs::create_dir_all(&wasm).unwrap();
+        fs::write(
+            wasm.join("ogham.mod.yaml"),
+            "module: example.test/wasm\nplugin:\n  build: printf '\\0asm' > ogham-gen-wasm.wasm\n",
+        )
+        .unwrap();
+        let wasm = dep("example.test/wasm", "v0.1.0", wasm, DepSource::Cache);
+        let installed = ensure_installed_wasm(&bin, &wasm).unwrap().unwrap();
+        assert_eq!(installed, bin.join("ogham-gen-wasm@v0.1.0.wasm"));
+        assert!(ensure_installed(&bin, &wasm).unwrap_err().contains("did not produce ogham-gen-wasm@v0.1.0"));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
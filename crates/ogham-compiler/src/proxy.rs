@@ -0,0 +1,437 @@
+//! Module proxy client — the HTTP protocol from docs/adr/package.md.
+//!
+//! A proxy serves, for module `github.com/org/db`:
+//!
+//! - `GET {base}/github.com/org/db/@v/list` — available versions, one per line
+//! - `GET {base}/github.com/org/db/@v/v1.2.0.info` — [`VersionInfo`] as JSON
+//! - `GET {base}/github.com/org/db/@v/v1.2.0.mod` — the module's `ogham.mod.yaml`
+//! - `GET {base}/github.com/org/db/@v/v1.2.0.zip` — source archive, every
+//!   entry under `github.com/org/db@v1.2.0/`
+//!
+//! `OGHAM_PROXY` is a comma-separated chain of proxy URLs and `direct`.
+//! A proxy answering 404 or 410 passes the request to the next entry;
+//! any other failure stops the chain.
+
+use crate::pkg::{self, ResolveError};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::{Component, Path};
+use std::time::Duration;
+
+/// Largest response body accepted from a proxy.
+const MAX_DOWNLOAD: u64 = 256 * 1024 * 1024;
+
+/// One entry of the `OGHAM_PROXY` chain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProxyEntry {
+    /// Proxy base URL, without trailing slash.
+    Url(String),
+    /// Fetch from the module's source (git) directly.
+    Direct,
+}
+
+/// `.info` metadata of a module version.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    /// Commit time, RFC 3339.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    /// `pkg::hash_dir` of the extracted archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+/// Parse an `OGHAM_PROXY` value. Empty entries are ignored; an empty chain
+/// means `direct`.
+pub fn parse_chain(value: &str) -> Vec<ProxyEntry> {
+    let chain: Vec<ProxyEntry> = value
+        .split(',')
+        .map(str::trim)
+        .filter(|e| !e.is_empty())
+        .map(|e| {
+            if e == "direct" {
+                ProxyEntry::Direct
+            } else {
+                ProxyEntry::Url(e.trim_end_matches('/').to_string())
+            }
+        })
+        .collect();
+    if chain.is_empty() {
+        vec![ProxyEntry::Direct]
+    } else {
+        chain
+    }
+}
+
+/// The proxy chain from `OGHAM_PROXY` (default: `direct`).
+pub fn chain_from_env() -> Vec<ProxyEntry> {
+    parse_chain(&std::env::var("OGHAM_PROXY").unwrap_or_default())
+}
+
+/// Versions of `module` known to the proxy, or `None` if it doesn't have
+/// the module.
+pub fn list(base: &str, module: &str) -> Result<Option<Vec<String>>, String> {
+    let Some(body) = get(&format!("{}/{}/@v/list", base, module))? else {
+        return Ok(None);
+    };
+    Ok(Some(
+        String::from_utf8_lossy(&body)
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(str::to_string)
+            .collect(),
+    ))
+}
+
+/// `.info` of a module version.
+pub fn info(base: &str, module: &str, version: &str) -> Result<Option<VersionInfo>, String> {
+    let url = format!("{}/{}/@v/{}.info", base, module, version);
+    let Some(body) = get(&url)? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|e| format!("invalid response from {}: {}", url, e))
+}
+
+/// `ogham.mod.yaml` of a module version.
+pub fn mod_file(base: &str, module: &str, version: &str) -> Result<Option<String>, String> {
+    let body = get(&format!("{}/{}/@v/{}.mod", base, module, version))?;
+    Ok(body.map(|b| String::from_utf8_lossy(&b).into_owned()))
+}
+
+/// Download a module version and extract it into `dest`.
+///
+/// The extracted tree is checked against the hash in `.info` and against
+/// `expected_hash` (from ogham.lock.yaml) before it is moved into place.
+/// Returns `false` if the proxy doesn't have the version.
+pub fn download(
+    base: &str,
+    module: &str,
+    version: &str,
+    dest: &Path,
+    expected_hash: Option<&str>,
+) -> Result<bool, ResolveError> {
+    let Some(info) = info(base, module, version)? else {
+        return Ok(false);
+    };
+    let url = format!("{}/{}/@v/{}.zip", base, module, version);
+    let Some(zip) = get(&url)? else {
+        return Ok(false);
+    };
+
+    eprintln!("  downloaded {} {} from {}", module, version, base);
+
+    let partial = crate::cache::partial_dir(dest);
+    let _ = std::fs::remove_dir_all(&partial);
+    let result = extract_zip(&zip, &zip_prefix(module, version), &partial)
+        .and_then(|_| crate::pkg::hash_dir(&partial))
+        .map_err(ResolveError::from)
+        .and_then(|actual| {
+            for expected in [info.hash.as_deref(), expected_hash].into_iter().flatten() {
+                if actual != expected {
+                    return Err(ResolveError::ChecksumMismatch(format!(
+                        "checksum mismatch for {} {} from {}:\n  expected: {}\n  got:      {}",
+                        module, version, base, expected, actual
+                    )));
+                }
+            }
+            crate::cache::record_hash(&partial, &actual).map_err(ResolveError::from)
+        });
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("cannot create {}: {}", parent.display(), e))?;
+    }
+    if std::fs::rename(&partial, dest).is_err() {
+        // Another process extracted it first
+        let _ = std::fs::remove_dir_all(&partial);
+        if !dest.is_dir() {
+            return Err(format!("cannot move {} into {}", module, dest.display()).into());
+        }
+    }
+    Ok(true)
+}
+
+/// Directory prefix of every entry in a module zip: `module@version/`.
+pub fn zip_prefix(module: &str, version: &str) -> String {
+    format!("{}@{}/", module, version)
+}
+
+/// Build a module zip from `dir`: every file outside hidden directories and
+/// `exclude`, sorted by path, with fixed timestamps — the same tree always
+/// gives the same bytes.
+pub fn pack_zip(dir: &Path, module: &str, version: &str, exclude: &[&Path]) -> Result<Vec<u8>, String> {
+    let files = pkg::module_files(dir, exclude)?;
+
+    let prefix = zip_prefix(module, version);
+    let options = zip::write::FileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644);
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (rel, path) in &files {
+        let content = std::fs::read(path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        writer
+            .start_file(format!("{}{}", prefix, rel), options)
+            .and_then(|_| writer.write_all(&content).map_err(Into::into))
+            .map_err(|e| format!("cannot add {} to zip: {}", rel, e))?;
+    }
+    let cursor = writer
+        .finish()
+        .map_err(|e| format!("cannot write zip: {}", e))?;
+    Ok(cursor.into_inner())
+}
+
+/// Extract a module zip into `dest`. Every entry must live under `prefix`
+/// and stay inside it — absolute paths and `..` are rejected.
+pub fn extract_zip(bytes: &[u8], prefix: &str, dest: &Path) -> Result<(), String> {
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))
+        .map_err(|e| format!("invalid zip: {}", e))?;
+
+    std::fs::create_dir_all(dest)
+        .map_err(|e| format!("cannot create {}: {}", dest.display(), e))?;
+
+    for i in 0..archive.len() {
+        let mut file = archive
+            .by_index(i)
+            .map_err(|e| format!("invalid zip: {}", e))?;
+        let name = file.name().to_string();
+        let Some(rel) = name.strip_prefix(prefix) else {
+            return Err(format!("zip entry {} is outside {}", name, prefix));
+        };
+        let rel = Path::new(rel);
+        if rel.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(format!("zip entry {} escapes the module directory", name));
+        }
+        if rel.as_os_str().is_empty() {
+            continue;
+        }
+
+        let target = dest.join(rel);
+        if file.is_dir() {
+            std::fs::create_dir_all(&target)
+                .map_err(|e| format!("cannot create {}: {}", target.display(), e))?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("cannot create {}: {}", parent.display(), e))?;
+        }
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)
+            .map_err(|e| format!("cannot extract {}: {}", name, e))?;
+        std::fs::write(&target, content)
+            .map_err(|e| format!("cannot write {}: {}", target.display(), e))?;
+    }
+
+    Ok(())
+}
+
+/// Upload a module version to a proxy that accepts publishing: the zip
+/// and `.mod` first, `.info` last — the proxy serves the version once its
+/// `.info` is in place.
+pub fn upload(
+    base: &str,
+    module: &str,
+    version: &str,
+    zip: &[u8],
+    mod_file: &[u8],
+    info: &VersionInfo,
+) -> Result<(), String> {
+    let info = serde_json::to_vec_pretty(info)
+        .map_err(|e| format!("cannot serialize info: {}", e))?;
+    for (ext, body) in [("zip", zip), ("mod", mod_file), ("info", info.as_slice())] {
+        put(&format!("{}/{}/@v/{}.{}", base, module, version, ext), body)?;
+    }
+    Ok(())
+}
+
+// ── Helpers ────────────────────────────────────────────────────────────
+
+fn put(url: &str, body: &[u8]) -> Result<(), String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(120))
+        .build();
+    match agent.put(url).send_bytes(body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            let message = response.into_string().unwrap_or_default();
+            Err(format!("{}: HTTP {} {}", url, code, message.trim()))
+        }
+        Err(e) => Err(format!("{}: {}", url, e)),
+    }
+}
+
+/// GET a URL. `None` for 404 and 410 — the proxy doesn't have it.
+fn get(url: &str) -> Result<Option<Vec<u8>>, String> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(120))
+        .build();
+    match agent.get(url).call() {
+        Ok(response) => {
+            let mut body = Vec::new();
+            response
+                .into_reader()
+                .take(MAX_DOWNLOAD)
+                .read_to_end(&mut body)
+                .map_err(|e| format!("{}: {}", url, e))?;
+            Ok(Some(body))
+        }
+        Err(ureq::Error::Status(404 | 410, _)) => Ok(None),
+        Err(ureq::Error::Status(code, _)) => Err(format!("{}: HTTP {}", url, code)),
+        Err(e) => Err(format!("{}: {}", url, e)),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::fs;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+    fn temp_dir() -> PathBuf {
+        let n = COUNTER.fetch_add(1, Ordering::SeqCst);
+        let dir = std::env::temp_dir().join(format!("ogham-proxy-{}-{}", std::process::id(), n));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// Minimal HTTP stand-in for a proxy: serves `routes` (path → body),
+    /// 404 for anything else. Returns the base URL.
+    pub(crate) fn serve(routes: HashMap<String, Vec<u8>>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut request_line = String::new();
+                if reader.read_line(&mut request_line).is_err() {
+                    continue;
+                }
+                // Skip headers
+                let mut line = String::new();
+                while reader.read_line(&mut line).map(|n| n > 2).unwrap_or(false) {
+                    line.clear();
+                }
+                let path = request_line.split_whitespace().nth(1).unwrap_or("/");
+                let (status, body) = match routes.get(path) {
+                    Some(body) => ("200 OK", body.clone()),
+                    None => ("404 Not Found", b"not found".to_vec()),
+                };
+                let mut stream = &stream;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    status,
+                    body.len()
+                );
+                let _ = stream.write_all(&body);
+            }
+        });
+        base
+    }
+
+    /// Routes serving `dir` as `module@version`.
+    pub(crate) fn module_routes(dir: &Path, module: &str, version: &str) -> HashMap<String, Vec<u8>> {
+        let info = VersionInfo {
+            version: version.to_string(),
+            time: None,
+            hash: Some(crate::pkg::hash_dir(dir).unwrap()),
+        };
+        let at = format!("/{}/@v/", module);
+        HashMap::from([
+            (format!("{}list", at), format!("{}\n", version).into_bytes()),
+            (format!("{}{}.info", at, version), serde_json::to_vec(&info).unwrap()),
+            (
+                format!("{}{}.mod", at, version),
+                fs::read(dir.join("ogham.mod.yaml")).unwrap_or_default(),
+            ),
+            (format!("{}{}.zip", at, version), pack_zip(dir, module, version, &[]).unwrap()),
+        ])
+    }
+
+    fn sample_module() -> PathBuf {
+        let dir = temp_dir();
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("ogham.mod.yaml"), "module: example.test/lib\nversion: 1.0.0\n").unwrap();
+        fs::write(dir.join("lib.ogham"), "package lib;\n").unwrap();
+        fs::write(dir.join("sub").join("more.ogham"), "package sub;\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_proxy_chain() {
+        assert_eq!(parse_chain(""), vec![ProxyEntry::Direct]);
+        assert_eq!(
+            parse_chain("https://internal.proxy/, https://proxy.ogham.dev,direct"),
+            vec![
+                ProxyEntry::Url("https://internal.proxy".into()),
+                ProxyEntry::Url("https://proxy.ogham.dev".into()),
+                ProxyEntry::Direct,
+            ]
+        );
+    }
+
+    #[test]
+    fn pack_zip_is_deterministic_and_round_trips() {
+        let src = sample_module();
+        let zip = pack_zip(&src, "example.test/lib", "v1.0.0", &[]).unwrap();
+        assert_eq!(zip, pack_zip(&src, "example.test/lib", "v1.0.0", &[]).unwrap());
+
+        let out = temp_dir().join("lib");
+        extract_zip(&zip, &zip_prefix("example.test/lib", "v1.0.0"), &out).unwrap();
+        assert_eq!(fs::read_to_string(out.join("sub").join("more.ogham")).unwrap(), "package sub;\n");
+        assert_eq!(crate::pkg::hash_dir(&src).unwrap(), crate::pkg::hash_dir(&out).unwrap());
+
+        // Wrong prefix is rejected
+        let err = extract_zip(&zip, "example.test/other@v1.0.0/", &temp_dir()).unwrap_err();
+        assert!(err.contains("outside"), "{}", err);
+
+        let _ = fs::remove_dir_all(&src);
+    }
+
+    #[test]
+    fn download_from_stand_in() {
+        let src = sample_module();
+        let base = serve(module_routes(&src, "example.test/lib", "v1.0.0"));
+
+        assert_eq!(list(&base, "example.test/lib").unwrap(), Some(vec!["v1.0.0".to_string()]));
+        assert_eq!(list(&base, "example.test/missing").unwrap(), None);
+        assert!(mod_file(&base, "example.test/lib", "v1.0.0").unwrap().unwrap().contains("example.test/lib"));
+
+        let dest = temp_dir().join("lib@v1.0.0");
+        assert!(download(&base, "example.test/lib", "v1.0.0", &dest, None).unwrap());
+        assert!(dest.join("lib.ogham").exists());
+        assert!(!download(&base, "example.test/lib", "v9.9.9", &temp_dir().join("x"), None).unwrap());
+
+        let _ = fs::remove_dir_all(&src);
+    }
+
+    #[test]
+    fn download_rejects_checksum_mismatch() {
+        let src = sample_module();
+        let base = serve(module_routes(&src, "example.test/lib", "v1.0.0"));
+
+        let dest = temp_dir().join("lib@v1.0.0");
+        let err = download(&base, "example.test/lib", "v1.0.0", &dest, Some("h1:0000")).unwrap_err();
+        assert!(matches!(err, ResolveError::ChecksumMismatch(_)), "{}", err);
+        assert!(!dest.exists());
+
+        let _ = fs::remove_dir_all(&src);
+    }
+}
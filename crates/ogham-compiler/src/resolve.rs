@@ -8,7 +8,7 @@ use std::collections::{HashMap, HashSet};
 use crate::ast::{self, AstNode};
 use crate::diagnostics::Diagnostics;
 use crate::hir::*;
-use crate::index::ParsedFile;
+use crate::index::{make_loc, ParsedFile};
 /// Extract the short package name from a full_name like "github.com/org/proj/common.Address" → "common".
 /// For old-format "common.Address" → "common".
 fn split_full_name_pkg_short(full: &str) -> &str {
@@ -633,7 +633,7 @@ fn collect_imports(root: &ast::Root, _interner: &mut Interner, _pkg: &str) -> Im
 fn collect_fields(
     fields: &[ast::FieldDecl],
     interner: &mut Interner,
-    _file_sym: Sym,
+    file_sym: Sym,
     pkg: &str,
     import_path: &str,
     imports: &ImportMap,
@@ -674,7 +674,7 @@ fn collect_fields(
                 annotations,
                 mapping,
                 trace: None,
-                loc: Loc::default(),
+                loc: make_loc(file_sym, f.syntax()),
             })
         })
         .collect()
@@ -684,7 +684,7 @@ fn collect_fields(
 fn collect_oneofs(
     oneofs: &[ast::OneofDecl],
     interner: &mut Interner,
-    _file_sym: Sym,
+    file_sym: Sym,
     pkg: &str,
     import_path: &str,
     imports: &ImportMap,
@@ -723,7 +723,7 @@ fn collect_oneofs(
                         ty,
                         annotations,
                         mapping,
-                        loc: Loc::default(),
+                        loc: make_loc(file_sym, f.syntax()),
                     })
                 })
                 .collect();
@@ -733,7 +733,7 @@ fn collect_oneofs(
                 name: interner.intern(&name),
                 fields,
                 annotations: oneof_annotations,
-                loc: Loc::default(),
+                loc: make_loc(file_sym, o.syntax()),
             })
         })
         .collect()
@@ -2036,11 +2036,14 @@ pub fn populate_annotation_params(
                         .map(|tr| resolve_type_ref(&tr, interner, pkg, ip, &imports, symbols, diag, &file.file_name))
                         .unwrap_or(ResolvedType::Error);
 
+                    let tightens = param_tightening(field, &file.file_name, diag);
+
                     Some(AnnotationParamDef {
                         name: name_sym,
                         ty,
                         is_optional,
                         default_value: None,
+                        tightens,
                     })
                 })
                 .collect();
@@ -2049,13 +2052,70 @@ pub fn populate_annotation_params(
             // For overloaded annotations, find the one that matches this specific declaration
             // by checking that it was allocated from this file (use last in the vec as heuristic).
             // In practice each annotation_decl creates exactly one AnnotationDef.
+            let tightens = decl_tightening(&ann_decl, &file.file_name, diag);
             if let Some(&id) = ids.last() {
                 arenas.annotation_defs[id].params = params;
+                arenas.annotation_defs[id].tightens = tightens;
             }
         }
     }
 }
 
+/// Read `@tightens` from an annotation definition: adding the annotation
+/// to a field tightens validation, whatever its parameters.
+fn decl_tightening(decl: &ast::AnnotationDecl, file_name: &str, diag: &mut Diagnostics) -> bool {
+    let mut tightens = false;
+    for ann in decl.annotations() {
+        if ann.simple_name().as_deref() != Some("tightens") {
+            continue;
+        }
+        if ann.args().is_some_and(|args| !args.args().is_empty()) {
+            let r = ann.syntax().text_range();
+            diag.error(
+                file_name,
+                usize::from(r.start())..usize::from(r.end()),
+                "@tightens on an annotation definition takes no arguments",
+            );
+        }
+        tightens = true;
+    }
+    tightens
+}
+
+/// Read `@tightens(increase|decrease|change)` from an annotation parameter.
+fn param_tightening(
+    field: &ast::AnnotationField,
+    file_name: &str,
+    diag: &mut Diagnostics,
+) -> Option<Tightening> {
+    for ann in field.annotations() {
+        let range = {
+            let r = ann.syntax().text_range();
+            usize::from(r.start())..usize::from(r.end())
+        };
+        if ann.simple_name().as_deref() != Some("tightens") {
+            diag.error(file_name, range, "only @tightens(...) is allowed on annotation parameters");
+            continue;
+        }
+        let direction = ann
+            .args()
+            .and_then(|args| args.args().into_iter().next())
+            .and_then(|arg| arg.value())
+            .map(|v| v.syntax().text().to_string());
+        match direction.as_deref().map(str::trim) {
+            Some("increase") => return Some(Tightening::Increase),
+            Some("decrease") => return Some(Tightening::Decrease),
+            Some("change") => return Some(Tightening::Change),
+            _ => diag.error(
+                file_name,
+                range,
+                "@tightens expects `increase`, `decrease` or `change`",
+            ),
+        }
+    }
+    None
+}
+
 // ── Pass 14: Annotation overload resolution + validation ─────────────
 
 /// Check that a `ResolvedType` matches a `TypeConstraint`.
@@ -0,0 +1,132 @@
+//! Multi-module workspaces: ogham.work.yaml lists member modules that live
+//! in one tree. Members are resolved as a single dependency graph, and a
+//! member required by another member is always used from its directory.
+
+use crate::manifest::{self, ModFile};
+use crate::pipeline::SourceFile;
+use std::path::{Path, PathBuf};
+
+/// One module listed in ogham.work.yaml.
+#[derive(Debug)]
+pub struct Member {
+    /// Directory as written in ogham.work.yaml.
+    pub path: String,
+    /// Canonical member directory.
+    pub dir: PathBuf,
+    pub mod_file: ModFile,
+}
+
+impl Member {
+    pub fn module(&self) -> &str {
+        &self.mod_file.module
+    }
+}
+
+/// A loaded workspace: its root directory and every member module.
+#[derive(Debug)]
+pub struct Workspace {
+    /// Canonical directory containing ogham.work.yaml.
+    pub root: PathBuf,
+    pub members: Vec<Member>,
+}
+
+impl Workspace {
+    /// Load ogham.work.yaml from `root` and the ogham.mod.yaml of every member.
+    pub fn load(root: &Path) -> Result<Self, String> {
+        let root = root
+            .canonicalize()
+            .map_err(|e| format!("cannot open {}: {}", root.display(), e))?;
+        let work = manifest::load_work_file(&root)?;
+        if work.members.is_empty() {
+            return Err("ogham.work.yaml: 'members' lists no modules".into());
+        }
+
+        let mut members: Vec<Member> = Vec::new();
+        for path in work.members {
+            let dir = root
+                .join(&path)
+                .canonicalize()
+                .map_err(|_| format!("ogham.work.yaml: member {} not found", path))?;
+            let mod_file =
+                manifest::load_mod_file(&dir).map_err(|e| format!("ogham.work.yaml: member {}: {}", path, e))?;
+            if let Some(other) = members.iter().find(|m| m.mod_file.module == mod_file.module) {
+                return Err(format!(
+                    "ogham.work.yaml: {} is both {} and {}",
+                    mod_file.module, other.path, path
+                ));
+            }
+            members.push(Member { path, dir, mod_file });
+        }
+
+        Ok(Workspace { root, members })
+    }
+
+    /// The workspace `dir` belongs to: ogham.work.yaml in `dir` or its
+    /// closest ancestor. `OGHAM_WORK=off` disables workspaces.
+    pub fn find(dir: &Path) -> Result<Option<Self>, String> {
+        if !enabled() {
+            return Ok(None);
+        }
+        let Ok(dir) = dir.canonicalize() else {
+            return Ok(None);
+        };
+        match dir.ancestors().find(|d| d.join("ogham.work.yaml").is_file()) {
+            Some(root) => Self::load(root).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    pub fn member(&self, module: &str) -> Option<&Member> {
+        self.members.iter().find(|m| m.module() == module)
+    }
+
+    /// The member whose directory is `dir`.
+    pub fn member_at(&self, dir: &Path) -> Option<&Member> {
+        let dir = dir.canonicalize().ok()?;
+        self.members.iter().find(|m| m.dir == dir)
+    }
+
+    /// Every `.ogham` file of every member.
+    pub fn sources(&self) -> Result<Vec<SourceFile>, String> {
+        let mut sources = Vec::new();
+        for member in &self.members {
+            crate::pkg::collect_ogham_files(&member.dir, &mut sources)?;
+        }
+        Ok(sources)
+    }
+}
+
+/// Workspaces are on unless `OGHAM_WORK=off`.
+pub fn enabled() -> bool {
+    std::env::var("OGHAM_WORK").map_or(true, |v| v != "off")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn find_from_member_directory() {
+        let root = std::env::temp_dir().join(format!("ogham-work-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for (dir, module) in [("common", "example.com/common"), ("shop", "example.com/shop")] {
+            fs::create_dir_all(root.join(dir).join("schemas")).unwrap();
+            fs::write(root.join(dir).join("ogham.mod.yaml"), format!("module: {}\n", module)).unwrap();
+        }
+        fs::write(root.join("ogham.work.yaml"), "members:\n  - ./common\n  - ./shop\n").unwrap();
+
+        let ws = Workspace::find(&root.join("shop").join("schemas")).unwrap().unwrap();
+        assert_eq!(ws.root, root.canonicalize().unwrap());
+        assert_eq!(ws.members.len(), 2);
+        assert_eq!(ws.member_at(&root.join("shop")).unwrap().module(), "example.com/shop");
+        assert!(ws.member_at(&root).is_none());
+        assert_eq!(ws.member("example.com/common").unwrap().path, "./common");
+
+        fs::write(root.join("ogham.work.yaml"), "members:\n  - ./common\n  - ./common/\n").unwrap();
+        let err = Workspace::load(&root).unwrap_err();
+        assert!(err.contains("example.com/common is both"), "{}", err);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
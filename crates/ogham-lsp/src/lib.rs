@@ -20,10 +20,15 @@ pub struct Backend {
 
 #[tower_lsp::async_trait]
 impl LanguageServer for Backend {
-    async fn initialize(&self, _: InitializeParams) -> Result<InitializeResult> {
+    async fn initialize(&self, params: InitializeParams) -> Result<InitializeResult> {
         // Index embedded std library
         self.index.index_std();
 
+        // Index ogham.work.yaml workspace members
+        if let Some(root) = workspace_root(&params) {
+            self.index.index_workspace(&root);
+        }
+
         Ok(InitializeResult {
             capabilities: ServerCapabilities {
                 text_document_sync: Some(TextDocumentSyncCapability::Kind(
@@ -787,6 +792,19 @@ impl Backend {
     }
 }
 
+/// Root directory of the editor workspace: the first workspace folder, or
+/// the root URI of older clients.
+#[allow(deprecated)]
+fn workspace_root(params: &InitializeParams) -> Option<std::path::PathBuf> {
+    let uri = params
+        .workspace_folders
+        .as_ref()
+        .and_then(|folders| folders.first())
+        .map(|folder| &folder.uri)
+        .or(params.root_uri.as_ref())?;
+    uri.to_file_path().ok()
+}
+
 // ── Hover helpers ──────────────────────────────────────────────────────
 
 fn find_hover_info(token: &ogham_compiler::syntax_kind::SyntaxToken) -> Option<String> {
@@ -4,6 +4,8 @@
 use dashmap::DashMap;
 use ogham_compiler::ast::{self, AstNode};
 use ogham_compiler::parser::Parse;
+use ogham_compiler::workspace::Workspace;
+use std::path::Path;
 use tower_lsp::lsp_types::*;
 
 /// Per-document state cached after each parse.
@@ -276,6 +278,26 @@ impl WorkspaceIndex {
             self.index_document(&uri, &source_file.content, &parse);
         }
     }
+
+    /// Index every member of the ogham.work.yaml workspace `dir` belongs
+    /// to, so symbols from other modules resolve before their files are
+    /// opened. Returns the number of files indexed.
+    pub fn index_workspace(&self, dir: &Path) -> usize {
+        let Ok(Some(ws)) = Workspace::find(dir) else {
+            return 0;
+        };
+        let Ok(sources) = ws.sources() else {
+            return 0;
+        };
+        for source_file in &sources {
+            let Ok(uri) = Url::from_file_path(&source_file.name) else {
+                continue;
+            };
+            let parse = ogham_compiler::parser::parse(&source_file.content);
+            self.index_document(&uri, &source_file.content, &parse);
+        }
+        sources.len()
+    }
 }
 
 fn collect_type_children(_source: &str, uri: &Url, ty: &ast::TypeDecl) -> Vec<SymbolDef> {
@@ -388,3 +388,42 @@ async fn test_std_types_in_completion() {
     // UUID should come from std index
     assert!(labels.contains(&"UUID"), "expected UUID from std: {:?}", &labels[..10.min(labels.len())]);
 }
+
+#[tokio::test]
+async fn test_workspace_members_indexed() {
+    let root = std::env::temp_dir().join(format!("ogham-lsp-work-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&root);
+    for (dir, source) in [
+        ("common", "package common;\ntype Money { int64 amount = 1; }\n"),
+        ("shop", "package shop;\ntype Order { string id = 1; }\n"),
+    ] {
+        std::fs::create_dir_all(root.join(dir)).unwrap();
+        std::fs::write(root.join(dir).join("ogham.mod.yaml"), format!("module: example.com/{}\n", dir)).unwrap();
+        std::fs::write(root.join(dir).join(format!("{}.ogham", dir)), source).unwrap();
+    }
+    std::fs::write(root.join("ogham.work.yaml"), "members:\n  - ./common\n  - ./shop\n").unwrap();
+
+    let mut service = build();
+    let root_uri = tower_lsp::lsp_types::Url::from_file_path(root.join("shop")).unwrap();
+    let _ = request(
+        &mut service,
+        "initialize",
+        1,
+        json!({ "capabilities": {}, "processId": null, "rootUri": root_uri }),
+    )
+    .await;
+
+    // Money lives in another member, and no file has been opened
+    let resp = request(&mut service, "workspace/symbol", 2, json!({ "query": "Money" })).await;
+    let symbols = resp
+        .pointer("/result")
+        .and_then(|r| r.as_array())
+        .expect("expected symbols");
+    assert!(
+        symbols.iter().any(|s| s["name"] == "Money"),
+        "missing Money: {:?}",
+        symbols
+    );
+
+    let _ = std::fs::remove_dir_all(&root);
+}
@@ -0,0 +1,167 @@
+//! Ogham module proxy — serves the `@v/list`, `.info`, `.mod` and `.zip`
+//! layout from docs/adr/package.md out of a storage directory, filling it on
+//! demand from upstream git repositories or other proxies.
+
+mod store;
+
+use clap::Parser;
+use ogham_compiler::proxy;
+use std::io::Read;
+use std::path::PathBuf;
+use std::sync::Arc;
+use store::{PublishError, ServeError, Store};
+use tiny_http::{Header, Method, Response, Server};
+
+#[derive(Parser)]
+#[command(name = "ogham-proxy", version, about = "Ogham module proxy")]
+struct Args {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    listen: String,
+
+    /// Storage directory for served modules
+    #[arg(long)]
+    storage: PathBuf,
+
+    /// Where missing modules come from: comma-separated proxy URLs and `direct`
+    #[arg(long, default_value = "direct")]
+    upstream: String,
+
+    /// Serve only what is already in storage (air-gapped mirrors)
+    #[arg(long, conflicts_with = "allow_publish")]
+    read_only: bool,
+
+    /// Accept uploads from `ogham publish` (HTTP PUT)
+    #[arg(long)]
+    allow_publish: bool,
+
+    /// Number of worker threads
+    #[arg(long, default_value_t = 4)]
+    workers: usize,
+
+    /// Largest upload accepted, in bytes; larger ones get 413
+    #[arg(long, default_value_t = MAX_UPLOAD)]
+    max_upload: u64,
+}
+
+/// Default `--max-upload`: 64 MiB.
+const MAX_UPLOAD: u64 = 64 << 20;
+
+fn main() {
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    std::fs::create_dir_all(&args.storage)
+        .map_err(|e| format!("cannot create {}: {}", args.storage.display(), e))?;
+
+    let store = Arc::new(Store::new(
+        args.storage.clone(),
+        proxy::parse_chain(&args.upstream),
+        args.read_only,
+    ));
+    let allow_publish = args.allow_publish;
+    let max_upload = args.max_upload;
+    let server = Arc::new(
+        Server::http(&args.listen).map_err(|e| format!("cannot listen on {}: {}", args.listen, e))?,
+    );
+
+    eprintln!(
+        "ogham-proxy listening on http://{} (storage: {}{})",
+        args.listen,
+        args.storage.display(),
+        if args.read_only { ", read-only" } else { "" }
+    );
+
+    let workers: Vec<_> = (0..args.workers.max(1))
+        .map(|_| {
+            let server = Arc::clone(&server);
+            let store = Arc::clone(&store);
+            std::thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(&store, allow_publish, max_upload, request);
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+fn respond(store: &Store, allow_publish: bool, max_upload: u64, mut request: tiny_http::Request) {
+    let (status, content_type, body) = if *request.method() == Method::Put && allow_publish {
+        upload(store, max_upload, &mut request)
+    } else if *request.method() != Method::Get {
+        (405, "text/plain", b"method not allowed".to_vec())
+    } else {
+        match store::parse_path(request.url()) {
+            None => (404, "text/plain", b"not found".to_vec()),
+            Some(req) => match store.handle(&req) {
+                Ok(Some(body)) => (200, req.content_type(), body),
+                Ok(None) => (404, "text/plain", b"not found".to_vec()),
+                // Anything but 404/410 stops a client's chain, so a
+                // broken upstream is never mistaken for a missing module
+                Err(ServeError::Upstream(e)) => {
+                    eprintln!("  {}: {}", request.url(), e);
+                    (502, "text/plain", e.into_bytes())
+                }
+                Err(ServeError::Failed(e)) => {
+                    eprintln!("  {}: {}", request.url(), e);
+                    (500, "text/plain", e.into_bytes())
+                }
+            },
+        }
+    };
+
+    let header = Header::from_bytes(&b"Content-Type"[..], content_type.as_bytes())
+        .expect("valid header");
+    let response = Response::from_data(body)
+        .with_status_code(status)
+        .with_header(header);
+    let _ = request.respond(response);
+}
+
+fn upload(store: &Store, max_upload: u64, request: &mut tiny_http::Request) -> (u16, &'static str, Vec<u8>) {
+    let Some(req) = store::parse_path(request.url()) else {
+        return (404, "text/plain", b"not found".to_vec());
+    };
+    let body = match read_limited(request.as_reader(), max_upload) {
+        Ok(Some(body)) => body,
+        Ok(None) => {
+            return (413, "text/plain", format!("upload larger than {} bytes", max_upload).into_bytes());
+        }
+        Err(e) => return (400, "text/plain", format!("cannot read upload: {}", e).into_bytes()),
+    };
+    match store.publish(&req, &body) {
+        Ok(()) => (201, "text/plain", b"created".to_vec()),
+        Err(PublishError::Exists(e)) => (409, "text/plain", e.into_bytes()),
+        Err(PublishError::Rejected(e)) => (400, "text/plain", e.into_bytes()),
+        Err(PublishError::Failed(e)) => {
+            eprintln!("  {}: {}", request.url(), e);
+            (500, "text/plain", e.into_bytes())
+        }
+    }
+}
+
+/// Read a body of at most `limit` bytes; `None` if it is larger.
+fn read_limited(reader: impl Read, limit: u64) -> std::io::Result<Option<Vec<u8>>> {
+    let mut body = Vec::new();
+    reader.take(limit.saturating_add(1)).read_to_end(&mut body)?;
+    Ok((body.len() as u64 <= limit).then_some(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uploads_are_limited() {
+        assert_eq!(read_limited(&b"12345"[..], 5).unwrap(), Some(b"12345".to_vec()));
+        assert_eq!(read_limited(&b"123456"[..], 5).unwrap(), None);
+    }
+}
@@ -0,0 +1,427 @@
+//! Storage directory and on-demand fill.
+//!
+//! Layout mirrors the URL space: `<storage>/<module>/@v/<version>.{info,mod,zip}`.
+//! A version counts as stored once its `.info` exists — it is written last.
+
+use ogham_compiler::pkg::{self, SemVer};
+use ogham_compiler::proxy::{self, ProxyEntry, VersionInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A request in the proxy URL space.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Request {
+    List { module: String },
+    Info { module: String, version: String },
+    Mod { module: String, version: String },
+    Zip { module: String, version: String },
+}
+
+impl Request {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Request::List { .. } | Request::Mod { .. } => "text/plain; charset=utf-8",
+            Request::Info { .. } => "application/json",
+            Request::Zip { .. } => "application/zip",
+        }
+    }
+}
+
+/// Parse `/<module>/@v/list` or `/<module>/@v/<version>.<ext>`. Paths that
+/// could escape the storage directory are rejected.
+pub fn parse_path(path: &str) -> Option<Request> {
+    let path = path.split('?').next()?.strip_prefix('/')?;
+    let (module, rest) = path.split_once("/@v/")?;
+    let safe = |segment: &str| {
+        !segment.is_empty()
+            && segment != "."
+            && segment != ".."
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-._~".contains(c))
+    };
+    if !module.split('/').all(safe) {
+        return None;
+    }
+    let module = module.to_string();
+
+    if rest == "list" {
+        return Some(Request::List { module });
+    }
+    let (version, ext) = rest.rsplit_once('.')?;
+    if !safe(version) {
+        return None;
+    }
+    let version = version.to_string();
+    match ext {
+        "info" => Some(Request::Info { module, version }),
+        "mod" => Some(Request::Mod { module, version }),
+        "zip" => Some(Request::Zip { module, version }),
+        _ => None,
+    }
+}
+
+/// Why an upload was refused.
+#[derive(Debug)]
+pub enum PublishError {
+    /// The version is already published (409).
+    Exists(String),
+    /// The upload is invalid (400).
+    Rejected(String),
+    /// Storage failure (500).
+    Failed(String),
+}
+
+/// Why a request could not be served. A module or version that doesn't
+/// exist is not an error — `handle` returns `None` for it (404).
+#[derive(Debug)]
+pub enum ServeError {
+    /// Upstream failed, or served a module that fails its checksum (502).
+    Upstream(String),
+    /// Storage failure (500).
+    Failed(String),
+}
+
+pub struct Store {
+    root: PathBuf,
+    upstream: Vec<ProxyEntry>,
+    read_only: bool,
+}
+
+impl Store {
+    pub fn new(root: PathBuf, upstream: Vec<ProxyEntry>, read_only: bool) -> Self {
+        Store {
+            root,
+            upstream,
+            read_only,
+        }
+    }
+
+    /// Response body for a request, or `None` if the module or version
+    /// doesn't exist.
+    pub fn handle(&self, req: &Request) -> Result<Option<Vec<u8>>, ServeError> {
+        let (module, version, ext) = match req {
+            Request::List { module } => return self.list(module),
+            Request::Info { module, version } => (module, version, "info"),
+            Request::Mod { module, version } => (module, version, "mod"),
+            Request::Zip { module, version } => (module, version, "zip"),
+        };
+
+        if !self.is_stored(module, version) && (self.read_only || !self.fill(module, version)?) {
+            return Ok(None);
+        }
+        let path = self.version_dir(module).join(format!("{}.{}", version, ext));
+        std::fs::read(&path)
+            .map(Some)
+            .map_err(|e| ServeError::Failed(format!("cannot read {}: {}", path.display(), e)))
+    }
+
+    /// Stored versions, plus whatever upstream lists unless read-only.
+    fn list(&self, module: &str) -> Result<Option<Vec<u8>>, ServeError> {
+        let mut versions = self.stored_versions(module);
+        let mut known = !versions.is_empty();
+        if !self.read_only {
+            if let Some(upstream) = pkg::list_versions(module, &self.upstream).map_err(ServeError::Upstream)? {
+                known = true;
+                versions.extend(upstream);
+            }
+        }
+        if !known {
+            return Ok(None);
+        }
+
+        versions.sort_by_key(|v| (SemVer::parse(v), v.clone()));
+        versions.dedup();
+        let mut body = versions.join("\n");
+        body.push('\n');
+        Ok(Some(body.into_bytes()))
+    }
+
+    fn stored_versions(&self, module: &str) -> Vec<String> {
+        let Ok(entries) = std::fs::read_dir(self.version_dir(module)) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .strip_suffix(".info")
+                    .map(str::to_string)
+            })
+            .collect()
+    }
+
+    /// Fetch a version from upstream the same way `ogham install` would and
+    /// store its zip, `.mod` and `.info`. Returns `false` if upstream
+    /// doesn't list the version.
+    fn fill(&self, module: &str, version: &str) -> Result<bool, ServeError> {
+        eprintln!("  filling {}@{}", module, version);
+        let dep = match pkg::fetch_exact(module, version, &self.upstream) {
+            Ok(dep) => dep,
+            Err(e) if !self.upstream_lists(module, version) => {
+                eprintln!("  {}@{}: {}", module, version, e);
+                return Ok(false);
+            }
+            Err(e) => return Err(ServeError::Upstream(e)),
+        };
+        if !dep.path.is_dir() {
+            return Err(ServeError::Upstream(format!("{}@{} has no files to serve", module, version)));
+        }
+        self.store(module, version, &dep.path).map_err(ServeError::Failed)?;
+        Ok(true)
+    }
+
+    /// Whether the upstream chain lists `version` of `module`. A failed
+    /// listing counts as listed: only a definite answer makes a miss a 404.
+    fn upstream_lists(&self, module: &str, version: &str) -> bool {
+        let wanted = SemVer::parse(version);
+        match pkg::list_versions(module, &self.upstream) {
+            Ok(Some(listed)) => listed
+                .iter()
+                .any(|v| v == version || (wanted.is_some() && SemVer::parse(v) == wanted)),
+            Ok(None) => false,
+            Err(_) => true,
+        }
+    }
+
+    /// Pack a fetched module into storage.
+    fn store(&self, module: &str, version: &str, src: &Path) -> Result<(), String> {
+        let zip = proxy::pack_zip(src, module, version, &[])?;
+        let mod_file = std::fs::read(src.join("ogham.mod.yaml")).unwrap_or_default();
+        let info = VersionInfo {
+            version: version.to_string(),
+            time: pkg::commit_time(src),
+            hash: Some(pkg::hash_dir(src)?),
+        };
+        let info = serde_json::to_vec_pretty(&info)
+            .map_err(|e| format!("cannot serialize info: {}", e))?;
+
+        let dir = self.version_dir(module);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("cannot create {}: {}", dir.display(), e))?;
+        write_atomic(&dir.join(format!("{}.zip", version)), &zip)?;
+        write_atomic(&dir.join(format!("{}.mod", version)), &mod_file)?;
+        write_atomic(&dir.join(format!("{}.info", version)), &info)
+    }
+
+    /// Store an uploaded file (`ogham publish`). Versions are immutable: a
+    /// version whose `.info` exists is never overwritten. The `.info` must
+    /// come last, and its hash must match the uploaded zip.
+    pub fn publish(&self, req: &Request, body: &[u8]) -> Result<(), PublishError> {
+        let (module, version, ext) = match req {
+            Request::List { .. } => return Err(PublishError::Rejected("cannot upload a version list".into())),
+            Request::Info { module, version } => (module, version, "info"),
+            Request::Mod { module, version } => (module, version, "mod"),
+            Request::Zip { module, version } => (module, version, "zip"),
+        };
+        if self.is_stored(module, version) {
+            return Err(PublishError::Exists(format!("{}@{} is already published", module, version)));
+        }
+
+        let dir = self.version_dir(module);
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| PublishError::Failed(format!("cannot create {}: {}", dir.display(), e)))?;
+
+        if ext == "info" {
+            self.check_upload(module, version, body).map_err(PublishError::Rejected)?;
+        }
+        eprintln!("  published {}@{}.{}", module, version, ext);
+        write_atomic(&dir.join(format!("{}.{}", version, ext)), body).map_err(PublishError::Failed)
+    }
+
+    /// Verify an uploaded `.info` against the zip uploaded before it.
+    fn check_upload(&self, module: &str, version: &str, info: &[u8]) -> Result<(), String> {
+        let info: VersionInfo =
+            serde_json::from_slice(info).map_err(|e| format!("invalid .info: {}", e))?;
+        if info.version != version {
+            return Err(format!(".info is for {}, not {}", info.version, version));
+        }
+
+        let dir = self.version_dir(module);
+        let zip_path = dir.join(format!("{}.zip", version));
+        if !zip_path.is_file() || !dir.join(format!("{}.mod", version)).is_file() {
+            return Err("upload the .zip and .mod before the .info".into());
+        }
+        let zip = std::fs::read(&zip_path)
+            .map_err(|e| format!("cannot read {}: {}", zip_path.display(), e))?;
+
+        // Unique per upload: worker threads share the pid, and modules
+        // share versions
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let scratch = std::env::temp_dir().join(format!(
+            "ogham-proxy-upload-{}-{}-{}-{}",
+            std::process::id(),
+            module.replace('/', "_"),
+            version,
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&scratch);
+        let hash = proxy::extract_zip(&zip, &proxy::zip_prefix(module, version), &scratch)
+            .and_then(|_| pkg::hash_dir(&scratch));
+        let _ = std::fs::remove_dir_all(&scratch);
+        let hash = hash?;
+
+        match info.hash {
+            Some(expected) if expected == hash => Ok(()),
+            Some(expected) => Err(format!(
+                "checksum mismatch: .info says {}, uploaded zip is {}",
+                expected, hash
+            )),
+            None => Err(".info has no hash".into()),
+        }
+    }
+
+    fn is_stored(&self, module: &str, version: &str) -> bool {
+        self.version_dir(module)
+            .join(format!("{}.info", version))
+            .is_file()
+    }
+
+    fn version_dir(&self, module: &str) -> PathBuf {
+        self.root.join(module).join("@v")
+    }
+}
+
+fn write_atomic(path: &Path, content: &[u8]) -> Result<(), String> {
+    // Unique per write: worker threads share the pid, and `.zip`, `.mod`
+    // and `.info` of one version share a stem
+    static NEXT: AtomicUsize = AtomicUsize::new(0);
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let tmp = path.with_file_name(format!(
+        "{}.tmp-{}-{}",
+        name,
+        std::process::id(),
+        NEXT.fetch_add(1, Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp, content)
+        .and_then(|_| std::fs::rename(&tmp, path))
+        .map_err(|e| format!("cannot write {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ogham-proxy-store-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn concurrent_writes_of_one_version_do_not_mix() {
+        let dir = temp_dir("atomic");
+        let threads: Vec<_> = ["zip", "mod", "info"]
+            .into_iter()
+            .flat_map(|ext| (0..8).map(move |_| ext))
+            .map(|ext| {
+                let path = dir.join(format!("v1.0.0.{}", ext));
+                std::thread::spawn(move || write_atomic(&path, ext.as_bytes()).unwrap())
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        for ext in ["zip", "mod", "info"] {
+            assert_eq!(fs::read_to_string(dir.join(format!("v1.0.0.{}", ext))).unwrap(), ext);
+        }
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_proxy_paths() {
+        assert_eq!(
+            parse_path("/github.com/org/db/@v/list"),
+            Some(Request::List { module: "github.com/org/db".into() })
+        );
+        assert_eq!(
+            parse_path("/github.com/org/db/@v/v1.2.0.zip"),
+            Some(Request::Zip { module: "github.com/org/db".into(), version: "v1.2.0".into() })
+        );
+        assert_eq!(parse_path("/github.com/org/db/@v/v1.2.0.exe"), None);
+        assert_eq!(parse_path("/github.com/../etc/@v/list"), None);
+        assert_eq!(parse_path("/github.com/org/db/@v/../x.info"), None);
+        assert_eq!(parse_path("/github.com/org/db"), None);
+    }
+
+    #[test]
+    fn publish_verifies_and_freezes_versions() {
+        let root = temp_dir("publish");
+        let src = root.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("ogham.mod.yaml"), "module: example.test/lib\nversion: 1.0.0\n").unwrap();
+        fs::write(src.join("lib.ogham"), "package lib;\n").unwrap();
+        let artifacts = pkg::pack(&src, &[]).unwrap();
+        let info = serde_json::to_vec(&artifacts.info).unwrap();
+
+        let store = Store::new(root.join("storage"), vec![ProxyEntry::Direct], false);
+        let path = |ext: &str| parse_path(&format!("/example.test/lib/@v/v1.0.0.{}", ext)).unwrap();
+
+        // .info before the zip is refused
+        assert!(matches!(store.publish(&path("info"), &info), Err(PublishError::Rejected(_))));
+
+        store.publish(&path("zip"), &artifacts.zip).unwrap();
+        store.publish(&path("mod"), &artifacts.mod_file).unwrap();
+        let tampered = serde_json::to_vec(&VersionInfo {
+            hash: Some("h1:0000".into()),
+            ..artifacts.info.clone()
+        })
+        .unwrap();
+        assert!(matches!(store.publish(&path("info"), &tampered), Err(PublishError::Rejected(_))));
+        store.publish(&path("info"), &info).unwrap();
+
+        assert_eq!(store.handle(&path("zip")).unwrap().unwrap(), artifacts.zip);
+        assert!(matches!(store.publish(&path("zip"), b"other"), Err(PublishError::Exists(_))));
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn read_only_serves_storage_only() {
+        let root = temp_dir("ro");
+        let src = root.join("src");
+        fs::create_dir_all(&src).unwrap();
+        fs::write(src.join("ogham.mod.yaml"), "module: example.test/lib\n").unwrap();
+        fs::write(src.join("lib.ogham"), "package lib;\n").unwrap();
+
+        let storage = root.join("storage");
+        let dir = storage.join("example.test/lib/@v");
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("v1.0.0.zip"), proxy::pack_zip(&src, "example.test/lib", "v1.0.0", &[]).unwrap()).unwrap();
+        fs::write(dir.join("v1.0.0.mod"), "module: example.test/lib\n").unwrap();
+        fs::write(dir.join("v1.0.0.info"), "{\"version\":\"v1.0.0\"}").unwrap();
+        fs::write(dir.join("v1.10.0.info"), "{\"version\":\"v1.10.0\"}").unwrap();
+        fs::write(dir.join("v1.2.0.info"), "{\"version\":\"v1.2.0\"}").unwrap();
+
+        let store = Store::new(storage, vec![ProxyEntry::Direct], true);
+        let list = store.handle(&parse_path("/example.test/lib/@v/list").unwrap()).unwrap().unwrap();
+        assert_eq!(String::from_utf8(list).unwrap(), "v1.0.0\nv1.2.0\nv1.10.0\n");
+
+        let module = store.handle(&parse_path("/example.test/lib/@v/v1.0.0.mod").unwrap()).unwrap();
+        assert_eq!(module.unwrap(), b"module: example.test/lib\n");
+
+        // Never reaches upstream
+        assert_eq!(store.handle(&parse_path("/example.test/lib/@v/v2.0.0.zip").unwrap()).unwrap(), None);
+        assert_eq!(store.handle(&parse_path("/example.test/other/@v/list").unwrap()).unwrap(), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn unreachable_upstream_is_not_a_miss() {
+        let root = temp_dir("down");
+        // Nothing listens on a port that was just released
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let upstream = vec![ProxyEntry::Url(format!("http://127.0.0.1:{}", port))];
+
+        let store = Store::new(root.clone(), upstream, false);
+        let result = store.handle(&parse_path("/example.test/lib/@v/list").unwrap());
+        assert!(matches!(result, Err(ServeError::Upstream(_))), "{:?}", result);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -26,9 +26,13 @@
 //!     });
 //! }
 //! ```
+//!
+//! The same handler can run inside the compiler as a WebAssembly plugin; see
+//! [`wasm_plugin!`].
 
 mod runner;
 mod codegen;
+pub mod wasm;
 
 // Re-export proto types for plugin authors
 pub use ogham_proto::oghamproto::ir::*;
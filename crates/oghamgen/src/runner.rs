@@ -44,15 +44,7 @@ where
         }
         Err(e) => {
             // Fatal error — still try to send a response with the error
-            let response = CompileResponse {
-                files: Vec::new(),
-                errors: vec![CompileError {
-                    message: e,
-                    severity: Severity::Error as i32,
-                    source_type: String::new(),
-                    source_field: String::new(),
-                }],
-            };
+            let response = error_response(e);
             let mut buf = Vec::new();
             if response.encode(&mut buf).is_ok() {
                 let _ = io::stdout().write_all(&buf);
@@ -62,6 +54,19 @@ where
     }
 }
 
+/// A response carrying only `message` as an ERROR.
+pub(crate) fn error_response(message: String) -> CompileResponse {
+    CompileResponse {
+        files: Vec::new(),
+        errors: vec![CompileError {
+            message,
+            severity: Severity::Error as i32,
+            source_type: String::new(),
+            source_field: String::new(),
+        }],
+    }
+}
+
 fn run_inner<F>(handler: F) -> Result<CompileResponse, String>
 where
     F: FnOnce(CompileRequest) -> Result<CompileResponse, String>,
@@ -0,0 +1,124 @@
+//! WebAssembly entry point — runs a plugin inside the `ogham` process
+//! instead of as a binary speaking stdin/stdout.
+//!
+//! Build the plugin as a `cdylib` for `wasm32-wasip1` and hand its handler
+//! to [`wasm_plugin!`](crate::wasm_plugin), which exports the `alloc` and
+//! `compile` functions `ogham generate` calls:
+//!
+//! ```rust,ignore
+//! oghamgen::wasm_plugin!(|req: oghamgen::CompileRequest| {
+//!     let mut resp = oghamgen::CompileResponse::default();
+//!     // ... generate code ...
+//!     Ok(resp)
+//! });
+//! ```
+//!
+//! The request buffer is freed once decoded, and only the latest response
+//! is kept alive for the host to read.
+
+use std::cell::RefCell;
+
+use crate::runner::error_response;
+use crate::{CompileRequest, CompileResponse};
+use prost::Message;
+
+/// Export a plugin handler as the `alloc` and `compile` functions of a wasm
+/// plugin. Does nothing on targets other than `wasm32`, so a plugin crate can
+/// also build a native binary calling [`run`](crate::run).
+#[macro_export]
+macro_rules! wasm_plugin {
+    ($handler:expr) => {
+        #[cfg(target_arch = "wasm32")]
+        #[no_mangle]
+        pub extern "C" fn alloc(len: i32) -> i32 {
+            $crate::wasm::alloc(len as u32 as usize) as i32
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        #[no_mangle]
+        pub extern "C" fn compile(ptr: i32, len: i32) -> i64 {
+            // SAFETY: ogham writes the request into memory it got from `alloc`.
+            let (ptr, len) = unsafe { $crate::wasm::compile(ptr as u32 as *mut u8, len as u32 as usize, $handler) };
+            ((ptr as u32 as u64) << 32 | len as u32 as u64) as i64
+        }
+    };
+}
+
+thread_local! {
+    /// Encoded response of the latest [`compile`] call.
+    static RESPONSE: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Reserve `len` bytes for the request; [`compile`] frees them.
+#[doc(hidden)]
+pub fn alloc(len: usize) -> *mut u8 {
+    Box::into_raw(vec![0u8; len].into_boxed_slice()) as *mut u8
+}
+
+/// Decode and free the request at `ptr`, call `handler` and return where the
+/// encoded response is. It stays valid until the next call. A handler error
+/// is returned as an ERROR in the response, like [`run`](crate::run) does.
+///
+/// # Safety
+///
+/// `ptr` and `len` must describe memory returned by [`alloc`], which is not
+/// used afterwards.
+#[doc(hidden)]
+pub unsafe fn compile<F>(ptr: *mut u8, len: usize, handler: F) -> (*const u8, usize)
+where
+    F: FnOnce(CompileRequest) -> Result<CompileResponse, String>,
+{
+    let input = Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, len));
+    let request = CompileRequest::decode(&input[..]).map_err(|e| format!("failed to decode request: {}", e));
+    drop(input);
+    let response = request.and_then(handler).unwrap_or_else(error_response).encode_to_vec();
+    RESPONSE.with(|latest| {
+        let mut latest = latest.borrow_mut();
+        *latest = response;
+        (latest.as_ptr(), latest.len())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GeneratedFile, Severity};
+
+    /// Pass `input` through `alloc` and `compile` the way the host does.
+    fn call(input: &[u8], handler: impl FnOnce(CompileRequest) -> Result<CompileResponse, String>) -> CompileResponse {
+        let ptr = alloc(input.len());
+        let output = unsafe {
+            std::ptr::copy_nonoverlapping(input.as_ptr(), ptr, input.len());
+            let (ptr, len) = compile(ptr, input.len(), handler);
+            std::slice::from_raw_parts(ptr, len).to_vec()
+        };
+        CompileResponse::decode(output.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn round_trip_through_alloc_and_compile() {
+        let request = CompileRequest { output_dir: "gen".to_string(), ..Default::default() };
+        let response = call(&request.encode_to_vec(), |req| {
+            Ok(CompileResponse {
+                files: vec![GeneratedFile { name: format!("{}/a.txt", req.output_dir), content: b"a".to_vec(), append: false }],
+                errors: Vec::new(),
+            })
+        });
+        assert_eq!(response.files[0].name, "gen/a.txt");
+        assert!(response.errors.is_empty());
+    }
+
+    #[test]
+    fn handler_error_is_an_error_response() {
+        let response = call(&CompileRequest::default().encode_to_vec(), |_| Err("no types".to_string()));
+        assert!(response.files.is_empty());
+        assert_eq!(response.errors[0].message, "no types");
+        assert_eq!(response.errors[0].severity, Severity::Error as i32);
+    }
+
+    #[test]
+    fn bad_request_is_an_error_response() {
+        let response = call(&[0xff, 0xff, 0xff], |_| unreachable!("handler called on a bad request"));
+        assert!(response.errors[0].message.starts_with("failed to decode request"), "{}", response.errors[0].message);
+    }
+}